@@ -1,6 +1,6 @@
 import test from 'ava'
 
-import { tachyon } from '../index'
+import { ConflictPolicy, Dotfiles, TachyonResponse, router, tachyon } from '../index'
 
 test('list routes', (t) => {
   const server = tachyon()
@@ -65,3 +65,235 @@ test('create multiple routes', (t) => {
   t.true(routes.includes('/users POST'))
   t.true(routes.includes('/users/1 PUT'))
 })
+
+test('create route with middleware chain', (t) => {
+  const server = tachyon()
+  const requireAuth = (_req, res) => {
+    res.status(401).send({ error: 'Unauthorized' })
+  }
+  server.get('/admin', [requireAuth, (_req, res) => res.send('Admin')])
+  t.deepEqual(server.routes(), ['/admin GET'])
+})
+
+test('run middleware passed as rest arguments', async (t) => {
+  const server = tachyon()
+  const calls: string[] = []
+  const requireAuth = (req, res) => {
+    calls.push('requireAuth')
+    if (req.params.user !== 'root') res.status(401).send({ error: 'Unauthorized' })
+  }
+  const rateLimit = (_req, _res) => {
+    calls.push('rateLimit')
+  }
+  server.get(
+    '/admin/:user',
+    requireAuth,
+    rateLimit,
+    (_req, res) => {
+      calls.push('handler')
+      res.send('Welcome!')
+    },
+    { name: 'admin' },
+  )
+  t.is(server.describeRoutes()[0].middleware, 2)
+  t.is(server.url('admin', { user: 'root' }), '/admin/root')
+
+  server.listen(38926)
+  const get = (path: string) => fetch(`http://127.0.0.1:38926${path}`)
+  // Requests are served once warmup has called every handler
+  for (let attempt = 0; ; attempt++) {
+    try {
+      await get('/ready')
+      break
+    } catch (error) {
+      if (attempt === 50) throw error
+      await new Promise((resolve) => setTimeout(resolve, 20))
+    }
+  }
+
+  calls.length = 0
+  const denied = await get('/admin/guest')
+  t.is(denied.status, 401)
+  t.deepEqual(await denied.json(), { error: 'Unauthorized' })
+  t.deepEqual(calls, ['requireAuth'])
+
+  calls.length = 0
+  const allowed = await get('/admin/root')
+  t.is(allowed.status, 200)
+  t.is(await allowed.json(), 'Welcome!')
+  t.deepEqual(calls, ['requireAuth', 'rateLimit', 'handler'])
+})
+
+test('reject route without handlers', (t) => {
+  const server = tachyon()
+  t.throws(() => server.get('/empty', []))
+  t.throws(() => server.get('/options', { name: 'options' }), {
+    message: 'Route GET /options needs at least one handler',
+  })
+  t.throws(() => server.get('/first', { name: 'first' }, (_req, res) => res.send('')), {
+    message: 'Route options must be the last argument',
+  })
+})
+
+test('mount router under prefix', (t) => {
+  const server = tachyon()
+  const users = router()
+  users.use((_req, _res) => {})
+  users.get('/', (_req, res) => res.send('List users'))
+  users.post('/:id', (_req, res) => res.send('Update user'))
+  server.mount('/api/v1/users', users)
+  const routes = server.routes()
+  t.is(routes.length, 2)
+  t.true(routes.includes('/api/v1/users GET'))
+  t.true(routes.includes('/api/v1/users/:id POST'))
+})
+
+test('reject conflicting routes when configured', (t) => {
+  const server = tachyon({ routeConflicts: ConflictPolicy.Throw })
+  server.get('/users/:id', (_req, res) => res.send('User'))
+  server.get('/users/me', (_req, res) => res.send('Me'))
+  const error = t.throws(() => server.get('/users/:userId', (_req, res) => res.send('User')))
+  t.is(error?.message, 'Route GET /users/:userId conflicts with GET /users/:id')
+  t.throws(() => server.get('/users/me', (_req, res) => res.send('Me')))
+})
+
+test('describe routes in a stable order', (t) => {
+  const server = tachyon()
+  const requireAuth = (_req, _res) => {}
+  server.post('/users', (_req, res) => res.send('Create user'))
+  server.get('/users/:id', [requireAuth, (_req, res) => res.send('User')], { meta: { tags: ['users'] } })
+  server.get('/users', (_req, res) => res.send('List users'))
+  t.deepEqual(server.routes(), ['/users GET', '/users POST', '/users/:id GET'])
+  t.deepEqual(server.describeRoutes()[2], {
+    method: 'GET',
+    path: '/users/:id',
+    params: ['id'],
+    middleware: 1,
+    meta: { tags: ['users'] },
+  })
+  t.true(server.printRoutes().startsWith('METHOD  PATH'))
+})
+
+test('replace and remove routes', (t) => {
+  const server = tachyon()
+  server.get('/beta', (_req, res) => res.send('Beta'))
+  server.replaceRoute('GET', '/beta', (_req, res) => res.send('Beta v2'))
+  t.throws(() => server.replaceRoute('GET', '/missing', (_req, res) => res.send('Missing')))
+  t.true(server.removeRoute('get', '/beta'))
+  t.false(server.removeRoute('GET', '/beta'))
+  t.deepEqual(server.routes(), [])
+})
+
+test('build urls of named routes', (t) => {
+  const server = tachyon()
+  server.get('/users/:id/files/*path', (_req, res) => res.send('File'), { name: 'user.file' })
+  t.is(
+    server.url('user.file', { id: 5, path: 'docs/my report.pdf' }, { query: { download: true } }),
+    '/users/5/files/docs/my%20report.pdf?download=true',
+  )
+  t.is(
+    server.url('user.file', { id: 5, path: 'a.txt' }, { query: { zoom: 2, page: 1, all: [true, false] } }),
+    '/users/5/files/a.txt?zoom=2&page=1&all=true&all=false',
+  )
+  t.throws(() => server.url('user.file', { id: 5 }))
+  t.throws(() => server.url('missing'))
+})
+
+test('serve routers per host', (t) => {
+  const server = tachyon()
+  const tenants = router()
+  tenants.get('/', (req, res) => res.send(req.params.tenant))
+  server.host(':tenant.example.com', tenants)
+  t.throws(() => server.host('bad host', tenants))
+  t.deepEqual(server.describeRoutes()[0], {
+    method: 'GET',
+    host: ':tenant.example.com',
+    path: '/',
+    params: [],
+    middleware: 0,
+  })
+})
+
+test('register error handlers', (t) => {
+  const server = tachyon({ bodyLimit: 1024 })
+  server.notFound((_req, res) => res.status(404).send({ error: { code: 'NOT_FOUND' } }))
+  server.onError((err, _req, res) => res.status(err.status).send({ error: { code: err.code } }))
+  t.throws(() => server.notFound([]))
+  t.deepEqual(server.routes(), [])
+})
+
+test('add lifecycle hooks', (t) => {
+  const server = tachyon()
+  const admin = router()
+  server.get('/users/:id', (_req, res) => res.send('User'))
+  server.addHook('onSend', (_req, res) => {
+    res.header('x-powered-by', 'tachyon')
+  })
+  server.addRouteHook('GET', '/users/:id', 'preHandler', (_req, _res) => {})
+  admin.addHook('onRequest', (_req, res) => res.status(401).send('Unauthorized'))
+  t.throws(() => server.addRouteHook('GET', '/missing', 'onSend', (_req, _res) => {}))
+  t.deepEqual(server.routes(), ['/users/:id GET'])
+})
+
+test('share locals between request and response', (t) => {
+  const res = new TachyonResponse()
+  res.locals.user = 'ann'
+  t.is(res.locals.user, 'ann')
+  t.is(res.locals, res.locals)
+})
+
+test('expose request cancellation types', (t) => {
+  const app = tachyon()
+  t.notThrows(() => {
+    app.get('/report', async (req, res) => {
+      const upstream = await fetch('http://127.0.0.1:1/report', { signal: req.signal })
+      res.send(await upstream.json())
+    })
+  })
+})
+
+test('stream response bodies', async (t) => {
+  const res = new TachyonResponse()
+  t.true(await res.write('id,name\n'))
+  t.true(await res.write(new Uint8Array([49, 44, 97, 10])))
+  res.end()
+  t.false(await res.write('late'))
+  await t.notThrowsAsync(new TachyonResponse().stream(['a', 'b']))
+})
+
+test('open server-sent event streams', (t) => {
+  const events = new TachyonResponse().sse({ keepAlive: 0 })
+  t.true(events.send({ event: 'update', id: '1', data: { count: 1 } }))
+  t.false(events.closed)
+  events.close()
+  t.true(events.closed)
+  t.false(events.send({ data: 'late' }))
+})
+
+test('register websocket routes', (t) => {
+  const app = tachyon()
+  app.ws(
+    '/chat/:room',
+    {
+      message(ws, data) {
+        ws.send(data)
+      },
+    },
+    { maxMessageSize: 64 * 1024, pingInterval: 0 },
+  )
+  t.deepEqual(app.routes(), ['/chat/:room GET'])
+  t.is(app.publish('room:lobby', 'nobody listens'), 0)
+})
+
+test('serve static files', (t) => {
+  const app = tachyon()
+  app.static('/assets', process.cwd(), { dotfiles: Dotfiles.Deny, maxAge: 3600 })
+  t.deepEqual(app.routes(), ['/assets GET', '/assets/* GET'])
+  const error = t.throws(() => app.static('/missing', `${process.cwd()}/missing`))
+  t.regex(error?.message ?? '', /is not a directory/)
+})
+
+test('send missing files', async (t) => {
+  await t.notThrowsAsync(new TachyonResponse().sendFile('missing.txt', { root: process.cwd() }))
+  await t.notThrowsAsync(new TachyonResponse().download('missing.txt', 'report.txt'))
+})
@@ -1,11 +1,252 @@
 /* auto-generated by NAPI-RS */
 /* eslint-disable */
+/**
+ * Server-Sent Events stream returned by `res.sse()`
+ *
+ * The stream ends with `close`, or when the client disconnects, which
+ * aborts `req.signal` as well.
+ */
+export declare class EventStream {
+  /**
+   * Send an event, false once the stream is closed
+   *
+   * Example usage:
+   * ```javascript
+   * const events = res.sse()
+   * events.send({ event: 'price', id: '42', data: { symbol: 'ACME', price: 12.5 } })
+   * ```
+   */
+  send(event: SseEvent): boolean
+  /** End the stream, the browser reconnects unless told otherwise */
+  close(): void
+  /** Whether the stream was closed or the client went away */
+  get closed(): boolean
+}
+
+/** Standalone group of routes that can be mounted on an app under a prefix */
+export declare class Router {
+  constructor()
+  /**
+   * Add middleware that runs before every route of this router
+   *
+   * Only routes mounted after this call pick the middleware up.
+   *
+   * Example usage:
+   * ```javascript
+   * const admin = router()
+   * admin.use(requireAuth)
+   * admin.use([audit, rateLimit])
+   * ```
+   */
+  use(
+    callbacks:
+      | ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>)
+      | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>>,
+  ): void
+  /**
+   * Add a lifecycle hook to every route of this router
+   *
+   * Only routes mounted after this call pick the hook up, and the app hooks
+   * run first.
+   *
+   * Example usage:
+   * ```javascript
+   * const api = router()
+   * api.addHook('onSend', (req, res) => {
+   *   res.header('cache-control', 'no-store')
+   * })
+   * ```
+   */
+  addHook(
+    name: HookName,
+    callbacks:
+      | ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>)
+      | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>>,
+  ): void
+  /**
+   * Add a GET route handler to this router
+   *
+   * Example usage:
+   * ```javascript
+   * users.get('/:id', (req, res) => {
+   *   res.send('User!')
+   * })
+   * ```
+   */
+  get(
+    route: string,
+    arg1:
+      | ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>)
+      | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>>
+      | RouteOptions,
+    arg2?:
+      | ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>)
+      | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>>
+      | RouteOptions
+      | undefined
+      | null,
+    arg3?:
+      | ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>)
+      | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>>
+      | RouteOptions
+      | undefined
+      | null,
+    arg4?:
+      | ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>)
+      | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>>
+      | RouteOptions
+      | undefined
+      | null,
+    arg5?:
+      | ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>)
+      | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>>
+      | RouteOptions
+      | undefined
+      | null
+  ): void
+  /** Add a POST route handler to this router */
+  post(
+    route: string,
+    arg1:
+      | ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>)
+      | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>>
+      | RouteOptions,
+    arg2?:
+      | ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>)
+      | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>>
+      | RouteOptions
+      | undefined
+      | null,
+    arg3?:
+      | ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>)
+      | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>>
+      | RouteOptions
+      | undefined
+      | null,
+    arg4?:
+      | ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>)
+      | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>>
+      | RouteOptions
+      | undefined
+      | null,
+    arg5?:
+      | ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>)
+      | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>>
+      | RouteOptions
+      | undefined
+      | null
+  ): void
+  /** Add a PUT route handler to this router */
+  put(
+    route: string,
+    arg1:
+      | ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>)
+      | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>>
+      | RouteOptions,
+    arg2?:
+      | ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>)
+      | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>>
+      | RouteOptions
+      | undefined
+      | null,
+    arg3?:
+      | ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>)
+      | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>>
+      | RouteOptions
+      | undefined
+      | null,
+    arg4?:
+      | ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>)
+      | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>>
+      | RouteOptions
+      | undefined
+      | null,
+    arg5?:
+      | ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>)
+      | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>>
+      | RouteOptions
+      | undefined
+      | null
+  ): void
+  /** Add a DELETE route handler to this router */
+  delete(
+    route: string,
+    arg1:
+      | ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>)
+      | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>>
+      | RouteOptions,
+    arg2?:
+      | ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>)
+      | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>>
+      | RouteOptions
+      | undefined
+      | null,
+    arg3?:
+      | ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>)
+      | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>>
+      | RouteOptions
+      | undefined
+      | null,
+    arg4?:
+      | ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>)
+      | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>>
+      | RouteOptions
+      | undefined
+      | null,
+    arg5?:
+      | ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>)
+      | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>>
+      | RouteOptions
+      | undefined
+      | null
+  ): void
+  /** Add a PATCH route handler to this router */
+  patch(
+    route: string,
+    arg1:
+      | ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>)
+      | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>>
+      | RouteOptions,
+    arg2?:
+      | ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>)
+      | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>>
+      | RouteOptions
+      | undefined
+      | null,
+    arg3?:
+      | ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>)
+      | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>>
+      | RouteOptions
+      | undefined
+      | null,
+    arg4?:
+      | ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>)
+      | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>>
+      | RouteOptions
+      | undefined
+      | null,
+    arg5?:
+      | ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>)
+      | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>>
+      | RouteOptions
+      | undefined
+      | null
+  ): void
+  /** Nest another router under a path prefix of this one */
+  mount(prefix: string, router: Router): void
+  routes(): Array<string>
+  /** Every route with its params, middleware count and metadata, sorted by path */
+  describeRoutes(): Array<RouteInfo>
+}
+
 export declare class Tachyon {
   constructor()
   /**
    * Add a GET route handler with Express-like syntax
    * Supports both sync and async handlers
    *
+   * Up to five handlers, handler arrays or, last, route options follow the path
+   *
    * Example usage:
    * ```javascript
    * app.get('/', (req, res) => {
@@ -15,13 +256,43 @@ export declare class Tachyon {
    * app.get('/async', async (req, res) => {
    *   res.send('Async Hello!')
    * })
+   *
+   * // Middleware runs in order until one of them sends the response
+   * app.get('/admin', requireAuth, rateLimit, (req, res) => {
+   *   res.send('Welcome!')
+   * })
    * ```
    */
   get(
     route: string,
-    callback:
-      | ((req: TachyonRequest, res: TachyonResponse) => void)
-      | ((req: TachyonRequest, res: TachyonResponse) => Promise<void>),
+    arg1:
+      | ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>)
+      | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>>
+      | RouteOptions,
+    arg2?:
+      | ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>)
+      | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>>
+      | RouteOptions
+      | undefined
+      | null,
+    arg3?:
+      | ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>)
+      | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>>
+      | RouteOptions
+      | undefined
+      | null,
+    arg4?:
+      | ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>)
+      | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>>
+      | RouteOptions
+      | undefined
+      | null,
+    arg5?:
+      | ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>)
+      | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>>
+      | RouteOptions
+      | undefined
+      | null
   ): void
   /**
    * Add a POST route handler with Express-like syntax
@@ -41,9 +312,34 @@ export declare class Tachyon {
    */
   post(
     route: string,
-    callback:
-      | ((req: TachyonRequest, res: TachyonResponse) => void)
-      | ((req: TachyonRequest, res: TachyonResponse) => Promise<void>),
+    arg1:
+      | ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>)
+      | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>>
+      | RouteOptions,
+    arg2?:
+      | ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>)
+      | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>>
+      | RouteOptions
+      | undefined
+      | null,
+    arg3?:
+      | ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>)
+      | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>>
+      | RouteOptions
+      | undefined
+      | null,
+    arg4?:
+      | ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>)
+      | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>>
+      | RouteOptions
+      | undefined
+      | null,
+    arg5?:
+      | ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>)
+      | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>>
+      | RouteOptions
+      | undefined
+      | null
   ): void
   /**
    * Add a PUT route handler with Express-like syntax
@@ -58,9 +354,34 @@ export declare class Tachyon {
    */
   put(
     route: string,
-    callback:
-      | ((req: TachyonRequest, res: TachyonResponse) => void)
-      | ((req: TachyonRequest, res: TachyonResponse) => Promise<void>),
+    arg1:
+      | ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>)
+      | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>>
+      | RouteOptions,
+    arg2?:
+      | ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>)
+      | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>>
+      | RouteOptions
+      | undefined
+      | null,
+    arg3?:
+      | ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>)
+      | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>>
+      | RouteOptions
+      | undefined
+      | null,
+    arg4?:
+      | ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>)
+      | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>>
+      | RouteOptions
+      | undefined
+      | null,
+    arg5?:
+      | ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>)
+      | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>>
+      | RouteOptions
+      | undefined
+      | null
   ): void
   /**
    * Add a DELETE route handler with Express-like syntax
@@ -75,9 +396,34 @@ export declare class Tachyon {
    */
   delete(
     route: string,
-    callback:
-      | ((req: TachyonRequest, res: TachyonResponse) => void)
-      | ((req: TachyonRequest, res: TachyonResponse) => Promise<void>),
+    arg1:
+      | ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>)
+      | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>>
+      | RouteOptions,
+    arg2?:
+      | ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>)
+      | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>>
+      | RouteOptions
+      | undefined
+      | null,
+    arg3?:
+      | ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>)
+      | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>>
+      | RouteOptions
+      | undefined
+      | null,
+    arg4?:
+      | ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>)
+      | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>>
+      | RouteOptions
+      | undefined
+      | null,
+    arg5?:
+      | ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>)
+      | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>>
+      | RouteOptions
+      | undefined
+      | null
   ): void
   /**
    * Add a PATCH route handler with Express-like syntax
@@ -92,11 +438,244 @@ export declare class Tachyon {
    */
   patch(
     route: string,
-    callback:
-      | ((req: TachyonRequest, res: TachyonResponse) => void)
-      | ((req: TachyonRequest, res: TachyonResponse) => Promise<void>),
+    arg1:
+      | ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>)
+      | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>>
+      | RouteOptions,
+    arg2?:
+      | ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>)
+      | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>>
+      | RouteOptions
+      | undefined
+      | null,
+    arg3?:
+      | ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>)
+      | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>>
+      | RouteOptions
+      | undefined
+      | null,
+    arg4?:
+      | ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>)
+      | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>>
+      | RouteOptions
+      | undefined
+      | null,
+    arg5?:
+      | ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>)
+      | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>>
+      | RouteOptions
+      | undefined
+      | null
   ): void
   routes(): Array<string>
+  /**
+   * Every route with its params, middleware count and metadata, sorted by path
+   *
+   * Routes of virtual hosts come last, grouped by host.
+   */
+  describeRoutes(): Array<RouteInfo>
+  /**
+   * Route table formatted for startup logs
+   *
+   * Example usage:
+   * ```javascript
+   * console.log(app.printRoutes())
+   * // METHOD  PATH        PARAMS  MIDDLEWARE
+   * // GET     /           -       0
+   * // GET     /users/:id  id      1
+   * ```
+   */
+  printRoutes(): string
+  /**
+   * Build the path of a named route, params and query values being percent-encoded
+   *
+   * Example usage:
+   * ```javascript
+   * app.get('/users/:id', showUser, { name: 'user.show' })
+   *
+   * app.url('user.show', { id: 5 }, { query: { tab: 'posts' } }) // '/users/5?tab=posts'
+   * ```
+   */
+  url(name: string, params?: Record<string, any> | undefined | null, options?: UrlOptions | undefined | null): string
+  /**
+   * Swap the handlers of a registered route without restarting the server
+   *
+   * Requests already running finish on the previous handlers. The middleware
+   * of a mounted router keeps running before the new ones.
+   *
+   * Example usage:
+   * ```javascript
+   * app.replaceRoute('GET', '/feature', (req, res) => {
+   *   res.send('New implementation')
+   * })
+   * ```
+   */
+  replaceRoute(
+    method: string,
+    route: string,
+    callbacks:
+      | ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>)
+      | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>>,
+    options?: RouteOptions | undefined | null,
+  ): void
+  /**
+   * Unregister a route, returns `false` when it was not registered
+   *
+   * Example usage:
+   * ```javascript
+   * if (!flags.betaSearch) {
+   *   app.removeRoute('GET', '/search')
+   * }
+   * ```
+   */
+  removeRoute(method: string, route: string): boolean
+  /**
+   * Serve the routes of a `Router` to requests for matching hosts only
+   *
+   * Patterns are exact (`api.example.com`), wildcard (`*.example.com`) or
+   * capture a label (`:tenant.example.com`) into `req.params`.
+   *
+   * Example usage:
+   * ```javascript
+   * const tenants = router()
+   * tenants.get('/', (req, res) => {
+   *   res.send(`Hello ${req.params.tenant}`)
+   * })
+   *
+   * app.host(':tenant.example.com', tenants)
+   * ```
+   */
+  host(host: string, router: Router): void
+  /**
+   * Answer requests matching no route, instead of the plain `Not Found` text
+   *
+   * Example usage:
+   * ```javascript
+   * app.notFound((req, res) => {
+   *   res.status(404).send({ error: { code: 'NOT_FOUND', message: 'No such page' } })
+   * })
+   * ```
+   */
+  notFound(
+    callbacks:
+      | ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>)
+      | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>>,
+  ): void
+  /**
+   * Render the errors raised while serving a request
+   *
+   * Called for unknown routes without a `notFound` handler, unsupported
+   * methods, invalid or too large bodies and handlers that throw or time out.
+   * The default plain text response is sent when the handler sends nothing.
+   *
+   * Example usage:
+   * ```javascript
+   * app.onError((err, req, res) => {
+   *   res.status(err.status).send({ error: { code: err.code, message: err.message } })
+   * })
+   * ```
+   */
+  onError(callback: (err: HttpError, req: TachyonRequest, res: TachyonResponse) => void | Promise<void>): void
+  /**
+   * Add a lifecycle hook run for every request
+   *
+   * `onRequest` runs once the route matched and `preHandler` once the body is
+   * parsed, both stop the request when they send a response. `onSend` can
+   * still change the payload and headers, and `onResponse` runs once the
+   * response is written.
+   *
+   * Example usage:
+   * ```javascript
+   * app.addHook('onSend', (req, res) => {
+   *   res.header('x-powered-by', 'tachyon')
+   * })
+   *
+   * app.addHook('onResponse', (req, res) => {
+   *   console.log(res.statusCode, `${res.elapsedTime.toFixed(1)}ms`)
+   * })
+   * ```
+   */
+  addHook(
+    name: HookName,
+    callbacks:
+      | ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>)
+      | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>>,
+  ): void
+  /**
+   * Add a lifecycle hook to a single route, run after the global ones
+   *
+   * Example usage:
+   * ```javascript
+   * app.addRouteHook('GET', '/reports/:id', 'preHandler', requireAdmin)
+   * ```
+   */
+  addRouteHook(
+    method: string,
+    route: string,
+    name: HookName,
+    callbacks:
+      | ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>)
+      | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>>,
+  ): void
+  /**
+   * Mount every route of a `Router` under a path prefix
+   *
+   * Example usage:
+   * ```javascript
+   * const users = router()
+   * users.use(requireAuth)
+   * users.get('/:id', (req, res) => {
+   *   res.send('User!')
+   * })
+   *
+   * app.mount('/api/v1/users', users)
+   * ```
+   */
+  mount(prefix: string, router: Router): void
+  /**
+   * Accept WebSocket connections on a path, other requests get 426
+   *
+   * Callbacks of a connection run in order. `ws` is the same object for all
+   * of them and exposes the params, query and locals of the upgrade request.
+   *
+   * Example usage:
+   * ```javascript
+   * app.ws('/chat/:room', {
+   *   open(ws) {
+   *     ws.send(`Welcome to ${ws.params.room}`)
+   *   },
+   *   message(ws, data) {
+   *     ws.send(data)
+   *   },
+   *   close(ws, code, reason) {
+   *     console.log('left', code, reason)
+   *   },
+   * })
+   * ```
+   */
+  ws(route: string, callbacks: WebSocketCallbacks, options?: WebSocketOptions | undefined | null): void
+  /**
+   * Serve the files of a directory under a path prefix, without calling into JS
+   *
+   * Directories answer with their `index.html`, files get an `ETag`,
+   * `Last-Modified` and `Cache-Control`, and paths resolving outside of the
+   * directory are answered with 404.
+   *
+   * Example usage:
+   * ```javascript
+   * app.static('/assets', './public', { maxAge: 31536000, immutable: true })
+   * ```
+   */
+  static(prefix: string, root: string, options?: StaticOptions | undefined | null): void
+  /**
+   * Send a message to every WebSocket subscribed to a topic, returning how many got it
+   *
+   * Example usage:
+   * ```javascript
+   * app.publish('prices', JSON.stringify({ symbol: 'ACME', price: 12.5 }))
+   * ```
+   */
+  publish(topic: string, data: string | Uint8Array): number
   listen(port: number): Promise<void>
 }
 
@@ -104,10 +683,311 @@ export declare class TachyonResponse {
   constructor()
   send(msg?: any | undefined | null): void
   status(code: number): TachyonResponse
+  /** Status code of the response, 200 unless set with `status` */
+  get statusCode(): number
+  /** Value sent so far, e.g. to transform it in an `onSend` hook */
+  get payload(): any | null
+  /** Milliseconds since the request was received */
+  get elapsedTime(): number
+  /**
+   * Answer with a Server-Sent Events stream
+   *
+   * Example usage:
+   * ```javascript
+   * app.get('/prices', (req, res) => {
+   *   const events = res.sse()
+   *   const unsubscribe = prices.subscribe((price) => events.send({ event: 'price', data: price }))
+   *   req.signal.addEventListener('abort', unsubscribe)
+   * })
+   * ```
+   */
+  sse(options?: SseOptions | undefined | null): EventStream
+  /**
+   * Set a response header, replacing any previous value
+   *
+   * Example usage:
+   * ```javascript
+   * res.header('cache-control', 'no-store').send({ ok: true })
+   * ```
+   */
+  header(name: string, value: string): TachyonResponse
+  /**
+   * Write a chunk of a streamed body, resolves once the client is ready for more
+   *
+   * Example usage:
+   * ```javascript
+   * res.header('content-type', 'text/csv')
+   * for await (const row of db.rows()) {
+   *   if (!(await res.write(`${row.id},${row.name}\n`))) break
+   * }
+   * res.end()
+   * ```
+   */
+  write(chunk: string | Uint8Array): Promise<boolean>
+  /** Finish a streamed body, optionally with a last chunk */
+  end(chunk?: string | Uint8Array): void
+  /**
+   * Stream an async iterable or a Node `Readable`, ending the response after it
+   *
+   * The source stops being read once the client goes away.
+   *
+   * Example usage:
+   * ```javascript
+   * app.get('/export', (req, res) => res.stream(fs.createReadStream('export.csv')))
+   * ```
+   */
+  stream(source: AsyncIterable<string | Uint8Array> | Iterable<string | Uint8Array>): Promise<void>
+  /**
+   * Stream a file from disk, answering 404 when it is missing
+   *
+   * Example usage:
+   * ```javascript
+   * app.get('/reports/:id', (req, res) => {
+   *   res.sendFile(`${req.params.id}.pdf`, { root: './reports', maxAge: 3600 })
+   * })
+   * ```
+   */
+  sendFile(path: string, options?: SendFileOptions | undefined | null): Promise<void>
+  /**
+   * Send a file as an attachment, saved as `filename` or under its own name
+   *
+   * Example usage:
+   * ```javascript
+   * app.get('/invoices/:id/pdf', (req, res) => {
+   *   res.download(`./invoices/${req.params.id}.pdf`, `invoice-${req.params.id}.pdf`)
+   * })
+   * ```
+   */
+  download(
+    path: string,
+    filename?: string | undefined | null,
+    options?: SendFileOptions | undefined | null,
+  ): Promise<void>
+  getHeader(name: string): string | null
+  removeHeader(name: string): void
+  /** Same object as `req.locals`, e.g. for values rendered in templates */
+  get locals(): Record<string, any>
+}
+
+/** Open connection of a WebSocket route */
+export declare class WebSocket {
+  /**
+   * Send a text or binary message, false once the connection is closing
+   *
+   * Example usage:
+   * ```javascript
+   * ws.send(JSON.stringify({ type: 'joined', room: ws.params.room }))
+   * ```
+   */
+  send(data: string | Uint8Array): boolean
+  /**
+   * Receive the messages sent with `app.publish` to a topic
+   *
+   * Example usage:
+   * ```javascript
+   * app.ws('/rooms/:room', {
+   *   open(ws) {
+   *     ws.subscribe(`room:${ws.params.room}`)
+   *   },
+   *   message(ws, data) {
+   *     app.publish(`room:${ws.params.room}`, data)
+   *   },
+   * })
+   * ```
+   */
+  subscribe(topic: string): void
+  /** Stop receiving the messages of a topic, false when not subscribed */
+  unsubscribe(topic: string): boolean
+  isSubscribed(topic: string): boolean
+  /** Topics the connection is subscribed to */
+  get topics(): Array<string>
+  /** Close the connection, with 1000 unless another code is given */
+  close(code?: number | undefined | null, reason?: string | undefined | null): void
+  get params(): Record<string, string>
+  get query(): Record<string, string>
+  /** Same object as `req.locals` of the upgrade request */
+  get locals(): Record<string, any>
+  /** Whether `close` was called or the connection is gone */
+  get closed(): boolean
+}
+
+/** Stage of the request pipeline a hook runs at */
+export declare const enum HookName {
+  /** Route matched, before the body is read */
+  OnRequest = 'onRequest',
+  /** Body parsed, before the route handlers */
+  PreHandler = 'preHandler',
+  /** Response ready, payload and headers can still be changed */
+  OnSend = 'onSend',
+  /** Response written to the client, `res.elapsedTime` gives the timing */
+  OnResponse = 'onResponse'
+}
+
+/** How static files treat paths with a segment starting with a dot */
+export declare const enum Dotfiles {
+  /** Serve them like any other file */
+  Allow = 'allow',
+  /** Answer with 403 */
+  Deny = 'deny',
+  /** Answer with 404, as if they did not exist */
+  Ignore = 'ignore'
+}
+
+/** What to do when a new route duplicates or shadows an existing one */
+export declare const enum ConflictPolicy {
+  /** Fail the registration */
+  Throw = 'throw',
+  /** Print a warning and register the route anyway */
+  Warn = 'warn'
 }
 
-export declare function tachyon(): Tachyon
+/**
+ * Error produced while serving a request, rendered by `onError`
+ *
+ * Covers what the framework detects itself (unknown route, bad payload...)
+ * as well as handlers that throw, reject or time out.
+ */
+export interface HttpError {
+  status: number
+  /** Stable identifier such as `NOT_FOUND` or `HANDLER_ERROR` */
+  code: string
+  message: string
+}
+
+/** Structured description of a registered route */
+export interface RouteInfo {
+  method: string
+  /** Host pattern of the virtual host serving the route, if any */
+  host?: string
+  path: string
+  /** Names of the `:param` and catch-all segments, in order */
+  params: Array<string>
+  /** Handlers running before the route handler, router middleware included */
+  middleware: number
+  meta?: any
+}
+
+/** Settings of a single route, passed after its handlers */
+export interface RouteOptions {
+  /** Unique name used to build links with `url` */
+  name?: string
+  /** Free-form data listed by `describeRoutes`, e.g. a description or tags */
+  meta?: any
+}
+
+/** Settings of a Server-Sent Events stream opened with `res.sse()` */
+export interface SseOptions {
+  /** Milliseconds without events before a keep-alive comment is sent, 15000 by default, 0 to disable */
+  keepAlive?: number
+}
+
+/** One message of a Server-Sent Events stream */
+export interface SseEvent {
+  /** Event type, the browser dispatches `message` when missing */
+  event?: string
+  /** Sent back by the browser as `Last-Event-ID` when it reconnects */
+  id?: string
+  /** Strings are sent as is, other values as JSON */
+  data?: any
+  /** Milliseconds the browser waits before reconnecting */
+  retry?: number
+}
+
+/** Settings of the files served by `app.static` */
+export interface StaticOptions {
+  /** Serve `index.html` for directories, `true` by default */
+  index?: boolean
+  /** Reaction to paths such as `/.env` or `/.git/config`, `"ignore"` by default */
+  dotfiles?: Dotfiles
+  /** Seconds browsers may reuse a file without checking, 0 by default */
+  maxAge?: number
+  /** Add `immutable` to `Cache-Control`, for fingerprinted file names */
+  immutable?: boolean
+  /** Send an `ETag` and answer `If-None-Match` with 304, `true` by default */
+  etag?: boolean
+  /** Send `Last-Modified` and answer `If-Modified-Since` with 304, `true` by default */
+  lastModified?: boolean
+}
+
+/** Settings of a file sent with `res.sendFile` or `res.download` */
+export interface SendFileOptions {
+  /** Directory relative paths are resolved in, they may not leave it */
+  root?: string
+  /** Seconds browsers may reuse the file without checking, 0 by default */
+  maxAge?: number
+  /** Add `immutable` to `Cache-Control` */
+  immutable?: boolean
+  /** Send an `ETag` and answer `If-None-Match` with 304, `true` by default */
+  etag?: boolean
+  /** Send `Last-Modified` and answer `If-Modified-Since` with 304, `true` by default */
+  lastModified?: boolean
+}
+
+/** Limits of the connections accepted by `app.ws` */
+export interface WebSocketOptions {
+  /** Largest message in bytes, 1 MiB by default. Bigger ones close the connection with 1009 */
+  maxMessageSize?: number
+  /** Milliseconds between pings, 30000 by default, 0 to disable. Clients missing one are dropped */
+  pingInterval?: number
+}
+
+/** Callbacks of a WebSocket route as received from `app.ws(...)` */
+export interface WebSocketCallbacks {
+  open?: (ws: WebSocket) => void | Promise<void>
+  message?: (ws: WebSocket, data: string | Buffer) => void | Promise<void>
+  close?: (ws: WebSocket, code: number, reason: string) => void | Promise<void>
+}
+
+export declare function router(): Router
+
+export declare function tachyon(options?: TachyonOptions | undefined | null): Tachyon
+
+/** Extra parts of a link built by `url` */
+export interface UrlOptions {
+  /** Appended as a percent-encoded query string, arrays repeat the key */
+  query?: Record<string, any>
+}
+
+/** Settings passed to `tachyon()` when creating an app */
+export interface TachyonOptions {
+  /**
+   * Reaction to duplicate or ambiguous routes. By default a route registered
+   * twice throws and an overlapping pattern prints a warning
+   */
+  routeConflicts?: ConflictPolicy
+  /** Collapse `//` into a single `/` before routing, `true` by default */
+  mergeSlashes?: boolean
+  /** Accept `%2F` inside parameter values, `true` by default */
+  allowEncodedSlashes?: boolean
+  /** Treat `/users` and `/users/` as different routes, `true` by default */
+  strictTrailingSlash?: boolean
+  /**
+   * Redirect to the registered form of a path that only differs by its
+   * trailing slash, 301 for GET and 308 otherwise. `false` by default
+   */
+  redirectTrailingSlash?: boolean
+  /** Match static segments with the same case only, `true` by default */
+  caseSensitive?: boolean
+  /**
+   * Largest accepted request body in bytes, bigger bodies are answered
+   * with 413. No limit by default
+   */
+  bodyLimit?: number
+  /**
+   * Milliseconds the handlers of a route may take before the request is
+   * answered with 503 and `req.signal` aborts. No limit by default
+   */
+  handlerTimeout?: number
+}
 
 export interface TachyonRequest {
   body: any
+  /** Values captured by `:param` and catch-all segments, `*` for an anonymous catch-all */
+  params: Record<string, string>
+  /** Values shared by every middleware, hook and handler of the request */
+  locals: Record<string, any>
+  /** Aborted when the client disconnects or a handler times out */
+  signal: AbortSignal
+  /** `Last-Event-ID` header of a reconnecting `EventSource` */
+  lastEventId?: string
 }
@@ -557,6 +557,11 @@ if (!nativeBinding) {
 }
 
 module.exports = nativeBinding
+module.exports.Router = nativeBinding.Router
 module.exports.Tachyon = nativeBinding.Tachyon
 module.exports.TachyonResponse = nativeBinding.TachyonResponse
+module.exports.ConflictPolicy = nativeBinding.ConflictPolicy
+module.exports.Dotfiles = nativeBinding.Dotfiles
+module.exports.HookName = nativeBinding.HookName
+module.exports.router = nativeBinding.router
 module.exports.tachyon = nativeBinding.tachyon
@@ -0,0 +1,90 @@
+use std::path::Path;
+
+/// Sent for extensions missing from the table
+const DEFAULT: &str = "application/octet-stream";
+
+/// `Content-Type` of a file, guessed from its extension
+pub fn content_type(path: &Path) -> &'static str {
+  let Some(extension) = path.extension().and_then(|extension| extension.to_str()) else {
+    return DEFAULT;
+  };
+
+  match extension.to_ascii_lowercase().as_str() {
+    // Text
+    "html" | "htm" => "text/html; charset=utf-8",
+    "css" => "text/css; charset=utf-8",
+    "js" | "mjs" | "cjs" => "text/javascript; charset=utf-8",
+    "txt" | "text" | "log" => "text/plain; charset=utf-8",
+    "md" | "markdown" => "text/markdown; charset=utf-8",
+    "csv" => "text/csv; charset=utf-8",
+    "xml" => "application/xml; charset=utf-8",
+    "ics" => "text/calendar; charset=utf-8",
+    "vtt" => "text/vtt; charset=utf-8",
+    "json" | "map" => "application/json; charset=utf-8",
+    "jsonld" => "application/ld+json; charset=utf-8",
+    "webmanifest" => "application/manifest+json; charset=utf-8",
+    "yaml" | "yml" => "application/yaml; charset=utf-8",
+    // Images
+    "png" => "image/png",
+    "jpg" | "jpeg" => "image/jpeg",
+    "gif" => "image/gif",
+    "webp" => "image/webp",
+    "avif" => "image/avif",
+    "svg" => "image/svg+xml",
+    "ico" => "image/x-icon",
+    "bmp" => "image/bmp",
+    "tif" | "tiff" => "image/tiff",
+    // Fonts
+    "woff" => "font/woff",
+    "woff2" => "font/woff2",
+    "ttf" => "font/ttf",
+    "otf" => "font/otf",
+    // Audio and video
+    "mp3" => "audio/mpeg",
+    "wav" => "audio/wav",
+    "ogg" | "oga" => "audio/ogg",
+    "m4a" => "audio/mp4",
+    "flac" => "audio/flac",
+    "mp4" | "m4v" => "video/mp4",
+    "webm" => "video/webm",
+    "ogv" => "video/ogg",
+    "mov" => "video/quicktime",
+    "mkv" => "video/x-matroska",
+    "m3u8" => "application/vnd.apple.mpegurl",
+    "ts" => "video/mp2t",
+    // Documents and archives
+    "pdf" => "application/pdf",
+    "zip" => "application/zip",
+    "gz" => "application/gzip",
+    "tar" => "application/x-tar",
+    "7z" => "application/x-7z-compressed",
+    "wasm" => "application/wasm",
+    "doc" => "application/msword",
+    "docx" => "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+    "xls" => "application/vnd.ms-excel",
+    "xlsx" => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+    "ppt" => "application/vnd.ms-powerpoint",
+    "pptx" => "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+    _ => DEFAULT,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_content_type() {
+    assert_eq!(
+      content_type(Path::new("index.HTML")),
+      "text/html; charset=utf-8"
+    );
+    assert_eq!(
+      content_type(Path::new("assets/app.min.js")),
+      "text/javascript; charset=utf-8"
+    );
+    assert_eq!(content_type(Path::new("logo.svg")), "image/svg+xml");
+    assert_eq!(content_type(Path::new("archive.unknown")), DEFAULT);
+    assert_eq!(content_type(Path::new("Makefile")), DEFAULT);
+  }
+}
@@ -0,0 +1,516 @@
+use bytes::Bytes;
+use hyper::header::{self, HeaderMap, HeaderValue};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use std::{
+  io::{ErrorKind, SeekFrom},
+  ops::RangeInclusive,
+  path::{Component, Path, PathBuf},
+  sync::atomic::{AtomicU64, Ordering},
+  time::{SystemTime, UNIX_EPOCH},
+};
+use tokio::{
+  fs::File,
+  io::{AsyncReadExt, AsyncSeekExt},
+};
+
+use crate::{core::response::TachyonResponse, error::HttpError};
+
+mod mime;
+mod range;
+mod serve_static;
+
+pub use mime::content_type;
+pub use range::{parse_range, ByteRanges};
+pub(crate) use serve_static::StaticFiles;
+
+/// Size of the chunks read from disk
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Characters of RFC 8187 `attr-char` sent as is in `filename*`
+const ATTR_CHAR: &AsciiSet = &NON_ALPHANUMERIC
+  .remove(b'!')
+  .remove(b'#')
+  .remove(b'$')
+  .remove(b'&')
+  .remove(b'+')
+  .remove(b'-')
+  .remove(b'.')
+  .remove(b'^')
+  .remove(b'_')
+  .remove(b'`')
+  .remove(b'|')
+  .remove(b'~');
+
+/// Conditional and range headers of a request, kept for the file responses answering it
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ConditionalHeaders {
+  if_none_match: Option<HeaderValue>,
+  if_modified_since: Option<HeaderValue>,
+  range: Option<HeaderValue>,
+  if_range: Option<HeaderValue>,
+}
+
+impl ConditionalHeaders {
+  /// `None` for the plain requests most of them are
+  pub fn from_headers(headers: &HeaderMap) -> Option<Self> {
+    let conditions = Self {
+      if_none_match: headers.get(header::IF_NONE_MATCH).cloned(),
+      if_modified_since: headers.get(header::IF_MODIFIED_SINCE).cloned(),
+      range: headers.get(header::RANGE).cloned(),
+      if_range: headers.get(header::IF_RANGE).cloned(),
+    };
+    (conditions.if_none_match.is_some()
+      || conditions.if_modified_since.is_some()
+      || conditions.range.is_some())
+    .then_some(conditions)
+  }
+
+  /// Whether the copy the client holds is still the current one
+  fn is_fresh(&self, etag: Option<&str>, modified: Option<SystemTime>) -> bool {
+    // `If-None-Match` wins over `If-Modified-Since`, see RFC 9110 section 13.1.3
+    if let Some(if_none_match) = &self.if_none_match {
+      let (Some(etag), Ok(if_none_match)) = (etag, if_none_match.to_str()) else {
+        return false;
+      };
+      return if_none_match
+        .split(',')
+        .map(str::trim)
+        .any(|tag| tag == "*" || weak_eq(tag, etag));
+    }
+
+    let since = self
+      .if_modified_since
+      .as_ref()
+      .and_then(|since| since.to_str().ok())
+      .and_then(|since| httpdate::parse_http_date(since).ok());
+    match (since, modified) {
+      (Some(since), Some(modified)) => whole_seconds(modified) <= since,
+      _ => false,
+    }
+  }
+
+  /// Parts of the file to send, all of it unless `If-Range` still matches
+  fn byte_ranges(&self, etag: Option<&str>, modified: Option<SystemTime>, len: u64) -> ByteRanges {
+    let Some(range) = self.range.as_ref().and_then(|range| range.to_str().ok()) else {
+      return ByteRanges::Full;
+    };
+
+    if let Some(if_range) = &self.if_range {
+      let Ok(if_range) = if_range.to_str() else {
+        return ByteRanges::Full;
+      };
+      // Only strong validators fit, see RFC 9110 section 13.1.5
+      let matches = if if_range.starts_with('"') || if_range.starts_with("W/") {
+        etag.is_some_and(|etag| !etag.starts_with("W/") && etag == if_range)
+      } else {
+        let date = httpdate::parse_http_date(if_range).ok();
+        modified.is_some_and(|modified| date == Some(whole_seconds(modified)))
+      };
+      if !matches {
+        return ByteRanges::Full;
+      }
+    }
+    parse_range(range, len)
+  }
+}
+
+/// Headers sent along with a file
+#[derive(Debug, Clone)]
+pub(crate) struct FileOptions {
+  pub etag: bool,
+  pub last_modified: bool,
+  pub cache_control: Option<String>,
+}
+
+/// Stream a file as the response, or answer 304 when the client has it already
+///
+/// `Content-Type` comes from the extension unless the response has one.
+/// `Range` requests get the parts they ask for with 206, or 416 when none
+/// of them overlaps the file.
+pub(crate) async fn send_file(
+  res: &TachyonResponse,
+  path: &Path,
+  options: &FileOptions,
+) -> Result<(), HttpError> {
+  let file = File::open(path).await.map_err(open_error)?;
+  let metadata = file.metadata().await.map_err(open_error)?;
+  if !metadata.is_file() {
+    return Err(HttpError::not_found());
+  }
+
+  let modified = metadata.modified().ok();
+  let etag = options.etag.then(|| entity_tag(metadata.len(), modified));
+  if res.get_header("content-type").is_none() {
+    let _ = res.set_header("content-type", content_type(path));
+  }
+  if let Some(etag) = &etag {
+    let _ = res.set_header("etag", etag);
+  }
+  if let Some(modified) = modified.filter(|_| options.last_modified) {
+    let _ = res.set_header("last-modified", &httpdate::fmt_http_date(modified));
+  }
+  if let Some(cache_control) = &options.cache_control {
+    let _ = res.set_header("cache-control", cache_control);
+  }
+
+  let _ = res.set_header("accept-ranges", "bytes");
+
+  let len = metadata.len();
+  let modified = modified.filter(|_| options.last_modified);
+  let conditions = res.locals().get::<ConditionalHeaders>();
+  if let Some(conditions) = &conditions {
+    if conditions.is_fresh(etag.as_deref(), modified) {
+      res.status(304);
+      res.remove_header("content-type");
+      res.end();
+      return Ok(());
+    }
+  }
+
+  let ranges = conditions.map_or(ByteRanges::Full, |conditions| {
+    conditions.byte_ranges(etag.as_deref(), modified, len)
+  });
+  match ranges {
+    ByteRanges::Full => {
+      let _ = res.set_header("content-length", &len.to_string());
+      let whole = (len > 0).then(|| 0..=len - 1);
+      send_ranges(res, file, &[], whole.iter()).await;
+      res.end();
+    }
+    ByteRanges::Unsatisfiable => {
+      res.status(416);
+      res.remove_header("content-type");
+      let _ = res.set_header("content-range", &format!("bytes */{}", len));
+      let _ = res.set_header("content-length", "0");
+      res.end();
+    }
+    ByteRanges::Partial(ranges) if ranges.len() == 1 => {
+      let range = &ranges[0];
+      res.status(206);
+      let _ = res.set_header("content-range", &content_range(range, len));
+      let _ = res.set_header("content-length", &range_len(range).to_string());
+      send_ranges(res, file, &[], ranges.iter()).await;
+      res.end();
+    }
+    ByteRanges::Partial(ranges) => send_multipart(res, file, &ranges, len).await,
+  }
+  Ok(())
+}
+
+/// Send several ranges as a `multipart/byteranges` body, see RFC 9110 section 14.6
+async fn send_multipart(
+  res: &TachyonResponse,
+  file: File,
+  ranges: &[RangeInclusive<u64>],
+  len: u64,
+) {
+  let boundary = boundary();
+  let content_type = res.get_header("content-type").unwrap_or_default();
+  let heads: Vec<Bytes> = ranges
+    .iter()
+    .map(|range| {
+      let head = format!(
+        "--{}\r\nContent-Type: {}\r\nContent-Range: {}\r\n\r\n",
+        boundary,
+        content_type,
+        content_range(range, len)
+      );
+      Bytes::from(head)
+    })
+    .collect();
+  let closing = format!("--{}--\r\n", boundary);
+
+  // Each part ends with the line break before the next boundary
+  let body_len = heads
+    .iter()
+    .zip(ranges)
+    .map(|(head, range)| head.len() as u64 + range_len(range) + 2)
+    .sum::<u64>()
+    + closing.len() as u64;
+  res.status(206);
+  let _ = res.set_header(
+    "content-type",
+    &format!("multipart/byteranges; boundary={}", boundary),
+  );
+  let _ = res.set_header("content-length", &body_len.to_string());
+
+  if send_ranges(res, file, &heads, ranges.iter()).await {
+    res.write(closing).await;
+  }
+  res.end();
+}
+
+/// Write the ranges of `file`, each one after its part head when there are some
+///
+/// Returns false once the client went away or the file could not be read,
+/// the response is left for the caller to end.
+async fn send_ranges<'a>(
+  res: &TachyonResponse,
+  mut file: File,
+  heads: &[Bytes],
+  ranges: impl Iterator<Item = &'a RangeInclusive<u64>>,
+) -> bool {
+  for (index, range) in ranges.enumerate() {
+    if let Some(head) = heads.get(index) {
+      if !res.write(head.clone()).await {
+        return false;
+      }
+    }
+    if file.seek(SeekFrom::Start(*range.start())).await.is_err() {
+      return false;
+    }
+
+    let mut remaining = range_len(range);
+    while remaining > 0 {
+      let mut chunk = vec![0; remaining.min(CHUNK_SIZE as u64) as usize];
+      let read = match file.read(&mut chunk).await {
+        Ok(0) | Err(_) => return false,
+        Ok(read) => read,
+      };
+      chunk.truncate(read);
+      remaining -= read as u64;
+      if !res.write(Bytes::from(chunk)).await {
+        return false;
+      }
+    }
+    if !heads.is_empty() && !res.write(Bytes::from_static(b"\r\n")).await {
+      return false;
+    }
+  }
+  true
+}
+
+fn range_len(range: &RangeInclusive<u64>) -> u64 {
+  range.end() - range.start() + 1
+}
+
+fn content_range(range: &RangeInclusive<u64>, len: u64) -> String {
+  format!("bytes {}-{}/{}", range.start(), range.end(), len)
+}
+
+/// Separator of multipart parts, unlikely to show up in a file
+fn boundary() -> String {
+  static COUNTER: AtomicU64 = AtomicU64::new(0);
+  let nanos = SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map_or(0, |since| since.as_nanos() as u64);
+  format!(
+    "tachyon-{:016x}{:08x}",
+    nanos,
+    COUNTER.fetch_add(1, Ordering::Relaxed)
+  )
+}
+
+/// Path of a file sent by a handler, kept inside `root` when one is given
+pub(crate) async fn resolve_path(
+  path: &Path,
+  root: Option<&Path>,
+) -> std::result::Result<PathBuf, HttpError> {
+  let Some(root) = root else {
+    return Ok(path.to_path_buf());
+  };
+  let climbs = path
+    .components()
+    .any(|component| !matches!(component, Component::Normal(_) | Component::CurDir));
+  if climbs {
+    return Err(HttpError::not_found());
+  }
+
+  // Symlinks may point anywhere, only what resolves inside the root is sent
+  let root = tokio::fs::canonicalize(root).await.map_err(open_error)?;
+  let path = tokio::fs::canonicalize(root.join(path))
+    .await
+    .map_err(open_error)?;
+  if !path.starts_with(&root) {
+    return Err(HttpError::not_found());
+  }
+  Ok(path)
+}
+
+/// `Content-Disposition` asking browsers to save the file as `filename`
+///
+/// Old clients read the ASCII `filename`, others the UTF-8 `filename*`.
+pub fn content_disposition(filename: &str) -> String {
+  let fallback: String = filename
+    .chars()
+    .map(|c| match c {
+      ' '..='~' if c != '"' && c != '\\' => c,
+      _ => '_',
+    })
+    .collect();
+  format!(
+    "attachment; filename=\"{}\"; filename*=UTF-8''{}",
+    fallback,
+    utf8_percent_encode(filename, ATTR_CHAR)
+  )
+}
+
+fn open_error(err: std::io::Error) -> HttpError {
+  match err.kind() {
+    ErrorKind::PermissionDenied => HttpError::forbidden(),
+    ErrorKind::NotFound | ErrorKind::NotADirectory | ErrorKind::IsADirectory => {
+      HttpError::not_found()
+    }
+    _ => HttpError::handler_error(format!("Cannot read file: {}", err)),
+  }
+}
+
+/// Validator built from the size and modification time, as most servers do
+///
+/// Strong, so that `If-Range` can use it to resume downloads.
+fn entity_tag(len: u64, modified: Option<SystemTime>) -> String {
+  let millis = modified
+    .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+    .map_or(0, |since| since.as_millis());
+  format!("\"{:x}-{:x}\"", len, millis)
+}
+
+/// Weak comparison of RFC 9110 section 8.8.3.2, ignoring the `W/` prefixes
+fn weak_eq(a: &str, b: &str) -> bool {
+  let opaque = |tag: &'_ str| tag.strip_prefix("W/").unwrap_or(tag).to_string();
+  opaque(a) == opaque(b)
+}
+
+/// HTTP dates have no sub-second part
+fn whole_seconds(time: SystemTime) -> SystemTime {
+  match time.duration_since(UNIX_EPOCH) {
+    Ok(since) => UNIX_EPOCH + std::time::Duration::from_secs(since.as_secs()),
+    Err(_) => time,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::core::methods::Method;
+  use crate::testing::{call, get};
+  use crate::{SendFileOptions, Tachyon};
+  use std::time::Duration;
+
+  #[test]
+  fn test_conditional_headers() {
+    let modified = UNIX_EPOCH + Duration::from_millis(1_700_000_000_500);
+    let etag = entity_tag(42, Some(modified));
+    assert_eq!(etag, "\"2a-18bcfe569f4\"");
+
+    let mut headers = HeaderMap::new();
+    assert!(ConditionalHeaders::from_headers(&headers).is_none());
+
+    headers.insert(
+      header::IF_MODIFIED_SINCE,
+      HeaderValue::from_str(&httpdate::fmt_http_date(modified)).unwrap(),
+    );
+    let conditions = ConditionalHeaders::from_headers(&headers).unwrap();
+    assert!(conditions.is_fresh(Some(&etag), Some(modified)));
+    assert!(!conditions.is_fresh(Some(&etag), Some(modified + Duration::from_secs(1))));
+
+    // An ETag that changed wins over a date that did not
+    headers.insert(
+      header::IF_NONE_MATCH,
+      HeaderValue::from_static("\"other\", \"2a-18bcfe569f4\""),
+    );
+    let conditions = ConditionalHeaders::from_headers(&headers).unwrap();
+    assert!(conditions.is_fresh(Some(&etag), Some(modified)));
+    assert!(!conditions.is_fresh(Some("W/\"2b-1\""), Some(modified)));
+
+    // Ranges apply while `If-Range` names the current version only
+    let mut headers = HeaderMap::new();
+    headers.insert(header::RANGE, HeaderValue::from_static("bytes=0-9"));
+    headers.insert(header::IF_RANGE, HeaderValue::from_str(&etag).unwrap());
+    let conditions = ConditionalHeaders::from_headers(&headers).unwrap();
+    assert_eq!(
+      conditions.byte_ranges(Some(&etag), Some(modified), 42),
+      ByteRanges::Partial(vec![0..=9])
+    );
+    assert_eq!(
+      conditions.byte_ranges(Some("\"2a-0\""), Some(modified), 42),
+      ByteRanges::Full
+    );
+    headers.insert(
+      header::IF_RANGE,
+      HeaderValue::from_str(&httpdate::fmt_http_date(modified)).unwrap(),
+    );
+    let conditions = ConditionalHeaders::from_headers(&headers).unwrap();
+    assert_eq!(
+      conditions.byte_ranges(None, Some(modified), 42),
+      ByteRanges::Partial(vec![0..=9])
+    );
+  }
+
+  #[test]
+  fn test_content_disposition() {
+    assert_eq!(
+      content_disposition("report.pdf"),
+      "attachment; filename=\"report.pdf\"; filename*=UTF-8''report.pdf"
+    );
+    assert_eq!(
+      content_disposition("résumé \"final\".pdf"),
+      "attachment; filename=\"r_sum_ _final_.pdf\"; filename*=UTF-8''r%C3%A9sum%C3%A9%20%22final%22.pdf"
+    );
+  }
+
+  #[tokio::test]
+  async fn test_resolve_path() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    assert_eq!(
+      resolve_path(Path::new("Cargo.toml"), Some(root)).await,
+      Ok(root.join("Cargo.toml"))
+    );
+    assert_eq!(
+      resolve_path(Path::new("../etc/passwd"), Some(root)).await,
+      Err(HttpError::not_found())
+    );
+    assert_eq!(
+      resolve_path(Path::new("/etc/passwd"), Some(root)).await,
+      Err(HttpError::not_found())
+    );
+    assert_eq!(
+      resolve_path(Path::new("missing.txt"), Some(root)).await,
+      Err(HttpError::not_found())
+    );
+  }
+
+  #[tokio::test]
+  async fn test_send_and_download_files() {
+    let app = Tachyon::new();
+    app
+      .route(
+        Method::Get,
+        "/manifest",
+        |_req, res: TachyonResponse| async move {
+          let options = SendFileOptions {
+            root: Some(env!("CARGO_MANIFEST_DIR").to_string()),
+            ..Default::default()
+          };
+          res
+            .download("Cargo.toml", Some("manifest.toml"), options)
+            .await;
+        },
+      )
+      .unwrap();
+    app
+      .route(
+        Method::Get,
+        "/missing",
+        |_req, res: TachyonResponse| async move {
+          res
+            .send_file("does-not-exist.txt", SendFileOptions::default())
+            .await;
+        },
+      )
+      .unwrap();
+
+    let mut service = app.service();
+    let res = call(&mut service, get("/manifest")).await;
+    assert_eq!(res.status, hyper::StatusCode::OK);
+    assert_eq!(
+      res.headers[header::CONTENT_DISPOSITION],
+      "attachment; filename=\"manifest.toml\"; filename*=UTF-8''manifest.toml"
+    );
+    assert_eq!(res.body, std::fs::read("Cargo.toml").unwrap());
+
+    let res = call(&mut service, get("/missing")).await;
+    assert_eq!(res.status, hyper::StatusCode::NOT_FOUND);
+    assert!(!res.headers.contains_key(header::CONTENT_DISPOSITION));
+  }
+}
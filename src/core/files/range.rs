@@ -0,0 +1,138 @@
+use std::ops::RangeInclusive;
+
+/// More ranges than this are served as the whole file, they cost more than they save
+const MAX_RANGES: usize = 32;
+
+/// Outcome of a `Range` header for a file of a given size
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ByteRanges {
+  /// Missing, malformed or in another unit, the whole file is sent
+  Full,
+  /// None of the ranges overlaps the file, answered with 416
+  Unsatisfiable,
+  /// Satisfiable ranges in the requested order, each one inclusive
+  Partial(Vec<RangeInclusive<u64>>),
+}
+
+/// Parse a `Range` header, see RFC 9110 section 14.1.2
+///
+/// Ranges starting past the end are dropped, the others are clamped to it.
+/// Overlapping or adjacent ranges are merged so no byte is sent twice.
+pub fn parse_range(header: &str, len: u64) -> ByteRanges {
+  let Some(specs) = header.trim().strip_prefix("bytes=") else {
+    return ByteRanges::Full;
+  };
+
+  let mut ranges = Vec::new();
+  for spec in specs.split(',').map(str::trim) {
+    let Some((start, end)) = spec.split_once('-') else {
+      return ByteRanges::Full;
+    };
+    let range = match (start.trim(), end.trim()) {
+      // `-500` is the last 500 bytes
+      ("", suffix) => match suffix.parse::<u64>() {
+        Ok(0) => None,
+        Ok(suffix) if len > 0 => Some(len.saturating_sub(suffix)..=len - 1),
+        Ok(_) => None,
+        Err(_) => return ByteRanges::Full,
+      },
+      (start, end) => {
+        let Ok(start) = start.parse::<u64>() else {
+          return ByteRanges::Full;
+        };
+        let end = match end {
+          "" => u64::MAX,
+          end => match end.parse::<u64>() {
+            Ok(end) if end >= start => end,
+            _ => return ByteRanges::Full,
+          },
+        };
+        (start < len).then(|| start..=end.min(len - 1))
+      }
+    };
+    ranges.extend(range);
+  }
+
+  if ranges.len() > MAX_RANGES {
+    ByteRanges::Full
+  } else if ranges.is_empty() {
+    ByteRanges::Unsatisfiable
+  } else {
+    ByteRanges::Partial(coalesce(ranges))
+  }
+}
+
+/// Merge overlapping or adjacent ranges, keeping the request order otherwise
+fn coalesce(ranges: Vec<RangeInclusive<u64>>) -> Vec<RangeInclusive<u64>> {
+  let mut sorted = ranges.clone();
+  sorted.sort_by_key(|range| *range.start());
+  let touching = sorted
+    .windows(2)
+    .any(|pair| pair[1].start().saturating_sub(1) <= *pair[0].end());
+  if !touching {
+    return ranges;
+  }
+
+  let mut merged: Vec<RangeInclusive<u64>> = Vec::with_capacity(sorted.len());
+  for range in sorted {
+    match merged.last_mut() {
+      Some(last) if range.start().saturating_sub(1) <= *last.end() => {
+        *last = *last.start()..=*range.end().max(last.end());
+      }
+      _ => merged.push(range),
+    }
+  }
+  merged
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse_range() {
+    assert_eq!(
+      parse_range("bytes=0-499", 1000),
+      ByteRanges::Partial(vec![0..=499])
+    );
+    assert_eq!(
+      parse_range("bytes=500-, -100", 1000),
+      ByteRanges::Partial(vec![500..=999])
+    );
+    assert_eq!(
+      parse_range("bytes=900-999,0-99", 1000),
+      ByteRanges::Partial(vec![900..=999, 0..=99])
+    );
+    assert_eq!(
+      parse_range("bytes=200-299,0-99,100-149", 1000),
+      ByteRanges::Partial(vec![0..=149, 200..=299])
+    );
+    assert_eq!(
+      parse_range("bytes=900-5000", 1000),
+      ByteRanges::Partial(vec![900..=999])
+    );
+    assert_eq!(
+      parse_range("bytes=-5000", 1000),
+      ByteRanges::Partial(vec![0..=999])
+    );
+    assert_eq!(
+      parse_range("bytes=0-0,1000-", 1000),
+      ByteRanges::Partial(vec![0..=0])
+    );
+
+    assert_eq!(parse_range("bytes=1000-", 1000), ByteRanges::Unsatisfiable);
+    assert_eq!(parse_range("bytes=-0", 1000), ByteRanges::Unsatisfiable);
+    assert_eq!(parse_range("bytes=0-", 0), ByteRanges::Unsatisfiable);
+
+    assert_eq!(parse_range("items=0-5", 1000), ByteRanges::Full);
+    assert_eq!(parse_range("bytes=5-1", 1000), ByteRanges::Full);
+    assert_eq!(parse_range("bytes=abc", 1000), ByteRanges::Full);
+    let repeated = format!("bytes={}", vec!["0-"; 32].join(","));
+    assert_eq!(
+      parse_range(&repeated, 1000),
+      ByteRanges::Partial(vec![0..=999])
+    );
+    let many = format!("bytes={}", vec!["0-1"; 33].join(","));
+    assert_eq!(parse_range(&many, 1000), ByteRanges::Full);
+  }
+}
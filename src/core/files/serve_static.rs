@@ -0,0 +1,199 @@
+use async_trait::async_trait;
+use std::{
+  io::ErrorKind,
+  path::{Path, PathBuf},
+};
+
+use super::{send_file, FileOptions};
+use crate::{
+  core::{
+    options::{Dotfiles, StaticOptions},
+    request::TachyonRequest,
+    response::TachyonResponse,
+    router::TachyonHandler,
+  },
+  error::{HttpError, Result, TachyonError},
+};
+
+/// Route handler serving the files of a directory, registered by `app.static`
+pub(crate) struct StaticFiles {
+  /// Canonical path, every served file must resolve inside it
+  root: PathBuf,
+  options: StaticOptions,
+  file_options: FileOptions,
+}
+
+impl StaticFiles {
+  pub fn new(root: impl AsRef<Path>, options: StaticOptions) -> Result<Self> {
+    let root = root.as_ref();
+    let canonical = std::fs::canonicalize(root)
+      .ok()
+      .filter(|root| root.is_dir())
+      .ok_or_else(|| {
+        TachyonError::new(format!(
+          "Static root '{}' is not a directory",
+          root.display()
+        ))
+      })?;
+
+    let file_options = FileOptions {
+      etag: options.etag(),
+      last_modified: options.last_modified(),
+      cache_control: Some(options.cache_control()),
+    };
+    Ok(Self {
+      root: canonical,
+      options,
+      file_options,
+    })
+  }
+
+  /// File answering the path captured after the prefix
+  async fn resolve(&self, relative: &str) -> std::result::Result<PathBuf, HttpError> {
+    let mut path = self.root.clone();
+    for segment in relative.split('/').filter(|segment| !segment.is_empty()) {
+      // Never let a segment climb up or name another root, whatever the platform
+      if segment == "." || segment == ".." || segment.contains(['\\', '\0', ':']) {
+        return Err(HttpError::not_found());
+      }
+      if segment.starts_with('.') {
+        match self.options.dotfiles() {
+          Dotfiles::Allow => {}
+          Dotfiles::Deny => return Err(HttpError::forbidden()),
+          Dotfiles::Ignore => return Err(HttpError::not_found()),
+        }
+      }
+      path.push(segment);
+    }
+
+    // Symlinks may point anywhere, only what resolves inside the root is served
+    let mut path = tokio::fs::canonicalize(&path)
+      .await
+      .map_err(|err| match err.kind() {
+        ErrorKind::PermissionDenied => HttpError::forbidden(),
+        _ => HttpError::not_found(),
+      })?;
+    if !path.starts_with(&self.root) {
+      return Err(HttpError::not_found());
+    }
+
+    if tokio::fs::metadata(&path)
+      .await
+      .is_ok_and(|metadata| metadata.is_dir())
+    {
+      if !self.options.index() {
+        return Err(HttpError::not_found());
+      }
+      path.push("index.html");
+    }
+    Ok(path)
+  }
+}
+
+#[async_trait]
+impl TachyonHandler for StaticFiles {
+  async fn call(&self, req: TachyonRequest, res: TachyonResponse) {
+    let relative = req.params.get("*").map(String::as_str).unwrap_or_default();
+    let served = match self.resolve(relative).await {
+      Ok(path) => send_file(&res, &path, &self.file_options).await,
+      Err(err) => Err(err),
+    };
+    if let Err(err) = served {
+      res.fail(err);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::testing::{call, get, TempDir};
+  use crate::Tachyon;
+  use bytes::Bytes;
+  use http_body_util::Empty;
+  use hyper::{header, Request, StatusCode};
+
+  #[tokio::test]
+  async fn test_resolve_inside_root() {
+    let dir = TempDir::new("static");
+    dir.write("docs/index.html", "<h1>Docs</h1>");
+    dir.write(".env", "SECRET=1");
+
+    let files = StaticFiles::new(dir.path(), StaticOptions::default()).unwrap();
+    let root = files.root.clone();
+    assert_eq!(
+      files.resolve("docs").await,
+      Ok(root.join("docs/index.html"))
+    );
+    assert_eq!(
+      files.resolve("docs/../.env").await,
+      Err(HttpError::not_found())
+    );
+    assert_eq!(files.resolve(".env").await, Err(HttpError::not_found()));
+    assert_eq!(
+      files.resolve("missing.css").await,
+      Err(HttpError::not_found())
+    );
+
+    let files = StaticFiles::new(
+      &root,
+      StaticOptions {
+        dotfiles: Some(Dotfiles::Deny),
+        index: Some(false),
+        ..Default::default()
+      },
+    )
+    .unwrap();
+    assert_eq!(files.resolve(".env").await, Err(HttpError::forbidden()));
+    assert_eq!(files.resolve("docs").await, Err(HttpError::not_found()));
+    assert!(StaticFiles::new(root.join(".env"), StaticOptions::default()).is_err());
+  }
+
+  #[tokio::test]
+  async fn test_serve_static_files() {
+    let dir = TempDir::new("assets");
+    dir.write("app.js", "console.log(1)");
+
+    let app = Tachyon::new();
+    app
+      .serve_static(
+        "/assets",
+        dir.path(),
+        StaticOptions {
+          max_age: Some(3600),
+          ..Default::default()
+        },
+      )
+      .unwrap();
+    assert_eq!(app.routes(), vec!["/assets GET", "/assets/* GET"]);
+
+    let mut service = app.service();
+    let res = call(&mut service, get("/assets/app.js")).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(
+      res.headers[header::CONTENT_TYPE],
+      "text/javascript; charset=utf-8"
+    );
+    assert_eq!(res.headers[header::CACHE_CONTROL], "public, max-age=3600");
+    assert_eq!(res.text(), "console.log(1)");
+
+    let req = Request::get("/assets/app.js")
+      .header(header::IF_NONE_MATCH, &res.headers[header::ETAG])
+      .body(Empty::<Bytes>::new())
+      .unwrap();
+    let res = call(&mut service, req).await;
+    assert_eq!(res.status, StatusCode::NOT_MODIFIED);
+
+    let req = Request::get("/assets/app.js")
+      .header(header::RANGE, "bytes=8-")
+      .body(Empty::<Bytes>::new())
+      .unwrap();
+    let res = call(&mut service, req).await;
+    assert_eq!(res.status, StatusCode::PARTIAL_CONTENT);
+    assert_eq!(res.headers[header::CONTENT_RANGE], "bytes 8-13/14");
+    assert_eq!(res.text(), "log(1)");
+
+    let res = call(&mut service, get("/assets/missing.js")).await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+  }
+}
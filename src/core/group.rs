@@ -0,0 +1,226 @@
+use dashmap::DashMap;
+#[cfg(feature = "napi")]
+use napi_derive::napi;
+use std::sync::{Arc, RwLock};
+
+use crate::core::{
+  hooks::{HookName, Hooks},
+  router::{describe_routes, route_infos, HTTPCall, RouteInfo, TachyonHandler, TachyonRouter},
+};
+#[cfg(feature = "napi")]
+use crate::core::{
+  methods::Method,
+  wrapper::{into_handlers, RouteArg, TachyonCallbacks},
+};
+
+/// Standalone group of routes that can be mounted on an app under a prefix
+#[cfg_attr(feature = "napi", napi)]
+pub struct Router {
+  routes: Arc<DashMap<String, TachyonRouter>>,
+  middleware: Arc<RwLock<Vec<Arc<dyn TachyonHandler>>>>,
+  hooks: Arc<RwLock<Hooks>>,
+}
+
+impl Default for Router {
+  fn default() -> Self {
+    Self {
+      routes: Arc::new(DashMap::new()),
+      middleware: Arc::new(RwLock::new(Vec::new())),
+      hooks: Arc::new(RwLock::new(Hooks::default())),
+    }
+  }
+}
+
+#[cfg_attr(feature = "napi", napi)]
+impl Router {
+  #[cfg_attr(feature = "napi", napi(constructor))]
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  #[cfg_attr(feature = "napi", napi)]
+  pub fn routes(&self) -> Vec<String> {
+    describe_routes(&self.describe_routes())
+  }
+
+  /// Every route with its params, middleware count and metadata, sorted by path
+  #[cfg_attr(feature = "napi", napi)]
+  pub fn describe_routes(&self) -> Vec<RouteInfo> {
+    route_infos(&self.routes)
+  }
+}
+
+#[cfg(feature = "napi")]
+#[napi]
+impl Router {
+  /// Add middleware that runs before every route of this router
+  ///
+  /// Only routes mounted after this call pick the middleware up.
+  ///
+  /// Example usage:
+  /// ```javascript
+  /// const admin = router()
+  /// admin.use(requireAuth)
+  /// admin.use([audit, rateLimit])
+  /// ```
+  #[napi(
+    ts_args_type = r#"callbacks: ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>) | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>>"#,
+    js_name = "use"
+  )]
+  pub fn use_middleware(&self, callbacks: TachyonCallbacks) -> napi::Result<()> {
+    self.add_middleware(into_handlers(callbacks)?);
+    Ok(())
+  }
+
+  /// Add a lifecycle hook to every route of this router
+  ///
+  /// Only routes mounted after this call pick the hook up, and the app hooks
+  /// run first.
+  ///
+  /// Example usage:
+  /// ```javascript
+  /// const api = router()
+  /// api.addHook('onSend', (req, res) => {
+  ///   res.header('cache-control', 'no-store')
+  /// })
+  /// ```
+  #[napi(
+    ts_args_type = r#"name: HookName, callbacks: ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>) | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>>"#
+  )]
+  pub fn add_hook(&self, name: HookName, callbacks: TachyonCallbacks) -> napi::Result<()> {
+    self.add_hooks(name, into_handlers(callbacks)?);
+    Ok(())
+  }
+
+  /// Add a GET route handler to this router
+  ///
+  /// Example usage:
+  /// ```javascript
+  /// users.get('/:id', (req, res) => {
+  ///   res.send('User!')
+  /// })
+  /// ```
+  #[napi(
+    ts_args_type = r#"route: string, arg1: ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>) | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>> | RouteOptions, arg2?: ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>) | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>> | RouteOptions | undefined | null, arg3?: ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>) | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>> | RouteOptions | undefined | null, arg4?: ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>) | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>> | RouteOptions | undefined | null, arg5?: ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>) | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>> | RouteOptions | undefined | null"#,
+    js_name = "get"
+  )]
+  pub fn get(
+    &self,
+    route: String,
+    arg1: Option<RouteArg>,
+    arg2: Option<RouteArg>,
+    arg3: Option<RouteArg>,
+    arg4: Option<RouteArg>,
+    arg5: Option<RouteArg>,
+  ) -> napi::Result<()> {
+    self.call(route, Method::new(0), vec![arg1, arg2, arg3, arg4, arg5])
+  }
+
+  /// Add a POST route handler to this router
+  #[napi(
+    ts_args_type = r#"route: string, arg1: ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>) | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>> | RouteOptions, arg2?: ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>) | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>> | RouteOptions | undefined | null, arg3?: ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>) | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>> | RouteOptions | undefined | null, arg4?: ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>) | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>> | RouteOptions | undefined | null, arg5?: ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>) | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>> | RouteOptions | undefined | null"#
+  )]
+  pub fn post(
+    &self,
+    route: String,
+    arg1: Option<RouteArg>,
+    arg2: Option<RouteArg>,
+    arg3: Option<RouteArg>,
+    arg4: Option<RouteArg>,
+    arg5: Option<RouteArg>,
+  ) -> napi::Result<()> {
+    self.call(route, Method::new(1), vec![arg1, arg2, arg3, arg4, arg5])
+  }
+
+  /// Add a PUT route handler to this router
+  #[napi(
+    ts_args_type = r#"route: string, arg1: ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>) | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>> | RouteOptions, arg2?: ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>) | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>> | RouteOptions | undefined | null, arg3?: ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>) | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>> | RouteOptions | undefined | null, arg4?: ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>) | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>> | RouteOptions | undefined | null, arg5?: ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>) | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>> | RouteOptions | undefined | null"#
+  )]
+  pub fn put(
+    &self,
+    route: String,
+    arg1: Option<RouteArg>,
+    arg2: Option<RouteArg>,
+    arg3: Option<RouteArg>,
+    arg4: Option<RouteArg>,
+    arg5: Option<RouteArg>,
+  ) -> napi::Result<()> {
+    self.call(route, Method::new(2), vec![arg1, arg2, arg3, arg4, arg5])
+  }
+
+  /// Add a DELETE route handler to this router
+  #[napi(
+    ts_args_type = r#"route: string, arg1: ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>) | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>> | RouteOptions, arg2?: ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>) | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>> | RouteOptions | undefined | null, arg3?: ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>) | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>> | RouteOptions | undefined | null, arg4?: ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>) | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>> | RouteOptions | undefined | null, arg5?: ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>) | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>> | RouteOptions | undefined | null"#
+  )]
+  pub fn delete(
+    &self,
+    route: String,
+    arg1: Option<RouteArg>,
+    arg2: Option<RouteArg>,
+    arg3: Option<RouteArg>,
+    arg4: Option<RouteArg>,
+    arg5: Option<RouteArg>,
+  ) -> napi::Result<()> {
+    self.call(route, Method::new(3), vec![arg1, arg2, arg3, arg4, arg5])
+  }
+
+  /// Add a PATCH route handler to this router
+  #[napi(
+    ts_args_type = r#"route: string, arg1: ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>) | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>> | RouteOptions, arg2?: ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>) | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>> | RouteOptions | undefined | null, arg3?: ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>) | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>> | RouteOptions | undefined | null, arg4?: ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>) | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>> | RouteOptions | undefined | null, arg5?: ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>) | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>> | RouteOptions | undefined | null"#
+  )]
+  pub fn patch(
+    &self,
+    route: String,
+    arg1: Option<RouteArg>,
+    arg2: Option<RouteArg>,
+    arg3: Option<RouteArg>,
+    arg4: Option<RouteArg>,
+    arg5: Option<RouteArg>,
+  ) -> napi::Result<()> {
+    self.call(route, Method::new(4), vec![arg1, arg2, arg3, arg4, arg5])
+  }
+
+  /// Nest another router under a path prefix of this one
+  #[napi(js_name = "mount")]
+  pub fn mount_router(&self, prefix: String, router: &Router) -> napi::Result<()> {
+    Ok(self.mount(&prefix, router)?)
+  }
+}
+
+impl Router {
+  /// Append native middleware that runs before every route of this router
+  pub fn add_middleware(&self, handlers: Vec<Arc<dyn TachyonHandler>>) {
+    if let Ok(mut middleware) = self.middleware.write() {
+      middleware.extend(handlers);
+    }
+  }
+
+  /// Append native hooks for every route of this router
+  pub fn add_hooks(&self, name: HookName, handlers: Vec<Arc<dyn TachyonHandler>>) {
+    if let Ok(mut hooks) = self.hooks.write() {
+      hooks.add(name, handlers);
+    }
+  }
+
+  pub fn hooks(&self) -> Hooks {
+    self
+      .hooks
+      .read()
+      .map(|hooks| hooks.clone())
+      .unwrap_or_default()
+  }
+
+  pub fn middleware(&self) -> Vec<Arc<dyn TachyonHandler>> {
+    self
+      .middleware
+      .read()
+      .map(|middleware| middleware.clone())
+      .unwrap_or_default()
+  }
+}
+
+impl HTTPCall for Router {
+  fn route_table(&self) -> Arc<DashMap<String, TachyonRouter>> {
+    Arc::clone(&self.routes)
+  }
+}
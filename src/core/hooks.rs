@@ -0,0 +1,294 @@
+use bytes::Bytes;
+use http_body_util::{combinators::BoxBody, BodyExt};
+use hyper::body::{Body, Frame, SizeHint};
+#[cfg(feature = "napi")]
+use napi_derive::napi;
+use std::{
+  pin::Pin,
+  sync::Arc,
+  task::{Context, Poll},
+};
+use tower::BoxError;
+
+use crate::core::{request::TachyonRequest, response::TachyonResponse, router::TachyonHandler};
+
+/// Stage of the request pipeline a hook runs at
+#[cfg_attr(feature = "napi", napi(string_enum = "camelCase"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookName {
+  /// Route matched, before the body is read
+  OnRequest,
+  /// Body parsed, before the route handlers
+  PreHandler,
+  /// Response ready, payload and headers can still be changed
+  OnSend,
+  /// Response written to the client, `res.elapsedTime` gives the timing
+  OnResponse,
+}
+
+/// Handlers registered for each stage, in registration order
+#[derive(Clone, Default)]
+pub struct Hooks {
+  on_request: Vec<Arc<dyn TachyonHandler>>,
+  pre_handler: Vec<Arc<dyn TachyonHandler>>,
+  on_send: Vec<Arc<dyn TachyonHandler>>,
+  on_response: Vec<Arc<dyn TachyonHandler>>,
+}
+
+impl Hooks {
+  pub fn add(&mut self, name: HookName, handlers: Vec<Arc<dyn TachyonHandler>>) {
+    self.stage_mut(name).extend(handlers);
+  }
+
+  /// Append every hook of `other` after the ones already there
+  pub fn extend(&mut self, other: &Hooks) {
+    for name in [
+      HookName::OnRequest,
+      HookName::PreHandler,
+      HookName::OnSend,
+      HookName::OnResponse,
+    ] {
+      self.add(name, other.stage(name).to_vec());
+    }
+  }
+
+  pub fn stage(&self, name: HookName) -> &[Arc<dyn TachyonHandler>] {
+    match name {
+      HookName::OnRequest => &self.on_request,
+      HookName::PreHandler => &self.pre_handler,
+      HookName::OnSend => &self.on_send,
+      HookName::OnResponse => &self.on_response,
+    }
+  }
+
+  fn stage_mut(&mut self, name: HookName) -> &mut Vec<Arc<dyn TachyonHandler>> {
+    match name {
+      HookName::OnRequest => &mut self.on_request,
+      HookName::PreHandler => &mut self.pre_handler,
+      HookName::OnSend => &mut self.on_send,
+      HookName::OnResponse => &mut self.on_response,
+    }
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.on_request.is_empty()
+      && self.pre_handler.is_empty()
+      && self.on_send.is_empty()
+      && self.on_response.is_empty()
+  }
+}
+
+/// Global hooks followed by the ones of the matched route
+#[derive(Clone, Copy, Default)]
+pub struct HookSet<'a> {
+  pub global: Option<&'a Hooks>,
+  pub route: Option<&'a Hooks>,
+}
+
+impl<'a> HookSet<'a> {
+  pub fn is_empty(&self) -> bool {
+    self.global.is_none() && self.route.is_none()
+  }
+
+  pub fn stage(&self, name: HookName) -> impl Iterator<Item = &'a Arc<dyn TachyonHandler>> {
+    let global = self
+      .global
+      .map(|hooks| hooks.stage(name))
+      .unwrap_or_default();
+    let route = self
+      .route
+      .map(|hooks| hooks.stage(name))
+      .unwrap_or_default();
+    global.iter().chain(route)
+  }
+
+  /// Run the hooks of a stage in order
+  ///
+  /// `onRequest` and `preHandler` hooks stop the request once one of them
+  /// sends a response or fails, the other stages always run every hook.
+  pub async fn run(&self, name: HookName, req: &TachyonRequest, res: &TachyonResponse) {
+    let can_reply = matches!(name, HookName::OnRequest | HookName::PreHandler);
+    for hook in self.stage(name) {
+      hook.call(req.clone(), res.clone()).await;
+      if can_reply && (res.is_sent() || res.error().is_some()) {
+        break;
+      }
+    }
+  }
+}
+
+/// Response body calling `on_complete` once hyper is done with it
+///
+/// That is when the last byte was written, or when the client went away.
+pub struct CompletionBody {
+  inner: BoxBody<Bytes, BoxError>,
+  on_complete: Option<Box<dyn FnOnce() + Send + Sync>>,
+}
+
+impl CompletionBody {
+  pub fn wrap(
+    inner: BoxBody<Bytes, BoxError>,
+    on_complete: impl FnOnce() + Send + Sync + 'static,
+  ) -> BoxBody<Bytes, BoxError> {
+    Self {
+      inner,
+      on_complete: Some(Box::new(on_complete)),
+    }
+    .boxed()
+  }
+}
+
+impl Body for CompletionBody {
+  type Data = Bytes;
+  type Error = BoxError;
+
+  fn poll_frame(
+    mut self: Pin<&mut Self>,
+    cx: &mut Context<'_>,
+  ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+    Pin::new(&mut self.inner).poll_frame(cx)
+  }
+
+  fn is_end_stream(&self) -> bool {
+    self.inner.is_end_stream()
+  }
+
+  fn size_hint(&self) -> SizeHint {
+    self.inner.size_hint()
+  }
+}
+
+impl Drop for CompletionBody {
+  fn drop(&mut self) {
+    if let Some(on_complete) = self.on_complete.take() {
+      on_complete();
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::core::{methods::Method, router::HTTPCall};
+  use crate::testing::{call, get};
+  use crate::{Router, Tachyon};
+  use serde_json::json;
+
+  #[tokio::test]
+  async fn test_hooks_run_in_order() {
+    let tag = |text: &'static str| -> Arc<dyn TachyonHandler> {
+      Arc::new(move |_req, res: TachyonResponse| async move {
+        let seen = res
+          .get_header("x-seen")
+          .map(|seen| format!("{},{}", seen, text))
+          .unwrap_or_else(|| text.to_string());
+        res.set_header("x-seen", &seen).unwrap();
+      })
+    };
+    let reply: Arc<dyn TachyonHandler> = Arc::new(|_req, res: TachyonResponse| async move {
+      res.send(Some(json!("early")));
+    });
+
+    let mut global = Hooks::default();
+    global.add(HookName::OnRequest, vec![tag("global")]);
+    let mut route = Hooks::default();
+    route.add(
+      HookName::OnRequest,
+      vec![tag("route"), reply, tag("skipped")],
+    );
+    route.add(HookName::OnSend, vec![tag("send")]);
+
+    let hooks = HookSet {
+      global: Some(&global),
+      route: Some(&route),
+    };
+    let req = TachyonRequest::default();
+    let res = TachyonResponse::new();
+    hooks.run(HookName::OnRequest, &req, &res).await;
+    assert_eq!(res.get_header("x-seen").as_deref(), Some("global,route"));
+
+    // Later stages do not stop once the response is sent
+    hooks.run(HookName::OnSend, &req, &res).await;
+    assert_eq!(
+      res.get_header("x-seen").as_deref(),
+      Some("global,route,send")
+    );
+    assert!(HookSet::default().is_empty());
+  }
+
+  #[tokio::test]
+  async fn test_lifecycle_hooks() {
+    let app = Tachyon::new();
+    app
+      .route(
+        Method::Get,
+        "/users/:id",
+        |req: TachyonRequest, res: TachyonResponse| async move {
+          res.send(Some(json!({ "id": req.params["id"] })));
+        },
+      )
+      .unwrap();
+
+    // Hooks of a router only wrap the routes mounted from it
+    let admin = Router::new();
+    admin
+      .register("/", Method::Get, vec![Arc::new(|_req, _res| async move {})])
+      .unwrap();
+    admin.add_hooks(
+      HookName::OnRequest,
+      vec![Arc::new(|_req, res: TachyonResponse| async move {
+        res.status(401).send(Some("unauthorized".into()));
+      })],
+    );
+    app.mount("/admin", &admin).unwrap();
+
+    app.add_hooks(
+      HookName::OnSend,
+      vec![Arc::new(|_req, res: TachyonResponse| async move {
+        res.set_header("x-powered-by", "tachyon").unwrap();
+      })],
+    );
+    app
+      .add_route_hook(
+        "/users/:id",
+        Method::Get,
+        HookName::OnSend,
+        vec![Arc::new(|_req, res: TachyonResponse| async move {
+          let mut payload = res.payload().unwrap();
+          payload["wrapped"] = true.into();
+          res.send(Some(payload));
+        })],
+      )
+      .unwrap();
+
+    let (done, mut responded) = tokio::sync::mpsc::unbounded_channel();
+    app.add_hooks(
+      HookName::OnResponse,
+      vec![Arc::new(move |_req, res: TachyonResponse| {
+        let done = done.clone();
+        async move {
+          let _ = done.send((res.status_code(), res.elapsed_time() >= 0.0));
+        }
+      })],
+    );
+
+    let mut service = app.service();
+
+    let res = call(&mut service, get("/users/7")).await;
+    assert_eq!(res.status, hyper::StatusCode::OK);
+    assert_eq!(res.headers["x-powered-by"], "tachyon");
+    assert_eq!(res.text(), r#"{"id":"7","wrapped":true}"#);
+    assert_eq!(responded.recv().await, Some((200, true)));
+
+    let res = call(&mut service, get("/admin")).await;
+    assert_eq!(res.status, hyper::StatusCode::UNAUTHORIZED);
+    assert_eq!(res.headers["x-powered-by"], "tachyon");
+    assert_eq!(res.text(), "\"unauthorized\"");
+    assert_eq!(responded.recv().await, Some((401, true)));
+
+    // Global hooks see framework errors too
+    let res = call(&mut service, get("/missing")).await;
+    assert_eq!(res.headers["x-powered-by"], "tachyon");
+    assert_eq!(responded.recv().await, Some((404, true)));
+  }
+}
@@ -0,0 +1,250 @@
+use dashmap::DashMap;
+use std::sync::Arc;
+
+use crate::{
+  core::{
+    options::{ConflictPolicy, TachyonOptions},
+    pattern::MatchOptions,
+    router::{HTTPCall, TachyonRouter},
+  },
+  error::{Result, TachyonError},
+};
+
+/// One `.`-separated label of a host pattern
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Label {
+  Static(String),
+  /// `:name`, captured and exposed in `req.params`
+  Param(String),
+  /// `*`, any single label
+  Wildcard,
+}
+
+/// Host pattern such as `api.example.com`, `*.example.com` or `:tenant.example.com`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HostPattern {
+  raw: String,
+  labels: Vec<Label>,
+}
+
+impl HostPattern {
+  pub fn parse(host: &str) -> Result<Self> {
+    let invalid = |reason: &str| TachyonError::new(format!("Invalid host '{}': {}", host, reason));
+
+    if host.is_empty() {
+      return Err(invalid("it is empty"));
+    }
+
+    let mut labels = Vec::new();
+    for label in host.split('.') {
+      let label = if label == "*" {
+        Label::Wildcard
+      } else if let Some(name) = label.strip_prefix(':') {
+        if name.is_empty() || !name.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_') {
+          return Err(invalid(
+            "parameter names may only contain letters, digits and '_'",
+          ));
+        }
+        Label::Param(name.to_string())
+      } else if !label.is_empty()
+        && label
+          .bytes()
+          .all(|b| b.is_ascii_alphanumeric() || b == b'-')
+      {
+        Label::Static(label.to_ascii_lowercase())
+      } else {
+        return Err(invalid("labels may only contain letters, digits and '-'"));
+      };
+      labels.push(label);
+    }
+
+    Ok(Self {
+      raw: host.to_ascii_lowercase(),
+      labels,
+    })
+  }
+
+  pub fn as_str(&self) -> &str {
+    &self.raw
+  }
+
+  /// Whether the pattern only matches its own text
+  pub fn is_static(&self) -> bool {
+    self
+      .labels
+      .iter()
+      .all(|label| matches!(label, Label::Static(_)))
+  }
+
+  /// Captured `(name, label)` pairs when `host` matches, ignoring case
+  pub fn captures<'a>(&'a self, host: &'a str) -> Option<Vec<(&'a str, &'a str)>> {
+    let mut params = Vec::new();
+    let mut actual = host.split('.');
+
+    for label in &self.labels {
+      let value = actual.next().filter(|value| !value.is_empty())?;
+      match label {
+        Label::Static(text) if !text.eq_ignore_ascii_case(value) => return None,
+        Label::Param(name) => params.push((name.as_str(), value)),
+        _ => {}
+      }
+    }
+
+    actual.next().is_none().then_some(params)
+  }
+}
+
+/// Route table answering the requests sent to matching hosts
+#[derive(Clone)]
+pub struct VirtualHost {
+  pattern: HostPattern,
+  routes: Arc<DashMap<String, TachyonRouter>>,
+  options: Arc<TachyonOptions>,
+}
+
+impl VirtualHost {
+  pub fn new(pattern: HostPattern, options: Arc<TachyonOptions>) -> Self {
+    Self {
+      pattern,
+      routes: Arc::new(DashMap::new()),
+      options,
+    }
+  }
+
+  pub fn pattern(&self) -> &HostPattern {
+    &self.pattern
+  }
+}
+
+impl HTTPCall for VirtualHost {
+  fn route_table(&self) -> Arc<DashMap<String, TachyonRouter>> {
+    Arc::clone(&self.routes)
+  }
+
+  fn conflict_policy(&self) -> Option<ConflictPolicy> {
+    self.options.conflict_policy()
+  }
+
+  fn match_options(&self) -> MatchOptions {
+    self.options.match_options()
+  }
+}
+
+/// Host of a request without its port, from the URI authority or `Host` header
+pub fn request_host<B>(req: &hyper::Request<B>) -> Option<&str> {
+  let host = match req.uri().host() {
+    Some(host) => host,
+    None => req.headers().get(hyper::header::HOST)?.to_str().ok()?,
+  };
+
+  // `[::1]:8080` keeps its brackets, `example.com:8080` loses the port
+  let host = match host.rfind(':') {
+    Some(colon) if !host[colon..].contains(']') => &host[..colon],
+    _ => host,
+  };
+  Some(host.trim_end_matches('.'))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::core::{methods::Method, router::HTTPCall};
+  use crate::testing::call;
+  use crate::{Router, Tachyon, TachyonRequest, TachyonResponse};
+  use bytes::Bytes;
+  use http_body_util::Empty;
+  use hyper::Request;
+
+  fn captures(pattern: &str, host: &str) -> Option<Vec<(String, String)>> {
+    let pattern = HostPattern::parse(pattern).unwrap();
+    pattern.captures(host).map(|params| {
+      params
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect()
+    })
+  }
+
+  #[test]
+  fn test_host_patterns() {
+    assert_eq!(captures("api.example.com", "API.example.com"), Some(vec![]));
+    assert_eq!(captures("api.example.com", "admin.example.com"), None);
+    assert_eq!(captures("*.example.com", "admin.example.com"), Some(vec![]));
+    assert_eq!(captures("*.example.com", "example.com"), None);
+    assert_eq!(captures("*.example.com", "a.b.example.com"), None);
+    assert_eq!(
+      captures(":tenant.example.com", "acme.example.com"),
+      Some(vec![("tenant".to_string(), "acme".to_string())])
+    );
+
+    assert!(HostPattern::parse("").is_err());
+    assert!(HostPattern::parse("api..com").is_err());
+    assert!(HostPattern::parse(":.example.com").is_err());
+    assert!(HostPattern::parse("api_v1.example.com").is_err());
+  }
+
+  #[test]
+  fn test_request_host() {
+    let host = |value: &str| {
+      let req = hyper::Request::get("/")
+        .header(hyper::header::HOST, value)
+        .body(())
+        .unwrap();
+      request_host(&req).map(str::to_string)
+    };
+    assert_eq!(host("example.com:8080").as_deref(), Some("example.com"));
+    assert_eq!(host("example.com.").as_deref(), Some("example.com"));
+    assert_eq!(host("[::1]:3000").as_deref(), Some("[::1]"));
+
+    let req = hyper::Request::get("http://api.example.com/users")
+      .body(())
+      .unwrap();
+    assert_eq!(request_host(&req), Some("api.example.com"));
+  }
+
+  #[tokio::test]
+  async fn test_serve_virtual_hosts() {
+    let app = Tachyon::new();
+    let reply = |text: &'static str| {
+      move |req: TachyonRequest, res: TachyonResponse| async move {
+        let tenant = req.params.get("tenant").cloned().unwrap_or_default();
+        res.send(Some(format!("{}{}", text, tenant).into()));
+      }
+    };
+    app.route(Method::Get, "/", reply("main")).unwrap();
+
+    let api = Router::new();
+    api
+      .register("/", Method::Get, vec![Arc::new(reply("api"))])
+      .unwrap();
+    app.mount_host("api.example.com", &api).unwrap();
+
+    let tenants = Router::new();
+    tenants
+      .register("/", Method::Get, vec![Arc::new(reply("tenant:"))])
+      .unwrap();
+    app.mount_host(":tenant.example.com", &tenants).unwrap();
+
+    let mut service = app.service();
+    let get = |host: &str, uri: &str| {
+      Request::get(uri)
+        .header(hyper::header::HOST, host)
+        .body(Empty::<Bytes>::new())
+        .unwrap()
+    };
+
+    let res = call(&mut service, get("localhost:3000", "/")).await;
+    assert_eq!(res.text(), "\"main\"");
+    let res = call(&mut service, get("API.example.com:443", "/")).await;
+    assert_eq!(res.text(), "\"api\"");
+    let res = call(&mut service, get("acme.example.com", "/")).await;
+    assert_eq!(res.text(), "\"tenant:acme\"");
+    let res = call(&mut service, get("api.example.com", "/missing")).await;
+    assert_eq!(res.status, hyper::StatusCode::NOT_FOUND);
+
+    assert_eq!(
+      app.routes(),
+      vec!["/ GET", "api.example.com/ GET", ":tenant.example.com/ GET"]
+    );
+  }
+}
@@ -0,0 +1,111 @@
+use napi::{
+  bindgen_prelude::{
+    FromNapiValue, Function, FunctionRef, JsValuesTupleIntoVec, Object, ObjectRef,
+  },
+  threadsafe_function::{ThreadsafeCallContext, ThreadsafeFunction, ThreadsafeFunctionCallMode},
+  Env, JsValue, Result, Status,
+};
+use std::{collections::HashMap, sync::Arc};
+
+/// Work for the JS thread of an env, queued from any thread
+type JsTask = Box<dyn FnOnce(&Env) -> Result<()> + Send>;
+
+/// Runs queued tasks on the JS thread without keeping the event loop alive
+type JsTaskQueue = ThreadsafeFunction<JsTask, (), (), Status, false, true>;
+
+/// State of the addon kept in the instance data of each env
+struct Instance {
+  tasks: Arc<JsTaskQueue>,
+  /// Functions evaluated by [`script`], by source
+  scripts: HashMap<&'static str, FunctionRef<(), ()>>,
+}
+
+impl Instance {
+  fn get(env: &Env) -> Result<&'static mut Instance> {
+    if let Some(instance) = env.get_instance_data::<Instance>()? {
+      return Ok(instance);
+    }
+
+    let run: Function<(), ()> = env.create_function_from_closure("run", |_| Ok(()))?;
+    let tasks = run
+      .build_threadsafe_function::<JsTask>()
+      .weak::<true>()
+      .build_callback(|ctx: ThreadsafeCallContext<JsTask>| {
+        // Nobody is left to report the error to
+        let _ = (ctx.value)(&ctx.env);
+        Ok(())
+      })?;
+    env.set_instance_data(
+      Instance {
+        tasks: Arc::new(tasks),
+        scripts: HashMap::new(),
+      },
+      (),
+      |_| {},
+    )?;
+    env
+      .get_instance_data::<Instance>()?
+      .ok_or_else(|| napi::Error::from_reason("Addon instance data is unavailable"))
+  }
+}
+
+/// Function defined by the JS expression `source`, evaluated once per env
+pub(crate) fn script<'env, Args, Return>(
+  env: &'env Env,
+  source: &'static str,
+) -> Result<Function<'env, Args, Return>>
+where
+  Args: JsValuesTupleIntoVec,
+  Return: FromNapiValue,
+{
+  let instance = Instance::get(env)?;
+  let function = match instance.scripts.get(source) {
+    Some(function) => function.borrow_back(env)?,
+    None => {
+      let function: Function<(), ()> = env.run_script(source)?;
+      instance.scripts.insert(source, function.create_ref()?);
+      function
+    }
+  };
+  unsafe { Function::from_napi_value(env.raw(), function.raw()) }
+}
+
+/// Strong reference keeping a JS object alive across calls into JS
+///
+/// Only the JS thread of its env can delete a reference, a dropped one is
+/// handed to that thread through a threadsafe function of the env.
+pub(crate) struct JsRef {
+  object: Option<ObjectRef<false>>,
+  tasks: Arc<JsTaskQueue>,
+}
+
+impl JsRef {
+  pub fn new(env: &Env, object: &Object) -> Result<Self> {
+    Ok(Self {
+      object: Some(object.create_ref::<false>()?),
+      tasks: Arc::clone(&Instance::get(env)?.tasks),
+    })
+  }
+
+  pub fn get<'env>(&self, env: &'env Env) -> Result<Object<'env>> {
+    match &self.object {
+      Some(object) => object.get_value(env),
+      None => Err(napi::Error::from_reason("JS reference was released")),
+    }
+  }
+
+  /// Run `task` on the JS thread of the env owning the object, from any thread
+  pub fn queue(&self, task: impl FnOnce(&Env) -> Result<()> + Send + 'static) {
+    self
+      .tasks
+      .call(Box::new(task), ThreadsafeFunctionCallMode::NonBlocking);
+  }
+}
+
+impl Drop for JsRef {
+  fn drop(&mut self) {
+    if let Some(object) = self.object.take() {
+      self.queue(move |env| object.unref(env));
+    }
+  }
+}
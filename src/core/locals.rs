@@ -0,0 +1,181 @@
+use hyper::http::Extensions;
+use std::{
+  fmt,
+  sync::{Arc, Mutex},
+};
+
+/// Values attached to a single request, shared by its middleware, hooks and handler
+///
+/// Native code stores typed values, keyed by their type like `http::Extensions`.
+/// JS code sees `req.locals` and `res.locals`, the same plain object for every
+/// callback of the request, created on first use.
+#[derive(Clone, Default)]
+pub struct Locals {
+  inner: Arc<LocalsInner>,
+}
+
+#[derive(Default)]
+struct LocalsInner {
+  extensions: Mutex<Extensions>,
+  #[cfg(feature = "napi")]
+  object: Mutex<Option<crate::core::js_ref::JsRef>>,
+}
+
+impl Locals {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Store a value, returning the previous one of the same type
+  ///
+  /// ```ignore
+  /// #[derive(Clone)]
+  /// struct User { id: u64 }
+  ///
+  /// req.locals.insert(User { id: 7 });
+  /// let user = req.locals.get::<User>();
+  /// ```
+  pub fn insert<T: Clone + Send + Sync + 'static>(&self, value: T) -> Option<T> {
+    self.inner.extensions.lock().ok()?.insert(value)
+  }
+
+  pub fn get<T: Clone + Send + Sync + 'static>(&self) -> Option<T> {
+    self.inner.extensions.lock().ok()?.get::<T>().cloned()
+  }
+
+  pub fn remove<T: Clone + Send + Sync + 'static>(&self) -> Option<T> {
+    self.inner.extensions.lock().ok()?.remove::<T>()
+  }
+
+  pub fn contains<T: Clone + Send + Sync + 'static>(&self) -> bool {
+    self
+      .inner
+      .extensions
+      .lock()
+      .map(|extensions| extensions.get::<T>().is_some())
+      .unwrap_or(false)
+  }
+
+  /// Whether both handles belong to the same request
+  pub fn ptr_eq(&self, other: &Locals) -> bool {
+    Arc::ptr_eq(&self.inner, &other.inner)
+  }
+}
+
+impl fmt::Debug for Locals {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("Locals").finish_non_exhaustive()
+  }
+}
+
+#[cfg(feature = "napi")]
+mod js {
+  use napi::{
+    bindgen_prelude::{FromNapiValue, Object, ToNapiValue, TypeName, ValidateNapiValue},
+    sys, Env, JsValue, Result, ValueType,
+  };
+
+  use super::Locals;
+  use crate::core::js_ref::JsRef;
+
+  impl Locals {
+    /// The JS object of the request, created on first use
+    pub fn object<'env>(&self, env: &'env Env) -> Result<Object<'env>> {
+      let mut slot = self
+        .inner
+        .object
+        .lock()
+        .map_err(|_| napi::Error::from_reason("Request locals are unavailable"))?;
+      if let Some(object) = slot.as_ref() {
+        return object.get(env);
+      }
+
+      let object = Object::new(env)?;
+      *slot = Some(JsRef::new(env, &object)?);
+      Ok(object)
+    }
+  }
+
+  impl TypeName for Locals {
+    fn type_name() -> &'static str {
+      "Record<string, any>"
+    }
+
+    fn value_type() -> ValueType {
+      ValueType::Object
+    }
+  }
+
+  impl ValidateNapiValue for Locals {}
+
+  impl ToNapiValue for Locals {
+    unsafe fn to_napi_value(env: sys::napi_env, val: Self) -> Result<sys::napi_value> {
+      let env = Env::from_raw(env);
+      Ok(val.object(&env)?.raw())
+    }
+  }
+
+  /// Objects coming back from JS are not tied to a request
+  impl FromNapiValue for Locals {
+    unsafe fn from_napi_value(_env: sys::napi_env, _napi_val: sys::napi_value) -> Result<Self> {
+      Ok(Locals::new())
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::core::{methods::Method, router::HTTPCall};
+  use crate::testing::{call, get};
+  use crate::{Tachyon, TachyonHandler, TachyonRequest, TachyonResponse};
+
+  #[derive(Debug, Clone, PartialEq)]
+  struct User {
+    id: u64,
+  }
+
+  #[test]
+  fn test_typed_locals() {
+    let locals = Locals::new();
+    let shared = locals.clone();
+    assert!(shared.ptr_eq(&locals));
+    assert!(!Locals::new().ptr_eq(&locals));
+
+    assert_eq!(locals.insert(User { id: 7 }), None);
+    assert_eq!(shared.get::<User>(), Some(User { id: 7 }));
+    assert!(shared.contains::<User>());
+    assert!(!shared.contains::<String>());
+
+    assert_eq!(shared.insert(User { id: 8 }), Some(User { id: 7 }));
+    assert_eq!(locals.remove::<User>(), Some(User { id: 8 }));
+    assert_eq!(shared.get::<User>(), None);
+  }
+
+  #[tokio::test]
+  async fn test_locals_shared_by_request() {
+    #[derive(Clone)]
+    struct User(&'static str);
+
+    let app = Tachyon::new();
+    let authenticate: Arc<dyn TachyonHandler> = Arc::new(|req: TachyonRequest, _res| async move {
+      req.locals.insert(User("ann"));
+    });
+    let greet: Arc<dyn TachyonHandler> = Arc::new(|_req, res: TachyonResponse| async move {
+      let user = res.locals().get::<User>().map(|user| user.0);
+      res.send(Some(user.unwrap_or("nobody").into()));
+    });
+    app
+      .register("/me", Method::Get, vec![authenticate, greet.clone()])
+      .unwrap();
+    app
+      .register("/anonymous", Method::Get, vec![greet])
+      .unwrap();
+
+    let mut service = app.service();
+    assert_eq!(call(&mut service, get("/me")).await.text(), "\"ann\"");
+    // Nothing leaks from one request to the next
+    let res = call(&mut service, get("/anonymous")).await;
+    assert_eq!(res.text(), "\"nobody\"");
+  }
+}
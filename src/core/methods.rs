@@ -1,3 +1,5 @@
+use crate::error::TachyonError;
+
 pub enum Method {
   Get,
   Post,
@@ -18,6 +20,18 @@ impl Method {
     }
   }
 
+  /// Supported method of a request, `None` for HEAD, OPTIONS and the like
+  pub fn from_http(method: &hyper::Method) -> Option<Self> {
+    match *method {
+      hyper::Method::GET => Some(Method::Get),
+      hyper::Method::POST => Some(Method::Post),
+      hyper::Method::PUT => Some(Method::Put),
+      hyper::Method::DELETE => Some(Method::Delete),
+      hyper::Method::PATCH => Some(Method::Patch),
+      _ => None,
+    }
+  }
+
   pub fn id(&self) -> u8 {
     match self {
       Method::Get => 0,
@@ -73,6 +87,23 @@ impl From<hyper::Method> for Method {
     }
   }
 }
+
+impl std::str::FromStr for Method {
+  type Err = TachyonError;
+
+  /// Parse a method name such as `"GET"` or `"post"`
+  fn from_str(name: &str) -> Result<Self, Self::Err> {
+    match name.to_ascii_uppercase().as_str() {
+      "GET" => Ok(Method::Get),
+      "POST" => Ok(Method::Post),
+      "PUT" => Ok(Method::Put),
+      "DELETE" => Ok(Method::Delete),
+      "PATCH" => Ok(Method::Patch),
+      _ => Err(TachyonError::new(format!("Unsupported method '{}'", name))),
+    }
+  }
+}
+
 impl std::fmt::Display for Method {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
     let s = match self {
@@ -1,7 +1,23 @@
+pub mod files;
+pub mod group;
+pub mod hooks;
+pub mod host;
+#[cfg(feature = "napi")]
+mod js_ref;
+pub mod locals;
+pub mod methods;
+pub mod options;
+pub mod path;
+pub mod pattern;
 pub mod request;
 pub mod response;
 pub mod router;
+pub mod service;
+pub mod signal;
+pub mod sse;
+pub mod stream;
 pub mod tachyon;
+pub mod websocket;
 
-mod methods;
+#[cfg(feature = "napi")]
 mod wrapper;
@@ -0,0 +1,230 @@
+#[cfg(feature = "napi")]
+use napi_derive::napi;
+use std::time::Duration;
+
+use crate::core::pattern::MatchOptions;
+
+/// What to do when a new route duplicates or shadows an existing one
+#[cfg_attr(feature = "napi", napi(string_enum = "lowercase"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictPolicy {
+  /// Fail the registration
+  Throw,
+  /// Print a warning and register the route anyway
+  Warn,
+}
+
+/// How static files treat paths with a segment starting with a dot
+#[cfg_attr(feature = "napi", napi(string_enum = "lowercase"))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Dotfiles {
+  /// Serve them like any other file
+  Allow,
+  /// Answer with 403
+  Deny,
+  /// Answer with 404, as if they did not exist
+  #[default]
+  Ignore,
+}
+
+/// Settings passed to `tachyon()` when creating an app
+#[cfg_attr(feature = "napi", napi(object))]
+#[derive(Debug, Clone, Default)]
+pub struct TachyonOptions {
+  /// Reaction to duplicate or ambiguous routes. By default a route registered
+  /// twice throws and an overlapping pattern prints a warning
+  pub route_conflicts: Option<ConflictPolicy>,
+  /// Collapse `//` into a single `/` before routing, `true` by default
+  pub merge_slashes: Option<bool>,
+  /// Accept `%2F` inside parameter values, `true` by default
+  pub allow_encoded_slashes: Option<bool>,
+  /// Treat `/users` and `/users/` as different routes, `true` by default
+  pub strict_trailing_slash: Option<bool>,
+  /// Redirect to the registered form of a path that only differs by its
+  /// trailing slash, 301 for GET and 308 otherwise. `false` by default
+  pub redirect_trailing_slash: Option<bool>,
+  /// Match static segments with the same case only, `true` by default
+  pub case_sensitive: Option<bool>,
+  /// Largest accepted request body in bytes, bigger bodies are answered
+  /// with 413. No limit by default
+  pub body_limit: Option<u32>,
+  /// Milliseconds the handlers of a route may take before the request is
+  /// answered with 503 and `req.signal` aborts. No limit by default
+  pub handler_timeout: Option<u32>,
+}
+
+/// Settings of a single route, passed after its handlers
+#[cfg_attr(feature = "napi", napi(object))]
+#[derive(Debug, Clone, Default)]
+pub struct RouteOptions {
+  /// Unique name used to build links with `url`
+  pub name: Option<String>,
+  /// Free-form data listed by `describeRoutes`, e.g. a description or tags
+  pub meta: Option<serde_json::Value>,
+}
+
+/// Extra parts of a link built by `url`
+#[cfg(feature = "napi")]
+#[napi(object)]
+#[derive(Debug, Clone, Default)]
+pub struct UrlOptions {
+  /// Appended as a percent-encoded query string, arrays repeat the key
+  pub query: Option<serde_json::Map<String, serde_json::Value>>,
+}
+
+/// Settings of a Server-Sent Events stream opened with `res.sse()`
+#[cfg_attr(feature = "napi", napi(object))]
+#[derive(Debug, Clone, Default)]
+pub struct SseOptions {
+  /// Milliseconds without events before a keep-alive comment is sent, 15000 by default, 0 to disable
+  pub keep_alive: Option<u32>,
+}
+
+impl SseOptions {
+  pub fn keep_alive(&self) -> Option<Duration> {
+    match self.keep_alive.unwrap_or(15_000) {
+      0 => None,
+      millis => Some(Duration::from_millis(millis as u64)),
+    }
+  }
+}
+
+/// Limits of the connections accepted by `app.ws`
+#[cfg_attr(feature = "napi", napi(object))]
+#[derive(Debug, Clone, Default)]
+pub struct WebSocketOptions {
+  /// Largest message in bytes, 1 MiB by default. Bigger ones close the connection with 1009
+  pub max_message_size: Option<u32>,
+  /// Milliseconds between pings, 30000 by default, 0 to disable. Clients missing one are dropped
+  pub ping_interval: Option<u32>,
+}
+
+/// Settings of the files served by `app.static`
+#[cfg_attr(feature = "napi", napi(object))]
+#[derive(Debug, Clone, Default)]
+pub struct StaticOptions {
+  /// Serve `index.html` for directories, `true` by default
+  pub index: Option<bool>,
+  /// Reaction to paths such as `/.env` or `/.git/config`, `"ignore"` by default
+  pub dotfiles: Option<Dotfiles>,
+  /// Seconds browsers may reuse a file without checking, 0 by default
+  pub max_age: Option<u32>,
+  /// Add `immutable` to `Cache-Control`, for fingerprinted file names
+  pub immutable: Option<bool>,
+  /// Send an `ETag` and answer `If-None-Match` with 304, `true` by default
+  pub etag: Option<bool>,
+  /// Send `Last-Modified` and answer `If-Modified-Since` with 304, `true` by default
+  pub last_modified: Option<bool>,
+}
+
+impl StaticOptions {
+  pub fn index(&self) -> bool {
+    self.index.unwrap_or(true)
+  }
+
+  pub fn dotfiles(&self) -> Dotfiles {
+    self.dotfiles.unwrap_or_default()
+  }
+
+  pub fn cache_control(&self) -> String {
+    cache_control(self.max_age, self.immutable)
+  }
+
+  pub fn etag(&self) -> bool {
+    self.etag.unwrap_or(true)
+  }
+
+  pub fn last_modified(&self) -> bool {
+    self.last_modified.unwrap_or(true)
+  }
+}
+
+/// Settings of a file sent with `res.sendFile` or `res.download`
+#[cfg_attr(feature = "napi", napi(object))]
+#[derive(Debug, Clone, Default)]
+pub struct SendFileOptions {
+  /// Directory relative paths are resolved in, they may not leave it
+  pub root: Option<String>,
+  /// Seconds browsers may reuse the file without checking, 0 by default
+  pub max_age: Option<u32>,
+  /// Add `immutable` to `Cache-Control`
+  pub immutable: Option<bool>,
+  /// Send an `ETag` and answer `If-None-Match` with 304, `true` by default
+  pub etag: Option<bool>,
+  /// Send `Last-Modified` and answer `If-Modified-Since` with 304, `true` by default
+  pub last_modified: Option<bool>,
+}
+
+impl SendFileOptions {
+  pub fn cache_control(&self) -> String {
+    cache_control(self.max_age, self.immutable)
+  }
+
+  pub fn etag(&self) -> bool {
+    self.etag.unwrap_or(true)
+  }
+
+  pub fn last_modified(&self) -> bool {
+    self.last_modified.unwrap_or(true)
+  }
+}
+
+fn cache_control(max_age: Option<u32>, immutable: Option<bool>) -> String {
+  let mut cache_control = format!("public, max-age={}", max_age.unwrap_or(0));
+  if immutable.unwrap_or(false) {
+    cache_control.push_str(", immutable");
+  }
+  cache_control
+}
+
+impl WebSocketOptions {
+  pub fn max_message_size(&self) -> usize {
+    self.max_message_size.unwrap_or(1024 * 1024) as usize
+  }
+
+  pub fn ping_interval(&self) -> Option<Duration> {
+    match self.ping_interval.unwrap_or(30_000) {
+      0 => None,
+      millis => Some(Duration::from_millis(millis as u64)),
+    }
+  }
+}
+
+impl TachyonOptions {
+  pub fn conflict_policy(&self) -> Option<ConflictPolicy> {
+    self.route_conflicts
+  }
+
+  pub fn merge_slashes(&self) -> bool {
+    self.merge_slashes.unwrap_or(true)
+  }
+
+  pub fn allow_encoded_slashes(&self) -> bool {
+    self.allow_encoded_slashes.unwrap_or(true)
+  }
+
+  pub fn redirect_trailing_slash(&self) -> bool {
+    self.redirect_trailing_slash.unwrap_or(false)
+  }
+
+  pub fn body_limit(&self) -> Option<usize> {
+    self.body_limit.map(|limit| limit as usize)
+  }
+
+  pub fn handler_timeout(&self) -> Option<Duration> {
+    match self.handler_timeout.unwrap_or(0) {
+      0 => None,
+      millis => Some(Duration::from_millis(millis as u64)),
+    }
+  }
+
+  pub fn match_options(&self) -> MatchOptions {
+    let defaults = MatchOptions::default();
+    MatchOptions {
+      strict_trailing_slash: self
+        .strict_trailing_slash
+        .unwrap_or(defaults.strict_trailing_slash),
+      case_sensitive: self.case_sensitive.unwrap_or(defaults.case_sensitive),
+    }
+  }
+}
@@ -0,0 +1,185 @@
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use std::{borrow::Cow, collections::HashMap};
+
+use crate::error::{Result, TachyonError};
+
+/// Characters left as is by `encodeURIComponent`
+const COMPONENT: &AsciiSet = &NON_ALPHANUMERIC
+  .remove(b'-')
+  .remove(b'_')
+  .remove(b'.')
+  .remove(b'!')
+  .remove(b'~')
+  .remove(b'*')
+  .remove(b'\'')
+  .remove(b'(')
+  .remove(b')');
+
+/// Percent-encode a path segment or query component, `/` included
+pub fn encode_component(value: &str) -> Cow<'_, str> {
+  utf8_percent_encode(value, COMPONENT).into()
+}
+
+/// Resolve `.` and `..` segments and optionally collapse duplicate slashes
+///
+/// Dot segments are recognised in their percent-encoded form too, and a `..`
+/// climbing above the root is rejected. Paths that need no work are borrowed.
+pub fn normalize_path(path: &str, merge_slashes: bool) -> Result<Cow<'_, str>> {
+  let has_dots = path.contains("/.") || contains_ignore_case(path, "%2e");
+  let has_empty = merge_slashes && path.contains("//");
+  if !has_dots && !has_empty {
+    return Ok(Cow::Borrowed(path));
+  }
+
+  let raw_segments: Vec<&str> = path.strip_prefix('/').unwrap_or(path).split('/').collect();
+  let mut segments: Vec<&str> = Vec::with_capacity(raw_segments.len());
+
+  for (index, segment) in raw_segments.iter().enumerate() {
+    let is_last = index + 1 == raw_segments.len();
+
+    match dot_segment(segment) {
+      Some(dots) => {
+        if dots == 2 && segments.pop().is_none() {
+          return Err(TachyonError::new("Path escapes the root directory"));
+        }
+        // `/a/b/..` resolves to the directory `/a/`
+        if is_last {
+          segments.push("");
+        }
+      }
+      None if segment.is_empty() && merge_slashes && !is_last => {}
+      None => segments.push(segment),
+    }
+  }
+
+  Ok(Cow::Owned(format!("/{}", segments.join("/"))))
+}
+
+/// Percent-decode a captured parameter value
+///
+/// Invalid UTF-8 is always rejected, encoded slashes only when
+/// `allow_encoded_slashes` is off, and so are values decoding to a `..` segment.
+pub fn decode_param(value: &str, allow_encoded_slashes: bool) -> Result<Cow<'_, str>> {
+  if !value.contains('%') {
+    return Ok(Cow::Borrowed(value));
+  }
+
+  if !allow_encoded_slashes && contains_ignore_case(value, "%2f") {
+    return Err(TachyonError::new(
+      "Encoded slashes are not allowed in paths",
+    ));
+  }
+
+  let decoded = percent_decode_str(value)
+    .decode_utf8()
+    .map_err(|_| TachyonError::new("Invalid UTF-8 in path"))?;
+
+  if decoded.split(['/', '\\']).any(|segment| segment == "..") {
+    return Err(TachyonError::new("Path escapes the root directory"));
+  }
+
+  Ok(decoded)
+}
+
+/// Values of a query string by key, the last one winning, `+` standing for a space
+pub fn parse_query(query: &str) -> HashMap<String, String> {
+  let decode = |part: &str| {
+    let part = part.replace('+', " ");
+    percent_decode_str(&part).decode_utf8_lossy().into_owned()
+  };
+  query
+    .split('&')
+    .filter(|pair| !pair.is_empty())
+    .map(|pair| {
+      let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+      (decode(key), decode(value))
+    })
+    .collect()
+}
+
+/// `Some(1)` for `.`, `Some(2)` for `..`, encoded or not
+fn dot_segment(segment: &str) -> Option<u8> {
+  let rest = segment
+    .strip_prefix('.')
+    .or_else(|| strip_encoded_dot(segment))?;
+  if rest.is_empty() {
+    return Some(1);
+  }
+  let rest = rest.strip_prefix('.').or_else(|| strip_encoded_dot(rest))?;
+  rest.is_empty().then_some(2)
+}
+
+fn strip_encoded_dot(segment: &str) -> Option<&str> {
+  segment
+    .get(..3)
+    .filter(|prefix| prefix.eq_ignore_ascii_case("%2e"))
+    .map(|_| &segment[3..])
+}
+
+fn contains_ignore_case(haystack: &str, needle: &str) -> bool {
+  haystack
+    .as_bytes()
+    .windows(needle.len())
+    .any(|window| window.eq_ignore_ascii_case(needle.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn normalize(path: &str) -> Option<String> {
+    normalize_path(path, true).ok().map(Cow::into_owned)
+  }
+
+  #[test]
+  fn test_normalize_path() {
+    assert!(matches!(
+      normalize_path("/users/1", true),
+      Ok(Cow::Borrowed("/users/1"))
+    ));
+    assert_eq!(normalize("/users//1").as_deref(), Some("/users/1"));
+    assert_eq!(normalize("/users/./1").as_deref(), Some("/users/1"));
+    assert_eq!(normalize("/a/../users/1").as_deref(), Some("/users/1"));
+    assert_eq!(normalize("/a/%2E%2e/users").as_deref(), Some("/users"));
+    assert_eq!(normalize("/users/1/..").as_deref(), Some("/users/"));
+    assert_eq!(normalize("/users/").as_deref(), Some("/users/"));
+    assert_eq!(normalize("/.well-known").as_deref(), Some("/.well-known"));
+    assert_eq!(normalize("/..").as_deref(), None);
+    assert_eq!(normalize("/a/../../etc/passwd").as_deref(), None);
+    assert_eq!(normalize("/a/.%2e/%2E./b").as_deref(), None);
+
+    assert_eq!(
+      normalize_path("/users//1", false).unwrap(),
+      Cow::Borrowed("/users//1")
+    );
+  }
+
+  #[test]
+  fn test_encode_component() {
+    assert_eq!(encode_component("john doe"), "john%20doe");
+    assert_eq!(encode_component("a/b?c=d&e#f"), "a%2Fb%3Fc%3Dd%26e%23f");
+    assert_eq!(encode_component("café"), "caf%C3%A9");
+    assert!(matches!(encode_component("plain-id_1"), Cow::Borrowed(_)));
+  }
+
+  #[test]
+  fn test_decode_param() {
+    assert_eq!(decode_param("john%20doe", true).unwrap(), "john doe");
+    assert_eq!(decode_param("caf%C3%A9", true).unwrap(), "café");
+    assert_eq!(decode_param("a%2Fb", true).unwrap(), "a/b");
+    assert!(decode_param("a%2fb", false).is_err());
+    assert!(decode_param("%FF", true).is_err());
+    assert!(decode_param("..%2F..%2Fetc", true).is_err());
+    assert!(decode_param("..%5Cwin.ini", true).is_err());
+  }
+
+  #[test]
+  fn test_parse_query() {
+    let query = parse_query("room=general&name=ann+lee&emoji=%F0%9F%91%8B&flag&room=dev");
+    assert_eq!(query["room"], "dev");
+    assert_eq!(query["name"], "ann lee");
+    assert_eq!(query["emoji"], "👋");
+    assert_eq!(query["flag"], "");
+    assert!(parse_query("").is_empty());
+  }
+}
@@ -0,0 +1,799 @@
+use regex::Regex;
+use std::{borrow::Cow, cmp::Ordering, collections::HashMap};
+
+use crate::{
+  core::path::encode_component,
+  error::{Result, TachyonError},
+};
+
+/// One `/`-separated piece of a route pattern
+#[derive(Debug, Clone)]
+pub enum Segment {
+  /// Literal text that must match exactly
+  Static(String),
+  /// `:name`, `:name(regex)`, `:name?` or `:name(regex)?`
+  Param {
+    name: String,
+    constraint: Option<Regex>,
+    optional: bool,
+  },
+  /// `*` or `*name`, takes the rest of the path
+  CatchAll(String),
+}
+
+impl Segment {
+  /// Specificity rank, lower wins when several patterns match the same path
+  fn rank(&self) -> u8 {
+    match self {
+      Segment::Static(_) => 0,
+      Segment::Param {
+        constraint: Some(_),
+        ..
+      } => 1,
+      Segment::Param { .. } => 2,
+      Segment::CatchAll(_) => 3,
+    }
+  }
+
+  fn is_optional(&self) -> bool {
+    matches!(self, Segment::Param { optional: true, .. })
+  }
+
+  /// Whether both segments accept exactly the same values, names aside
+  fn same_shape(&self, other: &Segment, case_sensitive: bool) -> bool {
+    match (self, other) {
+      (Segment::Static(a), Segment::Static(b)) => eq_static(a, b, case_sensitive),
+      (Segment::Param { constraint: a, .. }, Segment::Param { constraint: b, .. }) => {
+        a.as_ref().map(Regex::as_str) == b.as_ref().map(Regex::as_str)
+      }
+      (Segment::CatchAll(_), Segment::CatchAll(_)) => true,
+      _ => false,
+    }
+  }
+
+  /// Whether a value could go to either segment, which specificity only
+  /// settles between a static segment, a param and a catch-all
+  fn overlaps(&self, other: &Segment, case_sensitive: bool) -> bool {
+    match (self, other) {
+      (Segment::Static(a), Segment::Static(b)) => eq_static(a, b, case_sensitive),
+      (Segment::Param { constraint: a, .. }, Segment::Param { constraint: b, .. }) => {
+        !disjoint(a.as_ref(), b.as_ref())
+      }
+      (Segment::CatchAll(_), Segment::CatchAll(_)) => true,
+      _ => false,
+    }
+  }
+}
+
+/// How request paths are compared with patterns
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MatchOptions {
+  /// `/users` and `/users/` are different routes
+  pub strict_trailing_slash: bool,
+  /// Static segments must match with the same case
+  pub case_sensitive: bool,
+}
+
+impl Default for MatchOptions {
+  fn default() -> Self {
+    Self {
+      strict_trailing_slash: true,
+      case_sensitive: true,
+    }
+  }
+}
+
+/// Route pattern parsed and compiled once at registration time
+///
+/// Supports static segments, `:param` segments with an optional regex
+/// constraint (`/users/:id(\d+)`), optional trailing params (`/posts/:slug?`)
+/// and a final catch-all (`/static/*`, `/files/*path`).
+#[derive(Debug, Clone)]
+pub struct RoutePattern {
+  raw: String,
+  segments: Vec<Segment>,
+  is_static: bool,
+}
+
+impl RoutePattern {
+  pub fn parse(route: &str) -> Result<Self> {
+    let invalid =
+      |reason: String| TachyonError::new(format!("Invalid route '{}': {}", route, reason));
+
+    if !route.starts_with('/') {
+      return Err(invalid("it must start with '/'".to_string()));
+    }
+
+    let raw_segments = split_segments(route).map_err(invalid)?;
+    let mut segments = Vec::with_capacity(raw_segments.len());
+    let mut names: Vec<&str> = Vec::new();
+
+    for (index, raw) in raw_segments.iter().enumerate() {
+      let is_last = index + 1 == raw_segments.len();
+
+      let segment = if let Some(name) = raw.strip_prefix('*') {
+        if !is_last {
+          return Err(invalid(format!(
+            "catch-all segment '{}' must be the last one",
+            raw
+          )));
+        }
+        if !name.is_empty() {
+          check_name(name).map_err(invalid)?;
+        }
+        Segment::CatchAll(if name.is_empty() { "*" } else { name }.to_string())
+      } else if let Some(spec) = raw.strip_prefix(':') {
+        let (spec, optional) = match spec.strip_suffix('?') {
+          Some(spec) => (spec, true),
+          None => (spec, false),
+        };
+
+        let (name, constraint) = match spec.find('(') {
+          Some(open) if spec.ends_with(')') => {
+            let source = &spec[open + 1..spec.len() - 1];
+            let regex = Regex::new(&format!("^(?:{})$", source)).map_err(|err| {
+              invalid(format!("bad constraint for ':{}': {}", &spec[..open], err))
+            })?;
+            (&spec[..open], Some(regex))
+          }
+          Some(_) => {
+            return Err(invalid(format!(
+              "unexpected text after the constraint of '{}'",
+              raw
+            )))
+          }
+          None => (spec, None),
+        };
+
+        check_name(name).map_err(invalid)?;
+        if names.contains(&name) {
+          return Err(invalid(format!("parameter ':{}' is used twice", name)));
+        }
+        names.push(name);
+
+        Segment::Param {
+          name: name.to_string(),
+          constraint,
+          optional,
+        }
+      } else {
+        if raw.contains(['(', ')']) {
+          return Err(invalid(format!(
+            "constraint without a parameter in '{}'",
+            raw
+          )));
+        }
+        Segment::Static(raw.to_string())
+      };
+
+      // Optional params only make sense at the end, one after the other
+      let previous_optional =
+        matches!(segments.last(), Some(Segment::Param { optional: true, .. }));
+      let current_optional = matches!(segment, Segment::Param { optional: true, .. });
+      if previous_optional && !current_optional {
+        return Err(invalid(format!(
+          "segment '{}' cannot follow an optional parameter",
+          raw
+        )));
+      }
+
+      segments.push(segment);
+    }
+
+    let is_static = segments
+      .iter()
+      .all(|segment| matches!(segment, Segment::Static(_)));
+
+    Ok(Self {
+      raw: route.to_string(),
+      segments,
+      is_static,
+    })
+  }
+
+  pub fn as_str(&self) -> &str {
+    &self.raw
+  }
+
+  pub fn segments(&self) -> &[Segment] {
+    &self.segments
+  }
+
+  /// Names of the `:param` and catch-all segments, in order
+  pub fn param_names(&self) -> Vec<&str> {
+    self
+      .segments
+      .iter()
+      .filter_map(|segment| match segment {
+        Segment::Static(_) => None,
+        Segment::Param { name, .. } | Segment::CatchAll(name) => Some(name.as_str()),
+      })
+      .collect()
+  }
+
+  /// Fill the pattern with `params`, percent-encoding their values
+  ///
+  /// Missing required params and values rejected by a constraint are errors,
+  /// a catch-all value keeps its `/` separators.
+  pub fn build(&self, params: &HashMap<String, String>) -> Result<String> {
+    let mut path = String::with_capacity(self.raw.len());
+
+    for segment in &self.segments {
+      let value = match segment {
+        Segment::Static(text) => Cow::Borrowed(text.as_str()),
+        Segment::Param {
+          name,
+          constraint,
+          optional,
+        } => {
+          let Some(value) = params.get(name) else {
+            if *optional {
+              break;
+            }
+            return Err(self.build_error(format!("missing parameter ':{}'", name)));
+          };
+          if let Some(regex) = constraint {
+            if !regex.is_match(value) {
+              return Err(self.build_error(format!(
+                "'{}' does not match the constraint of ':{}'",
+                value, name
+              )));
+            }
+          }
+          encode_component(value)
+        }
+        Segment::CatchAll(name) => {
+          let Some(value) = params.get(name) else {
+            return Err(self.build_error(format!("missing catch-all '{}'", name)));
+          };
+          Cow::Owned(
+            value
+              .split('/')
+              .map(encode_component)
+              .collect::<Vec<_>>()
+              .join("/"),
+          )
+        }
+      };
+      path.push('/');
+      path.push_str(&value);
+    }
+
+    // Every segment was an omitted optional param
+    if path.is_empty() {
+      path.push('/');
+    }
+
+    Ok(path)
+  }
+
+  fn build_error(&self, reason: String) -> TachyonError {
+    TachyonError::new(format!("Cannot build '{}': {}", self.raw, reason))
+  }
+
+  /// Whether the pattern only matches its own text, so a plain lookup is enough
+  pub fn is_static(&self) -> bool {
+    self.is_static
+  }
+
+  /// Zero allocation check against a request path
+  #[inline]
+  pub fn is_match(&self, path: &str, options: MatchOptions) -> bool {
+    self.match_with(path, options, |_, _| {})
+  }
+
+  /// Captured `(name, value)` pairs when `path` matches, `*` being the name of
+  /// an anonymous catch-all
+  pub fn captures<'a>(
+    &'a self,
+    path: &'a str,
+    options: MatchOptions,
+  ) -> Option<Vec<(&'a str, &'a str)>> {
+    let mut params = Vec::new();
+    self
+      .match_with(path, options, |name, value| params.push((name, value)))
+      .then_some(params)
+  }
+
+  fn match_with<'a>(
+    &'a self,
+    path: &'a str,
+    options: MatchOptions,
+    mut capture: impl FnMut(&'a str, &'a str),
+  ) -> bool {
+    // A catch-all keeps the trailing slash as part of its value
+    let ignore_slash =
+      !options.strict_trailing_slash && !matches!(self.segments.last(), Some(Segment::CatchAll(_)));
+    let path = if ignore_slash {
+      trim_trailing_slash(path)
+    } else {
+      path
+    };
+
+    // Fast path: no parameters, exact match required
+    if self.is_static {
+      let raw = if ignore_slash {
+        trim_trailing_slash(&self.raw)
+      } else {
+        &self.raw
+      };
+      return eq_static(raw, path, options.case_sensitive);
+    }
+
+    let segments = if ignore_slash {
+      self.segments_without_slash()
+    } else {
+      &self.segments
+    };
+
+    // The pattern starts with '/', so its first (empty) segment is skipped
+    let Some(rest) = path.strip_prefix('/') else {
+      return false;
+    };
+
+    let mut actual_segments = rest.split('/').peekable();
+    // Byte offset of the current actual segment, so a catch-all can take the rest
+    let mut offset = 1;
+
+    for segment in segments {
+      // Catch-all segment takes the rest of the path, even if empty
+      if let Segment::CatchAll(name) = segment {
+        if actual_segments.peek().is_none() {
+          return false;
+        }
+        capture(name, &path[offset..]);
+        return true;
+      }
+
+      let Some(actual) = actual_segments.next() else {
+        // Path exhausted, only optional params may remain
+        return matches!(segment, Segment::Param { optional: true, .. });
+      };
+      offset += actual.len() + 1;
+
+      match segment {
+        Segment::Static(text) => {
+          if !eq_static(text, actual, options.case_sensitive) {
+            return false;
+          }
+        }
+        Segment::Param {
+          name, constraint, ..
+        } => {
+          // Parameters can't be empty
+          if actual.is_empty() {
+            return false;
+          }
+          if let Some(regex) = constraint {
+            if !regex.is_match(actual) {
+              return false;
+            }
+          }
+          capture(name, actual);
+        }
+        Segment::CatchAll(_) => unreachable!(),
+      }
+    }
+
+    // Both exhausted = match, extra segments = length mismatch
+    actual_segments.next().is_none()
+  }
+
+  /// Order two patterns matching the same path, the most specific first
+  ///
+  /// Segments are compared left to right: a static segment beats a constrained
+  /// `:param`, which beats a plain `:param`, which beats a catch-all.
+  pub fn compare_specificity(&self, other: &RoutePattern) -> Ordering {
+    for (a, b) in self.segments.iter().zip(other.segments.iter()) {
+      match a.rank().cmp(&b.rank()) {
+        Ordering::Equal => continue,
+        ordering => return ordering,
+      }
+    }
+    other.segments.len().cmp(&self.segments.len())
+  }
+
+  /// Whether both patterns match the same paths with the same priority
+  ///
+  /// Parameter names are ignored, so `/users/:id` conflicts with
+  /// `/users/:userId`. Optional params count as both present and absent.
+  ///
+  /// Two params conflict unless their constraints are provably disjoint.
+  /// `:id(\d+)` conflicts with `:id([0-9]+)` and with an unconstrained
+  /// `:slug`, while `:format(json|xml)` does not with `:id(\d+)`: a list of
+  /// literal alternatives is disjoint from a constraint accepting none of them.
+  pub fn conflicts_with(&self, other: &RoutePattern, options: MatchOptions) -> bool {
+    let (a, b) = if options.strict_trailing_slash {
+      (self.segments.as_slice(), other.segments.as_slice())
+    } else {
+      (
+        self.segments_without_slash(),
+        other.segments_without_slash(),
+      )
+    };
+    let shortest = required_len(a).max(required_len(b));
+    let longest = a.len().min(b.len());
+
+    (shortest..=longest).any(|len| {
+      a[..len]
+        .iter()
+        .zip(&b[..len])
+        .all(|(a, b)| a.overlaps(b, options.case_sensitive))
+    })
+  }
+
+  /// Whether both patterns stand for the same route under `options`
+  ///
+  /// Parameter names are ignored, so `/users/:userId` finds `/users/:id`.
+  pub fn same_route(&self, other: &RoutePattern, options: MatchOptions) -> bool {
+    let (a, b) = if options.strict_trailing_slash {
+      (self.segments.as_slice(), other.segments.as_slice())
+    } else {
+      (
+        self.segments_without_slash(),
+        other.segments_without_slash(),
+      )
+    };
+
+    a.len() == b.len()
+      && a
+        .iter()
+        .zip(b)
+        .all(|(a, b)| a.same_shape(b, options.case_sensitive) && a.is_optional() == b.is_optional())
+  }
+
+  /// Segments without the empty one left by a trailing slash, `/` excepted
+  fn segments_without_slash(&self) -> &[Segment] {
+    match self.segments.split_last() {
+      Some((Segment::Static(last), rest)) if last.is_empty() && !rest.is_empty() => rest,
+      _ => &self.segments,
+    }
+  }
+}
+
+/// Whether no value satisfies both constraints, as far as it can be proven
+///
+/// Only a constraint listing literal alternatives can be checked against the
+/// other one, no constraint at all accepts anything.
+fn disjoint(a: Option<&Regex>, b: Option<&Regex>) -> bool {
+  let (Some(a), Some(b)) = (a, b) else {
+    return false;
+  };
+  let rejects_all = |list: &Regex, other: &Regex| {
+    literals(list).is_some_and(|words| !words.iter().any(|word| other.is_match(word)))
+  };
+  rejects_all(a, b) || rejects_all(b, a)
+}
+
+/// Alternatives of a constraint like `json|xml`, when it is nothing more
+fn literals(constraint: &Regex) -> Option<Vec<&str>> {
+  let source = constraint
+    .as_str()
+    .strip_prefix("^(?:")?
+    .strip_suffix(")$")?;
+  source
+    .split('|')
+    .map(|word| {
+      let literal = !word.is_empty()
+        && word
+          .chars()
+          .all(|char| char.is_ascii_alphanumeric() || matches!(char, '-' | '_' | '~'));
+      literal.then_some(word)
+    })
+    .collect()
+}
+
+/// Number of segments before the first optional param
+fn required_len(segments: &[Segment]) -> usize {
+  segments
+    .iter()
+    .take_while(|segment| !segment.is_optional())
+    .count()
+}
+
+#[inline]
+fn eq_static(a: &str, b: &str, case_sensitive: bool) -> bool {
+  if case_sensitive {
+    a == b
+  } else {
+    a.eq_ignore_ascii_case(b)
+  }
+}
+
+/// `/users/` becomes `/users`, the root stays `/`
+#[inline]
+fn trim_trailing_slash(path: &str) -> &str {
+  match path.strip_suffix('/') {
+    Some(trimmed) if !trimmed.is_empty() => trimmed,
+    _ => path,
+  }
+}
+
+/// Split on `/` outside of constraint parentheses, skipping the leading `/`
+fn split_segments(route: &str) -> std::result::Result<Vec<&str>, String> {
+  let mut segments = Vec::new();
+  let mut depth = 0usize;
+  let mut start = 1;
+
+  for (index, byte) in route.bytes().enumerate().skip(1) {
+    match byte {
+      b'(' => depth += 1,
+      b')' => {
+        depth = depth
+          .checked_sub(1)
+          .ok_or_else(|| "unbalanced ')'".to_string())?
+      }
+      b'/' if depth == 0 => {
+        segments.push(&route[start..index]);
+        start = index + 1;
+      }
+      _ => {}
+    }
+  }
+
+  if depth != 0 {
+    return Err("unclosed '(' in constraint".to_string());
+  }
+  segments.push(&route[start..]);
+
+  Ok(segments)
+}
+
+fn check_name(name: &str) -> std::result::Result<(), String> {
+  if name.is_empty() {
+    return Err("parameter without a name".to_string());
+  }
+  if !name.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_') {
+    return Err(format!(
+      "parameter name '{}' may only contain letters, digits and '_'",
+      name
+    ));
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn matches(pattern: &str, path: &str) -> bool {
+    RoutePattern::parse(pattern)
+      .unwrap()
+      .is_match(path, MatchOptions::default())
+  }
+
+  fn captures(pattern: &str, path: &str) -> Option<Vec<(String, String)>> {
+    let pattern = RoutePattern::parse(pattern).unwrap();
+    pattern
+      .captures(path, MatchOptions::default())
+      .map(|params| {
+        params
+          .into_iter()
+          .map(|(name, value)| (name.to_string(), value.to_string()))
+          .collect()
+      })
+  }
+
+  fn pair(name: &str, value: &str) -> (String, String) {
+    (name.to_string(), value.to_string())
+  }
+
+  #[test]
+  fn test_exact_match() {
+    assert!(matches("/users", "/users"));
+    assert!(matches("/api/posts", "/api/posts"));
+  }
+
+  #[test]
+  fn test_parameter_match() {
+    assert!(matches("/users/:id", "/users/123"));
+    assert!(matches("/users/:id/posts/:postId", "/users/123/posts/456"));
+  }
+
+  #[test]
+  fn test_path_mismatch() {
+    assert!(!matches("/users", "/posts"));
+    assert!(!matches("/users/:id", "/posts/123"));
+  }
+
+  #[test]
+  fn test_length_mismatch() {
+    assert!(!matches("/users/:id", "/users/123/extra"));
+    assert!(!matches("/users/:id/posts", "/users/123"));
+  }
+
+  #[test]
+  fn test_empty_parameter() {
+    assert!(!matches("/users/:id", "/users/"));
+  }
+
+  #[test]
+  fn test_catch_all_match() {
+    assert!(matches("/static/*", "/static/app.js"));
+    assert!(matches("/static/*", "/static/css/site.css"));
+    assert!(matches("/static/*", "/static/"));
+    assert!(!matches("/static/*", "/static"));
+    assert!(matches("/*", "/"));
+    assert!(matches("/*", "/any/deep/path"));
+  }
+
+  #[test]
+  fn test_captured_params() {
+    assert_eq!(
+      captures("/users/:id/posts/:postId", "/users/1/posts/2"),
+      Some(vec![pair("id", "1"), pair("postId", "2")])
+    );
+    assert_eq!(
+      captures("/files/:bucket/*path", "/files/docs/a/b.txt"),
+      Some(vec![pair("bucket", "docs"), pair("path", "a/b.txt")])
+    );
+    assert_eq!(
+      captures("/static/*", "/static/app.js"),
+      Some(vec![pair("*", "app.js")])
+    );
+    assert_eq!(captures("/users", "/users"), Some(vec![]));
+    assert_eq!(captures("/users/:id", "/posts/1"), None);
+  }
+
+  #[test]
+  fn test_constrained_parameter() {
+    assert!(matches(r"/users/:id(\d+)", "/users/42"));
+    assert!(!matches(r"/users/:id(\d+)", "/users/abc"));
+    assert!(!matches(r"/users/:id(\d+)", "/users/42abc"));
+    assert!(matches(
+      r"/files/:name([a-z]+\.(png|jpg))",
+      "/files/cat.png"
+    ));
+    assert!(!matches(
+      r"/files/:name([a-z]+\.(png|jpg))",
+      "/files/cat.gif"
+    ));
+  }
+
+  #[test]
+  fn test_optional_parameter() {
+    assert!(matches("/posts/:slug?", "/posts"));
+    assert!(matches("/posts/:slug?", "/posts/hello"));
+    assert!(!matches("/posts/:slug?", "/posts/hello/extra"));
+    assert_eq!(
+      captures(r"/archive/:year(\d{4})?/:month?", "/archive/2024"),
+      Some(vec![pair("year", "2024")])
+    );
+    assert!(!matches(r"/archive/:year(\d{4})?/:month?", "/archive/24"));
+  }
+
+  #[test]
+  fn test_invalid_patterns() {
+    for route in [
+      "users",
+      "/users/:",
+      "/users/:id(\\d+",
+      "/users/:id)",
+      "/users/:id([)",
+      "/users/:id(\\d+)x",
+      "/users/:user-id",
+      "/users/:id/:id",
+      "/files/*/meta",
+      "/posts/:slug?/comments",
+      "/users/(\\d+)",
+    ] {
+      assert!(
+        RoutePattern::parse(route).is_err(),
+        "{} should be rejected",
+        route
+      );
+    }
+  }
+
+  #[test]
+  fn test_specificity() {
+    let order = |a: &str, b: &str| {
+      RoutePattern::parse(a)
+        .unwrap()
+        .compare_specificity(&RoutePattern::parse(b).unwrap())
+    };
+
+    assert_eq!(order("/users/me", "/users/:id"), Ordering::Less);
+    assert_eq!(order(r"/users/:id(\d+)", "/users/:name"), Ordering::Less);
+    assert_eq!(order("/users/:id", "/users/*"), Ordering::Less);
+    assert_eq!(order("/*", "/static/*"), Ordering::Greater);
+    assert_eq!(order("/a/:b/*", "/a/:c/*"), Ordering::Equal);
+  }
+
+  #[test]
+  fn test_conflicts() {
+    let conflict = |a: &str, b: &str| {
+      RoutePattern::parse(a)
+        .unwrap()
+        .conflicts_with(&RoutePattern::parse(b).unwrap(), MatchOptions::default())
+    };
+
+    assert!(conflict("/users/:id", "/users/:userId"));
+    assert!(conflict("/files/*", "/files/*path"));
+    assert!(conflict(r"/users/:id(\d+)", r"/users/:n(\d+)"));
+    assert!(conflict("/posts", "/posts/:slug?"));
+    assert!(conflict("/posts/:slug?", "/posts/:id"));
+    assert!(conflict(r"/users/:id(\d+)", r"/users/:id([0-9]+)"));
+    assert!(conflict(r"/users/:id(\d+)", "/users/:slug"));
+    assert!(conflict(
+      "/export/:format(json|xml)",
+      "/export/:format(xml|pdf)"
+    ));
+    assert!(conflict("/export/:format(json|xml)", "/export/:name"));
+    assert!(!conflict(
+      "/export/:format(json|xml)",
+      "/export/:format(csv)"
+    ));
+    assert!(!conflict("/export/:format(json|xml)", r"/export/:id(\d+)"));
+    assert!(!conflict(r"/export/:id(\d+)", "/export/:format(json|xml)"));
+    assert!(!conflict("/users/:id", "/users/me"));
+    assert!(!conflict("/users/:id", "/users/:id/posts"));
+    assert!(!conflict("/files/*", "/files/:name"));
+  }
+
+  #[test]
+  fn test_loose_matching() {
+    let loose = MatchOptions {
+      strict_trailing_slash: false,
+      case_sensitive: false,
+    };
+    let matches =
+      |pattern: &str, path: &str| RoutePattern::parse(pattern).unwrap().is_match(path, loose);
+
+    assert!(matches("/users", "/users/"));
+    assert!(matches("/users/", "/users"));
+    assert!(matches("/", "/"));
+    assert!(matches("/Users/:id", "/users/1/"));
+    assert!(matches("/users/:id", "/USERS/1"));
+    assert!(!matches("/users/:id", "/users/"));
+    assert!(!matches("/users", "/users//"));
+    assert_eq!(
+      RoutePattern::parse("/files/*")
+        .unwrap()
+        .captures("/files/a/", loose),
+      Some(vec![("*", "a/")])
+    );
+
+    let conflict = |a: &str, b: &str| {
+      RoutePattern::parse(a)
+        .unwrap()
+        .conflicts_with(&RoutePattern::parse(b).unwrap(), loose)
+    };
+    assert!(conflict("/users", "/users/"));
+    assert!(conflict("/users/:id", "/Users/:name/"));
+    assert!(!conflict("/", "/users"));
+  }
+
+  #[test]
+  fn test_build() {
+    let build = |pattern: &str, params: &[(&str, &str)]| {
+      let params = params
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+      RoutePattern::parse(pattern).unwrap().build(&params)
+    };
+
+    assert_eq!(build("/", &[]).unwrap(), "/");
+    assert_eq!(build("/users/", &[]).unwrap(), "/users/");
+    assert_eq!(
+      build("/users/:id", &[("id", "john doe")]).unwrap(),
+      "/users/john%20doe"
+    );
+    assert_eq!(build("/posts/:slug?", &[]).unwrap(), "/posts");
+    assert_eq!(build("/:lang?", &[]).unwrap(), "/");
+    assert_eq!(
+      build("/files/*path", &[("path", "a b/c.txt")]).unwrap(),
+      "/files/a%20b/c.txt"
+    );
+    assert_eq!(
+      build("/static/*", &[("*", "app.js")]).unwrap(),
+      "/static/app.js"
+    );
+    assert_eq!(
+      build("/users/:id", &[]).unwrap_err().reason(),
+      "Cannot build '/users/:id': missing parameter ':id'"
+    );
+    assert!(build(r"/users/:id(\d+)", &[("id", "abc")]).is_err());
+  }
+}
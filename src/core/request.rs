@@ -1,15 +1,38 @@
+#[cfg(feature = "napi")]
 use napi_derive::napi;
 use serde_json::Value;
+use std::collections::HashMap;
 
-#[napi(object)]
+use crate::core::{locals::Locals, signal::AbortSignal};
+
+#[cfg_attr(feature = "napi", napi(object, object_to_js = false))]
 #[derive(Debug, Clone)]
 pub struct TachyonRequest {
   pub body: Value,
+  /// Values captured by `:param` and catch-all segments, `*` for an anonymous catch-all
+  pub params: HashMap<String, String>,
+  /// Values shared by every middleware, hook and handler of the request
+  pub locals: Locals,
+  /// Aborted when the client disconnects or a handler times out
+  pub signal: AbortSignal,
+  /// `Last-Event-ID` header of a reconnecting `EventSource`
+  pub last_event_id: Option<String>,
 }
 
 impl TachyonRequest {
   pub fn new(body: Value) -> Self {
-    Self { body }
+    Self {
+      body,
+      params: HashMap::new(),
+      locals: Locals::new(),
+      signal: AbortSignal::new(),
+      last_event_id: None,
+    }
+  }
+
+  pub fn with_params(mut self, params: HashMap<String, String>) -> Self {
+    self.params = params;
+    self
   }
 }
 
@@ -18,3 +41,34 @@ impl Default for TachyonRequest {
     Self::new(Value::Null)
   }
 }
+
+#[cfg(feature = "napi")]
+mod js {
+  use napi::{
+    bindgen_prelude::{JsObjectValue, Object, ToNapiValue},
+    sys, Env, JsValue, Property, PropertyAttributes, Result,
+  };
+
+  use super::TachyonRequest;
+
+  /// `signal` is a getter, the `AbortController` behind it is only created for handlers reading it
+  impl ToNapiValue for TachyonRequest {
+    unsafe fn to_napi_value(env: sys::napi_env, val: Self) -> Result<sys::napi_value> {
+      let env = Env::from_raw(env);
+      let mut object = Object::new(&env)?;
+      object.set_named_property("body", val.body)?;
+      object.set_named_property("params", val.params)?;
+      object.set_named_property("locals", val.locals)?;
+      if let Some(last_event_id) = val.last_event_id {
+        object.set_named_property("lastEventId", last_event_id)?;
+      }
+
+      let signal = val.signal;
+      object.define_properties(&[Property::new()
+        .with_utf8_name("signal")?
+        .with_getter_closure(move |_env, _this| Ok(signal.clone()))
+        .with_property_attributes(PropertyAttributes::Enumerable)])?;
+      Ok(object.raw())
+    }
+  }
+}
@@ -1,13 +1,60 @@
+use bytes::Bytes;
+use hyper::header::{self, HeaderMap, HeaderName, HeaderValue};
+#[cfg(feature = "napi")]
+use napi::bindgen_prelude::{Either, FnArgs, Function, PromiseRaw, Uint8Array, Unknown};
+#[cfg(feature = "napi")]
 use napi_derive::napi;
 use serde_json::Value;
-use std::sync::atomic::{AtomicPtr, AtomicU16, Ordering};
-use std::sync::Arc;
+use std::path::Path;
+use std::sync::atomic::{AtomicPtr, AtomicU16, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Instant;
+use tokio::sync::Notify;
+
+#[cfg(feature = "napi")]
+use crate::core::js_ref::script;
+use crate::{
+  core::{
+    files::{self, FileOptions},
+    locals::Locals,
+    options::{SendFileOptions, SseOptions},
+    sse::{EventStream, KEEP_ALIVE},
+    stream::{ResponseStream, StreamBody},
+  },
+  error::{HttpError, Result, TachyonError},
+};
 
 // Ultra-fast lock-free response implementation
-#[napi]
+#[cfg_attr(feature = "napi", napi)]
 pub struct TachyonResponse {
   data: Arc<AtomicPtr<String>>,
   status_code: Arc<AtomicU16>,
+  error: Arc<OnceLock<HttpError>>,
+  headers: Arc<Mutex<HeaderMap>>,
+  started: Instant,
+  locals: Locals,
+  stream: Arc<ResponseStream>,
+  deferred: Arc<Deferred>,
+}
+
+/// Work a handler started without waiting for it, such as `sendFile`
+#[derive(Default)]
+struct Deferred {
+  running: AtomicUsize,
+  settled: Notify,
+}
+
+/// Marks deferred work as done once dropped
+#[cfg(feature = "napi")]
+pub(crate) struct DeferGuard(Arc<Deferred>);
+
+#[cfg(feature = "napi")]
+impl Drop for DeferGuard {
+  fn drop(&mut self) {
+    if self.0.running.fetch_sub(1, Ordering::SeqCst) == 1 {
+      self.0.settled.notify_waiters();
+    }
+  }
 }
 
 impl Default for TachyonResponse {
@@ -16,22 +63,57 @@ impl Default for TachyonResponse {
   }
 }
 
-#[napi]
+#[cfg_attr(feature = "napi", napi)]
 impl TachyonResponse {
-  #[napi(constructor)]
+  #[cfg_attr(feature = "napi", napi(constructor))]
   pub fn new() -> Self {
     Self {
       data: Arc::new(AtomicPtr::new(std::ptr::null_mut())),
       status_code: Arc::new(AtomicU16::new(200)),
+      error: Arc::new(OnceLock::new()),
+      headers: Arc::new(Mutex::new(HeaderMap::new())),
+      started: Instant::now(),
+      locals: Locals::new(),
+      stream: Arc::new(ResponseStream::default()),
+      deferred: Arc::new(Deferred::default()),
     }
   }
 
-  #[napi]
+  #[cfg_attr(feature = "napi", napi)]
   pub fn send(&self, msg: Option<Value>) {
     let json_string = serde_json::to_string(&msg).unwrap_or_else(|_| "{}".to_string());
+    self.send_raw(json_string);
+  }
+
+  #[cfg_attr(feature = "napi", napi)]
+  pub fn status(&self, code: u16) -> TachyonResponse {
+    self.status_code.store(code, Ordering::SeqCst);
+    self.clone()
+  }
+
+  /// Status code of the response, 200 unless set with `status`
+  #[cfg_attr(feature = "napi", napi(getter))]
+  pub fn status_code(&self) -> u16 {
+    self.get_status()
+  }
+
+  /// Value sent so far, e.g. to transform it in an `onSend` hook
+  #[cfg_attr(feature = "napi", napi(getter))]
+  pub fn payload(&self) -> Option<Value> {
+    let data = self.get_data()?;
+    Some(serde_json::from_str(&data).unwrap_or(Value::String(data)))
+  }
+
+  /// Milliseconds since the request was received
+  #[cfg_attr(feature = "napi", napi(getter))]
+  pub fn elapsed_time(&self) -> f64 {
+    self.started.elapsed().as_secs_f64() * 1000.0
+  }
 
+  /// Store a body sent as is, without JSON encoding
+  pub(crate) fn send_raw(&self, body: String) {
     // Allocate new string on heap and store pointer atomically
-    let boxed = Box::new(json_string);
+    let boxed = Box::new(body);
     let new_ptr = Box::into_raw(boxed);
 
     // Swap old pointer with new one
@@ -45,15 +127,6 @@ impl TachyonResponse {
     }
   }
 
-  #[napi]
-  pub fn status(&self, code: u16) -> TachyonResponse {
-    self.status_code.store(code, Ordering::SeqCst);
-    Self {
-      data: Arc::clone(&self.data),
-      status_code: Arc::clone(&self.status_code),
-    }
-  }
-
   pub fn take_data(&self) -> Option<String> {
     // Atomically take the data pointer
     let ptr = self.data.swap(std::ptr::null_mut(), Ordering::SeqCst);
@@ -82,6 +155,197 @@ impl TachyonResponse {
     }
   }
 
+  /// Whether a handler already called `send` or started streaming the body
+  pub fn is_sent(&self) -> bool {
+    !self.data.load(Ordering::SeqCst).is_null() || self.stream.is_started()
+  }
+
+  /// Write a chunk of a streamed body, waiting while the client lags behind
+  ///
+  /// Status and headers go out with the first chunk, call `end` once done.
+  /// Returns false once the body ended or the client went away.
+  pub async fn write(&self, chunk: impl Into<Bytes>) -> bool {
+    let queued = self.stream.push(chunk.into());
+    if queued {
+      self.stream.drain().await;
+    }
+    queued
+  }
+
+  /// Finish a streamed body, an empty one if nothing was written
+  pub fn end(&self) {
+    self.stream.end();
+  }
+
+  /// Answer with a Server-Sent Events stream
+  ///
+  /// Example usage:
+  /// ```javascript
+  /// app.get('/prices', (req, res) => {
+  ///   const events = res.sse()
+  ///   const unsubscribe = prices.subscribe((price) => events.send({ event: 'price', data: price }))
+  ///   req.signal.addEventListener('abort', unsubscribe)
+  /// })
+  /// ```
+  #[cfg_attr(feature = "napi", napi)]
+  pub fn sse(&self, options: Option<SseOptions>) -> EventStream {
+    if let Ok(mut headers) = self.headers.lock() {
+      headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("text/event-stream"),
+      );
+      headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+      // Keep reverse proxies from holding events back
+      headers.insert("x-accel-buffering", HeaderValue::from_static("no"));
+    }
+    if let Some(every) = options.unwrap_or_default().keep_alive() {
+      self
+        .stream
+        .set_heartbeat(every, Bytes::from_static(KEEP_ALIVE));
+    }
+    self.stream.start();
+    EventStream::new(Arc::clone(&self.stream))
+  }
+
+  /// Stream a file from disk, with its `Content-Type`, `ETag` and `Last-Modified`
+  ///
+  /// Conditional and `Range` requests are answered with 304, 206 or 416.
+  /// A missing file fails the response with 404.
+  pub async fn send_file(&self, path: impl AsRef<Path>, options: SendFileOptions) {
+    let root = options.root.as_deref().map(Path::new);
+    let file_options = FileOptions {
+      etag: options.etag(),
+      last_modified: options.last_modified(),
+      cache_control: Some(options.cache_control()),
+    };
+    let sent = match files::resolve_path(path.as_ref(), root).await {
+      Ok(path) => files::send_file(self, &path, &file_options).await,
+      Err(err) => Err(err),
+    };
+    if let Err(err) = sent {
+      self.fail(err);
+    }
+  }
+
+  /// Send a file as an attachment, saved as `filename` or under its own name
+  pub async fn download(
+    &self,
+    path: impl AsRef<Path>,
+    filename: Option<&str>,
+    options: SendFileOptions,
+  ) {
+    let path = path.as_ref();
+    let filename = filename
+      .map(str::to_string)
+      .or_else(|| Some(path.file_name()?.to_string_lossy().into_owned()))
+      .unwrap_or_else(|| "download".to_string());
+    let _ = self.set_header(
+      "content-disposition",
+      &files::content_disposition(&filename),
+    );
+    self.send_file(path, options).await;
+  }
+
+  /// Track work answering the request after the handler returns
+  #[cfg(feature = "napi")]
+  pub(crate) fn defer(&self) -> DeferGuard {
+    self.deferred.running.fetch_add(1, Ordering::SeqCst);
+    DeferGuard(Arc::clone(&self.deferred))
+  }
+
+  /// Wait until the work started with `defer` is done
+  pub(crate) async fn settled(&self) {
+    loop {
+      let settled = self.deferred.settled.notified();
+      tokio::pin!(settled);
+      settled.as_mut().enable();
+      if self.deferred.running.load(Ordering::SeqCst) == 0 {
+        return;
+      }
+      settled.await;
+    }
+  }
+
+  pub fn is_streaming(&self) -> bool {
+    self.stream.is_started()
+  }
+
+  /// Wait until the handlers start streaming the body
+  pub(crate) async fn streaming(&self) {
+    self.stream.started().await;
+  }
+
+  /// Answer nobody, streamed writes fail right away instead of waiting for a client
+  pub(crate) fn detach(&self) {
+    self.stream.close();
+  }
+
+  /// Body of a streamed response, only the first call gets it
+  pub(crate) fn take_stream(&self) -> Option<StreamBody> {
+    self.stream.take_body()
+  }
+
+  /// Report an error instead of sending a response, only the first one is kept
+  ///
+  /// The remaining handlers are skipped and the error goes to `onError`.
+  pub fn fail(&self, error: HttpError) {
+    let _ = self.error.set(error);
+  }
+
+  pub fn error(&self) -> Option<&HttpError> {
+    self.error.get()
+  }
+
+  /// Set a response header, replacing any previous value
+  pub fn set_header(&self, name: &str, value: &str) -> Result<()> {
+    let name = HeaderName::try_from(name)
+      .map_err(|_| TachyonError::new(format!("Invalid header name '{}'", name)))?;
+    let value = HeaderValue::try_from(value)
+      .map_err(|_| TachyonError::new(format!("Invalid value for header '{}'", name)))?;
+    if let Ok(mut headers) = self.headers.lock() {
+      headers.insert(name, value);
+    }
+    Ok(())
+  }
+
+  pub fn get_header(&self, name: &str) -> Option<String> {
+    let headers = self.headers.lock().ok()?;
+    let value = headers.get(name)?.to_str().ok()?;
+    Some(value.to_string())
+  }
+
+  pub fn remove_header(&self, name: &str) {
+    if let Ok(mut headers) = self.headers.lock() {
+      headers.remove(name);
+    }
+  }
+
+  /// Every header set so far, leaving none behind
+  pub fn take_headers(&self) -> HeaderMap {
+    self
+      .headers
+      .lock()
+      .map(|mut headers| std::mem::take(&mut *headers))
+      .unwrap_or_default()
+  }
+
+  /// Measure `elapsedTime` from `started` instead of the creation of the response
+  pub(crate) fn with_start(mut self, started: Instant) -> Self {
+    self.started = started;
+    self
+  }
+
+  /// Share the locals of the request being answered
+  pub(crate) fn with_locals(mut self, locals: Locals) -> Self {
+    self.locals = locals;
+    self
+  }
+
+  /// Same values as `req.locals`
+  pub fn locals(&self) -> &Locals {
+    &self.locals
+  }
+
   pub fn get_status(&self) -> u16 {
     self.status_code.load(Ordering::SeqCst)
   }
@@ -91,11 +355,188 @@ impl TachyonResponse {
   }
 }
 
+#[cfg(feature = "napi")]
+#[napi]
+impl TachyonResponse {
+  /// Set a response header, replacing any previous value
+  ///
+  /// Example usage:
+  /// ```javascript
+  /// res.header('cache-control', 'no-store').send({ ok: true })
+  /// ```
+  #[napi(js_name = "header")]
+  pub fn header(&self, name: String, value: String) -> napi::Result<TachyonResponse> {
+    self.set_header(&name, &value)?;
+    Ok(self.clone())
+  }
+
+  /// Same object as `req.locals`, e.g. for values rendered in templates
+  #[napi(getter, js_name = "locals", ts_return_type = "Record<string, any>")]
+  pub fn js_locals<'env>(
+    &self,
+    env: &'env napi::Env,
+  ) -> napi::Result<napi::bindgen_prelude::Object<'env>> {
+    self.locals.object(env)
+  }
+
+  /// Write a chunk of a streamed body, resolves once the client is ready for more
+  ///
+  /// Example usage:
+  /// ```javascript
+  /// res.header('content-type', 'text/csv')
+  /// for await (const row of db.rows()) {
+  ///   if (!(await res.write(`${row.id},${row.name}\n`))) break
+  /// }
+  /// res.end()
+  /// ```
+  #[napi(js_name = "write", ts_args_type = "chunk: string | Uint8Array")]
+  pub fn write_chunk<'env>(
+    &self,
+    env: &'env napi::Env,
+    chunk: Either<String, Uint8Array>,
+  ) -> napi::Result<PromiseRaw<'env, bool>> {
+    let queued = self.stream.push(chunk_bytes(chunk));
+    let stream = Arc::clone(&self.stream);
+    env.spawn_future(async move {
+      if queued {
+        stream.drain().await;
+      }
+      Ok(queued)
+    })
+  }
+
+  /// Finish a streamed body, optionally with a last chunk
+  #[napi(js_name = "end", ts_args_type = "chunk?: string | Uint8Array")]
+  pub fn finish(&self, chunk: Option<Either<String, Uint8Array>>) {
+    if let Some(chunk) = chunk {
+      self.stream.push(chunk_bytes(chunk));
+    }
+    self.end();
+  }
+
+  /// Stream an async iterable or a Node `Readable`, ending the response after it
+  ///
+  /// The source stops being read once the client goes away.
+  ///
+  /// Example usage:
+  /// ```javascript
+  /// app.get('/export', (req, res) => res.stream(fs.createReadStream('export.csv')))
+  /// ```
+  #[napi(
+    ts_args_type = "source: AsyncIterable<string | Uint8Array> | Iterable<string | Uint8Array>",
+    ts_return_type = "Promise<void>"
+  )]
+  pub fn stream<'env>(
+    &self,
+    env: &'env napi::Env,
+    source: Unknown<'env>,
+  ) -> napi::Result<Unknown<'env>> {
+    // Handlers may return before the first chunk, the response is already taken
+    self.stream.start();
+    let pump: Function<FnArgs<(Unknown, TachyonResponse)>, Unknown> = script(env, PUMP)?;
+    pump.call((source, self.clone()).into())
+  }
+
+  /// Stream a file from disk, answering 404 when it is missing
+  ///
+  /// Example usage:
+  /// ```javascript
+  /// app.get('/reports/:id', (req, res) => {
+  ///   res.sendFile(`${req.params.id}.pdf`, { root: './reports', maxAge: 3600 })
+  /// })
+  /// ```
+  #[napi(
+    js_name = "sendFile",
+    ts_args_type = "path: string, options?: SendFileOptions | undefined | null"
+  )]
+  pub fn send_file_js<'env>(
+    &self,
+    env: &'env napi::Env,
+    path: String,
+    options: Option<SendFileOptions>,
+  ) -> napi::Result<PromiseRaw<'env, ()>> {
+    // Handlers usually return without awaiting, the request waits for the file
+    let guard = self.defer();
+    let res = self.clone();
+    env.spawn_future(async move {
+      res.send_file(path, options.unwrap_or_default()).await;
+      drop(guard);
+      Ok(())
+    })
+  }
+
+  /// Send a file as an attachment, saved as `filename` or under its own name
+  ///
+  /// Example usage:
+  /// ```javascript
+  /// app.get('/invoices/:id/pdf', (req, res) => {
+  ///   res.download(`./invoices/${req.params.id}.pdf`, `invoice-${req.params.id}.pdf`)
+  /// })
+  /// ```
+  #[napi(
+    js_name = "download",
+    ts_args_type = "path: string, filename?: string | undefined | null, options?: SendFileOptions | undefined | null"
+  )]
+  pub fn download_js<'env>(
+    &self,
+    env: &'env napi::Env,
+    path: String,
+    filename: Option<String>,
+    options: Option<SendFileOptions>,
+  ) -> napi::Result<PromiseRaw<'env, ()>> {
+    let guard = self.defer();
+    let res = self.clone();
+    env.spawn_future(async move {
+      res
+        .download(path, filename.as_deref(), options.unwrap_or_default())
+        .await;
+      drop(guard);
+      Ok(())
+    })
+  }
+
+  #[napi(js_name = "getHeader")]
+  pub fn header_value(&self, name: String) -> Option<String> {
+    self.get_header(&name)
+  }
+
+  #[napi(js_name = "removeHeader")]
+  pub fn unset_header(&self, name: String) {
+    self.remove_header(&name)
+  }
+}
+
+/// Writes every chunk of a source, ending the response even when it throws
+#[cfg(feature = "napi")]
+const PUMP: &str = r#"(source, res) => (async () => {
+  try {
+    for await (const chunk of source) {
+      if (!(await res.write(chunk))) break
+    }
+  } finally {
+    res.end()
+  }
+})()"#;
+
+#[cfg(feature = "napi")]
+fn chunk_bytes(chunk: Either<String, Uint8Array>) -> Bytes {
+  match chunk {
+    Either::A(text) => Bytes::from(text),
+    Either::B(data) => Bytes::copy_from_slice(&data),
+  }
+}
+
 impl Clone for TachyonResponse {
   fn clone(&self) -> Self {
     Self {
       data: Arc::clone(&self.data),
       status_code: Arc::clone(&self.status_code),
+      error: Arc::clone(&self.error),
+      headers: Arc::clone(&self.headers),
+      started: self.started,
+      locals: self.locals.clone(),
+      stream: Arc::clone(&self.stream),
+      deferred: Arc::clone(&self.deferred),
     }
   }
 }
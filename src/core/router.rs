@@ -1,73 +1,1029 @@
-use std::sync::Arc;
+use std::{collections::HashMap, future::Future, sync::Arc};
 
 use async_trait::async_trait;
-use napi::{
-  bindgen_prelude::{FnArgs, Function},
-  Result,
-};
+use dashmap::DashMap;
+#[cfg(feature = "napi")]
+use napi_derive::napi;
 
+#[cfg(feature = "napi")]
+use crate::core::wrapper::{into_handlers, route_args, RouteArg};
 use crate::{
   core::{
-    methods::Method, request::TachyonRequest, response::TachyonResponse,
-    wrapper::ThreadsafeFunctionWrapper,
+    group::Router,
+    hooks::{HookName, Hooks},
+    host::HostPattern,
+    methods::Method,
+    options::{ConflictPolicy, RouteOptions},
+    path::encode_component,
+    pattern::{MatchOptions, RoutePattern},
+    request::TachyonRequest,
+    response::TachyonResponse,
   },
-  Tachyon,
+  error::{HttpError, Result, TachyonError},
+  utils, Tachyon,
 };
 
+/// Anything that can answer a request, either a JS callback or native Rust code
+///
+/// Native handlers never cross the N-API boundary, which makes them a good fit
+/// for health checks, proxies and static responses. Async closures taking
+/// `(TachyonRequest, TachyonResponse)` implement this trait as well.
 #[async_trait]
 pub trait TachyonHandler: Send + Sync {
   async fn call(&self, req: TachyonRequest, res: TachyonResponse);
+
+  /// Whether `listen` calls the handler once before serving, as it does for JS callbacks
+  fn warms_up(&self) -> bool {
+    false
+  }
+}
+
+#[async_trait]
+impl<F, Fut> TachyonHandler for F
+where
+  F: Fn(TachyonRequest, TachyonResponse) -> Fut + Send + Sync,
+  Fut: Future<Output = ()> + Send,
+{
+  async fn call(&self, req: TachyonRequest, res: TachyonResponse) {
+    self(req, res).await
+  }
+}
+
+/// Renders the errors of the request pipeline, see [`HttpError`]
+#[async_trait]
+pub trait TachyonErrorHandler: Send + Sync {
+  async fn call(&self, err: HttpError, req: TachyonRequest, res: TachyonResponse);
+}
+
+#[async_trait]
+impl<F, Fut> TachyonErrorHandler for F
+where
+  F: Fn(HttpError, TachyonRequest, TachyonResponse) -> Fut + Send + Sync,
+  Fut: Future<Output = ()> + Send,
+{
+  async fn call(&self, err: HttpError, req: TachyonRequest, res: TachyonResponse) {
+    self(err, req, res).await
+  }
+}
+
+/// Ordered handlers of a single route
+///
+/// Handlers run one after another until one of them sends the response or
+/// fails, so middleware only has to skip `res.send` to pass control on.
+pub struct HandlerChain {
+  handlers: Vec<Arc<dyn TachyonHandler>>,
+}
+
+impl HandlerChain {
+  pub fn new(handlers: Vec<Arc<dyn TachyonHandler>>) -> Self {
+    Self { handlers }
+  }
+
+  pub fn handlers(&self) -> &[Arc<dyn TachyonHandler>] {
+    &self.handlers
+  }
+}
+
+#[async_trait]
+impl TachyonHandler for HandlerChain {
+  async fn call(&self, req: TachyonRequest, res: TachyonResponse) {
+    for handler in &self.handlers {
+      handler.call(req.clone(), res.clone()).await;
+      if res.is_sent() || res.error().is_some() {
+        break;
+      }
+    }
+  }
+
+  fn warms_up(&self) -> bool {
+    self.handlers.iter().any(|handler| handler.warms_up())
+  }
 }
 
 pub struct TachyonRouter {
   method: u8,
-  handler: Arc<dyn TachyonHandler>,
+  pattern: RoutePattern,
+  chain: Arc<HandlerChain>,
+  options: RouteOptions,
+  hooks: Option<Arc<Hooks>>,
+  /// Leading handlers that came from the middleware of mounted routers
+  mounted: usize,
 }
 
 impl TachyonRouter {
-  pub fn new(method: u8, handler: Arc<dyn TachyonHandler>) -> Self {
-    Self { method, handler }
+  pub fn new(
+    method: u8,
+    pattern: RoutePattern,
+    handlers: Vec<Arc<dyn TachyonHandler>>,
+    options: RouteOptions,
+  ) -> Self {
+    Self {
+      method,
+      pattern,
+      chain: Arc::new(HandlerChain::new(handlers)),
+      options,
+      hooks: None,
+      mounted: 0,
+    }
+  }
+
+  /// Route of a mounted router, `middleware` running before its own handlers
+  fn mounted(
+    router: &TachyonRouter,
+    pattern: RoutePattern,
+    middleware: &[Arc<dyn TachyonHandler>],
+  ) -> Self {
+    let mut handlers = Vec::with_capacity(middleware.len() + router.handlers().len());
+    handlers.extend(middleware.iter().cloned());
+    handlers.extend(router.handlers().iter().cloned());
+
+    let mut mounted = Self::new(router.method(), pattern, handlers, router.options().clone());
+    mounted.mounted = middleware.len() + router.mounted;
+    mounted
+  }
+
+  /// Hooks running after the global ones for this route only
+  pub fn with_hooks(mut self, hooks: Option<Arc<Hooks>>) -> Self {
+    self.hooks = hooks.filter(|hooks| !hooks.is_empty());
+    self
+  }
+
+  pub fn hooks(&self) -> Option<&Arc<Hooks>> {
+    self.hooks.as_ref()
+  }
+
+  pub fn add_hook(&mut self, name: HookName, handlers: Vec<Arc<dyn TachyonHandler>>) {
+    let hooks = self.hooks.get_or_insert_with(Default::default);
+    Arc::make_mut(hooks).add(name, handlers);
   }
 
   pub fn method(&self) -> u8 {
     self.method
   }
 
+  pub fn pattern(&self) -> &RoutePattern {
+    &self.pattern
+  }
+
+  pub fn options(&self) -> &RouteOptions {
+    &self.options
+  }
+
+  /// Whole chain as a single handler, cheap to clone out of the route table
   pub fn handler(&self) -> Arc<dyn TachyonHandler> {
-    Arc::clone(&self.handler)
+    Arc::clone(&self.chain) as Arc<dyn TachyonHandler>
+  }
+
+  pub fn handlers(&self) -> &[Arc<dyn TachyonHandler>] {
+    self.chain.handlers()
+  }
+}
+
+/// Structured description of a registered route
+#[cfg_attr(feature = "napi", napi(object))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteInfo {
+  pub method: String,
+  /// Host pattern of the virtual host serving the route, if any
+  pub host: Option<String>,
+  pub path: String,
+  /// Names of the `:param` and catch-all segments, in order
+  pub params: Vec<String>,
+  /// Handlers running before the route handler, router middleware included
+  pub middleware: u32,
+  pub meta: Option<serde_json::Value>,
+}
+
+/// Every route of a table, sorted by path then method
+pub fn route_infos(routes: &DashMap<String, TachyonRouter>) -> Vec<RouteInfo> {
+  let mut result: Vec<(u8, RouteInfo)> = routes
+    .iter()
+    .map(|entry| {
+      let router = entry.value();
+      let info = RouteInfo {
+        method: Method::new(router.method()).to_string(),
+        host: None,
+        path: router.pattern().as_str().to_string(),
+        params: router
+          .pattern()
+          .param_names()
+          .into_iter()
+          .map(str::to_string)
+          .collect(),
+        middleware: router.handlers().len().saturating_sub(1) as u32,
+        meta: router.options().meta.clone(),
+      };
+      (router.method(), info)
+    })
+    .collect();
+
+  result.sort_by(|(a_method, a), (b_method, b)| a.path.cmp(&b.path).then(a_method.cmp(b_method)));
+  result.into_iter().map(|(_, info)| info).collect()
+}
+
+impl RouteInfo {
+  /// Path prefixed by the host pattern, `api.example.com/users`
+  fn location(&self) -> String {
+    format!("{}{}", self.host.as_deref().unwrap_or(""), self.path)
+  }
+}
+
+/// Human readable `"<path> <METHOD>"` listing of routes
+pub fn describe_routes(routes: &[RouteInfo]) -> Vec<String> {
+  routes
+    .iter()
+    .map(|info| format!("{} {}", info.location(), info.method))
+    .collect()
+}
+
+/// Aligned `METHOD PATH PARAMS MIDDLEWARE` table, one route per line
+pub fn format_route_table(routes: &[RouteInfo]) -> String {
+  let header = ["METHOD", "PATH", "PARAMS", "MIDDLEWARE"].map(String::from);
+  let rows: Vec<[String; 4]> = routes
+    .iter()
+    .map(|info| {
+      let params = if info.params.is_empty() {
+        "-".to_string()
+      } else {
+        info.params.join(", ")
+      };
+      [
+        info.method.clone(),
+        info.location(),
+        params,
+        info.middleware.to_string(),
+      ]
+    })
+    .collect();
+
+  let mut widths = [0; 4];
+  for row in std::iter::once(&header).chain(&rows) {
+    for (width, cell) in widths.iter_mut().zip(row) {
+      *width = (*width).max(cell.chars().count());
+    }
+  }
+
+  let mut table = String::new();
+  for row in std::iter::once(&header).chain(&rows) {
+    let line = row
+      .iter()
+      .zip(widths)
+      .map(|(cell, width)| format!("{:<width$}", cell))
+      .collect::<Vec<_>>()
+      .join("  ");
+    table.push_str(line.trim_end());
+    table.push('\n');
   }
+  table
 }
 
+/// Routes of an app, router or virtual host, by `<method id>:<pattern>`
+type RouteTable = Arc<DashMap<String, TachyonRouter>>;
+
 pub trait HTTPCall {
-  fn call(
+  fn route_table(&self) -> Arc<DashMap<String, TachyonRouter>>;
+
+  /// How duplicate or ambiguous routes are reported, see [`check_route`] when unset
+  fn conflict_policy(&self) -> Option<ConflictPolicy> {
+    None
+  }
+
+  /// Matching rules used to tell whether two routes overlap
+  fn match_options(&self) -> MatchOptions {
+    MatchOptions::default()
+  }
+
+  /// Register a JS route from the arguments following its path
+  #[cfg(feature = "napi")]
+  fn call(&self, route: String, method: Method, args: Vec<Option<RouteArg>>) -> napi::Result<()> {
+    let (callbacks, options) = route_args(args)?;
+    let handlers = into_handlers(callbacks)?;
+    Ok(self.register_with(&route, method, handlers, options.unwrap_or_default())?)
+  }
+
+  /// Register an already built handler chain, JS or native
+  fn register(
     &self,
-    route: String,
+    route: &str,
     method: Method,
-    callback: Function<'static, FnArgs<(TachyonRequest, TachyonResponse)>, ()>,
-  ) -> Result<()>;
-}
+    handlers: Vec<Arc<dyn TachyonHandler>>,
+  ) -> Result<()> {
+    self.register_with(route, method, handlers, RouteOptions::default())
+  }
 
-impl HTTPCall for Tachyon {
-  fn call(
+  /// Same as [`HTTPCall::register`], with route specific settings
+  fn register_with(
     &self,
-    route: String,
+    route: &str,
     method: Method,
-    callback: Function<'static, FnArgs<(TachyonRequest, TachyonResponse)>, ()>,
+    handlers: Vec<Arc<dyn TachyonHandler>>,
+    options: RouteOptions,
   ) -> Result<()> {
-    // Build threadsafe function directly
-    // This works for both sync and async JavaScript functions
-    let handler = callback
-      .build_threadsafe_function()
-      .weak::<false>()
-      .build()?;
+    if handlers.is_empty() {
+      return Err(TachyonError::new(format!(
+        "Route {} {} needs at least one handler",
+        method, route
+      )));
+    }
+
+    // Constraints are compiled once here, invalid patterns never reach the table
+    let pattern = RoutePattern::parse(route)?;
 
-    let wrapper = ThreadsafeFunctionWrapper::new(handler);
-    let route_key = format!("{}:{}", method.id(), route);
+    self.insert_route(TachyonRouter::new(method.id(), pattern, handlers, options))
+  }
+
+  /// Add a route to the table unless it clashes with one already there
+  fn insert_route(&self, router: TachyonRouter) -> Result<()> {
+    let routes = self.route_table();
+    {
+      let registered: Vec<_> = routes.iter().collect();
+      check_route(
+        &router,
+        registered.iter().map(|entry| entry.value()),
+        self.match_options(),
+        self.conflict_policy(),
+      )?;
+    }
 
     // Fast insertion into route table
-    let router = TachyonRouter::new(method.id(), Arc::new(wrapper));
-    self.get_routes().insert(route_key, router);
+    routes.insert(route_key(router.method(), router.pattern()), router);
+
+    Ok(())
+  }
+
+  /// Replace the handlers of a registered route
+  ///
+  /// Requests already running keep the previous handlers until they finish,
+  /// the next ones get the new chain. The middleware of the router the route
+  /// was mounted from keeps running first.
+  fn replace(
+    &self,
+    route: &str,
+    method: Method,
+    handlers: Vec<Arc<dyn TachyonHandler>>,
+    options: RouteOptions,
+  ) -> Result<()> {
+    if handlers.is_empty() {
+      return Err(TachyonError::new(format!(
+        "Route {} {} needs at least one handler",
+        method, route
+      )));
+    }
+
+    let not_registered =
+      || TachyonError::new(format!("Route {} {} is not registered", method, route));
+    let (routes, key) = self.lookup(route, &method)?;
+    let key = key.ok_or_else(not_registered)?;
+
+    let replacement = {
+      let Some(entry) = routes.get(&key) else {
+        return Err(not_registered());
+      };
+      let mut chain = entry.handlers()[..entry.mounted].to_vec();
+      chain.extend(handlers);
+
+      let mut replacement =
+        TachyonRouter::new(method.id(), entry.pattern().clone(), chain, options)
+          .with_hooks(entry.hooks().cloned());
+      replacement.mounted = entry.mounted;
+      replacement
+    };
+
+    {
+      let registered: Vec<_> = routes.iter().collect();
+      check_route(
+        &replacement,
+        registered
+          .iter()
+          .filter(|entry| entry.key() != &key)
+          .map(|entry| entry.value()),
+        self.match_options(),
+        self.conflict_policy(),
+      )?;
+    }
+
+    // Only swap a route that was not removed meanwhile
+    if let Some(mut entry) = routes.get_mut(&key) {
+      *entry = replacement;
+    }
+
+    Ok(())
+  }
+
+  /// Path of the route registered as `name`, its params filled from `params`
+  ///
+  /// `query` pairs are percent-encoded and appended in order.
+  fn url_for(
+    &self,
+    name: &str,
+    params: &HashMap<String, String>,
+    query: &[(String, String)],
+  ) -> Result<String> {
+    let path = self
+      .route_table()
+      .iter()
+      .find(|entry| entry.options().name.as_deref() == Some(name))
+      .map(|entry| entry.pattern().build(params))
+      .ok_or_else(|| TachyonError::new(format!("No route is named '{}'", name)))??;
+
+    if query.is_empty() {
+      return Ok(path);
+    }
+    let query = query
+      .iter()
+      .map(|(key, value)| format!("{}={}", encode_component(key), encode_component(value)))
+      .collect::<Vec<_>>()
+      .join("&");
+    Ok(format!("{}?{}", path, query))
+  }
+
+  /// Unregister a route, `false` when `route` was not registered for `method`
+  ///
+  /// The handlers are released once the requests still using them are done.
+  fn remove(&self, route: &str, method: Method) -> bool {
+    let Ok((routes, Some(key))) = self.lookup(route, &method) else {
+      return false;
+    };
+    routes.remove(&key).is_some()
+  }
+
+  /// Add hooks to a registered route, they run after the global ones
+  fn add_route_hook(
+    &self,
+    route: &str,
+    method: Method,
+    name: HookName,
+    handlers: Vec<Arc<dyn TachyonHandler>>,
+  ) -> Result<()> {
+    let (routes, key) = self.lookup(route, &method)?;
+    let Some(mut entry) = key.and_then(|key| routes.get_mut(&key)) else {
+      return Err(TachyonError::new(format!(
+        "Route {} {} is not registered",
+        method, route
+      )));
+    };
+    entry.add_hook(name, handlers);
+
+    Ok(())
+  }
+
+  /// Table holding `route`, with the path of the route in there
+  fn locate<'r>(&self, route: &'r str) -> Result<(RouteTable, &'r str)> {
+    Ok((self.route_table(), route))
+  }
+
+  /// Table holding `route` and the key it is registered under, if it is
+  ///
+  /// `route` is parsed as at registration. Another spelling of the same route
+  /// finds it too, such as other param names or `/Users/` for `/users` when
+  /// neither case nor trailing slashes matter.
+  fn lookup(&self, route: &str, method: &Method) -> Result<(RouteTable, Option<String>)> {
+    let (routes, path) = self.locate(route)?;
+    let pattern = RoutePattern::parse(path)?;
+
+    let key = route_key(method.id(), &pattern);
+    if routes.contains_key(&key) {
+      return Ok((routes, Some(key)));
+    }
+    let options = self.match_options();
+    let key = routes
+      .iter()
+      .find(|entry| entry.method() == method.id() && entry.pattern().same_route(&pattern, options))
+      .map(|entry| entry.key().clone());
+    Ok((routes, key))
+  }
+
+  /// Copy every route of `router` under `prefix`, its middleware running first
+  ///
+  /// Hooks of the router run before the ones of each route. Nothing is copied
+  /// when one of the routes is rejected.
+  fn mount(&self, prefix: &str, router: &Router) -> Result<()> {
+    let middleware = router.middleware();
+    let router_hooks = router.hooks();
+
+    let mut mounted = Vec::new();
+    for entry in router.route_table().iter() {
+      let Some((_, path)) = entry.key().split_once(':') else {
+        continue;
+      };
+
+      let mut hooks = router_hooks.clone();
+      if let Some(route_hooks) = entry.value().hooks() {
+        hooks.extend(route_hooks);
+      }
+
+      let path = utils::join_paths(prefix, path);
+      let pattern = RoutePattern::parse(&path)?;
+      mounted.push(
+        TachyonRouter::mounted(entry.value(), pattern, &middleware)
+          .with_hooks(Some(Arc::new(hooks))),
+      );
+    }
+
+    // Every route is checked before the first one is inserted
+    let routes = self.route_table();
+    {
+      let registered: Vec<_> = routes.iter().collect();
+      for (index, route) in mounted.iter().enumerate() {
+        check_route(
+          route,
+          registered
+            .iter()
+            .map(|entry| entry.value())
+            .chain(&mounted[..index]),
+          self.match_options(),
+          self.conflict_policy(),
+        )?;
+      }
+    }
+    for route in mounted {
+      routes.insert(route_key(route.method(), route.pattern()), route);
+    }
 
     Ok(())
   }
 }
+
+/// Key of a route in its table, `<method id>:<pattern>`
+fn route_key(method: u8, pattern: &RoutePattern) -> String {
+  format!("{}:{}", method, pattern.as_str())
+}
+
+/// Fail when `router` cannot join `others`
+///
+/// A name already taken is always an error, a duplicate or overlapping pattern
+/// follows `policy`. Without one, registering the same route twice is an error
+/// and an overlapping pattern a warning.
+fn check_route<'a>(
+  router: &TachyonRouter,
+  others: impl IntoIterator<Item = &'a TachyonRouter>,
+  options: MatchOptions,
+  policy: Option<ConflictPolicy>,
+) -> Result<()> {
+  let method = Method::new(router.method());
+  let path = router.pattern().as_str();
+
+  let mut existing: Option<&str> = None;
+  for other in others {
+    let same_method = other.method() == router.method();
+    let same_route = same_method && other.pattern().as_str() == path;
+
+    if let Some(name) = &router.options().name {
+      if !same_route && other.options().name.as_ref() == Some(name) {
+        return Err(TachyonError::new(format!(
+          "Route name '{}' is already used by {} {}",
+          name,
+          Method::new(other.method()),
+          other.pattern().as_str()
+        )));
+      }
+    }
+
+    if same_route {
+      existing = Some(path);
+    } else if existing.is_none()
+      && same_method
+      && other.pattern().conflicts_with(router.pattern(), options)
+    {
+      existing = Some(other.pattern().as_str());
+    }
+  }
+
+  let Some(existing) = existing else {
+    return Ok(());
+  };
+  let (reason, default_policy) = if existing == path {
+    (
+      format!("Route {} {} is already registered", method, path),
+      ConflictPolicy::Throw,
+    )
+  } else {
+    (
+      format!(
+        "Route {} {} conflicts with {} {}",
+        method, path, method, existing
+      ),
+      ConflictPolicy::Warn,
+    )
+  };
+  match policy.unwrap_or(default_policy) {
+    ConflictPolicy::Throw => Err(TachyonError::new(reason)),
+    ConflictPolicy::Warn => {
+      eprintln!("Warning: {}", reason);
+      Ok(())
+    }
+  }
+}
+
+impl HTTPCall for Tachyon {
+  fn route_table(&self) -> Arc<DashMap<String, TachyonRouter>> {
+    self.get_routes()
+  }
+
+  fn conflict_policy(&self) -> Option<ConflictPolicy> {
+    self.options().conflict_policy()
+  }
+
+  fn match_options(&self) -> MatchOptions {
+    self.options().match_options()
+  }
+
+  /// Routes of virtual hosts are spelled `host/path`, as listed by [`Tachyon::routes`]
+  fn locate<'r>(&self, route: &'r str) -> Result<(RouteTable, &'r str)> {
+    let Some(slash) = route.find('/').filter(|&slash| slash > 0) else {
+      return Ok((self.route_table(), route));
+    };
+    let (host, path) = route.split_at(slash);
+    let pattern = HostPattern::parse(host)?;
+    self
+      .virtual_hosts()
+      .into_iter()
+      .find(|virtual_host| virtual_host.pattern() == &pattern)
+      .map(|virtual_host| (virtual_host.route_table(), path))
+      .ok_or_else(|| TachyonError::new(format!("No router is mounted for host '{}'", host)))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::core::options::TachyonOptions;
+  use crate::testing::{call, get};
+  use serde_json::json;
+
+  #[tokio::test]
+  async fn test_chain_stops_after_send() {
+    let chain = HandlerChain::new(vec![
+      Arc::new(|_req, _res: TachyonResponse| async move {}),
+      Arc::new(|_req, res: TachyonResponse| async move {
+        res.status(201).send(Some(json!("created")));
+      }),
+      Arc::new(|_req, res: TachyonResponse| async move {
+        res.send(Some(json!("unreachable")));
+      }),
+    ]);
+
+    let res = TachyonResponse::new();
+    chain.call(TachyonRequest::default(), res.clone()).await;
+
+    assert_eq!(res.get_status(), 201);
+    assert_eq!(res.take_data().as_deref(), Some("\"created\""));
+  }
+
+  #[test]
+  fn test_register_native_route() {
+    let app = Tachyon::new();
+    app
+      .route(
+        Method::Get,
+        "/health",
+        |_req, res: TachyonResponse| async move {
+          res.send(Some(json!({ "status": "OK" })));
+        },
+      )
+      .unwrap();
+
+    assert_eq!(app.routes(), vec!["/health GET".to_string()]);
+    assert!(app.register("/empty", Method::Get, vec![]).is_err());
+
+    let handler: Arc<dyn TachyonHandler> = Arc::new(|_req, _res| async move {});
+    assert!(app
+      .register("/files/*path/meta", Method::Get, vec![handler.clone()])
+      .is_err());
+    assert!(app
+      .register(r"/users/:id(\d+", Method::Get, vec![handler])
+      .is_err());
+  }
+
+  #[test]
+  fn test_route_conflicts() {
+    let handler: Arc<dyn TachyonHandler> = Arc::new(|_req, _res| async move {});
+    let register = |app: &Tachyon, route: &str, method: Method| {
+      app.register(route, method, vec![handler.clone()])
+    };
+
+    let app = Tachyon::with_options(TachyonOptions {
+      route_conflicts: Some(ConflictPolicy::Throw),
+      ..Default::default()
+    });
+    register(&app, "/users/:id", Method::Get).unwrap();
+    register(&app, "/users/me", Method::Get).unwrap();
+    register(&app, "/users/:userId", Method::Post).unwrap();
+
+    assert_eq!(
+      register(&app, "/users/:id", Method::Get)
+        .unwrap_err()
+        .reason(),
+      "Route GET /users/:id is already registered"
+    );
+    assert_eq!(
+      register(&app, "/users/:userId", Method::Get)
+        .unwrap_err()
+        .reason(),
+      "Route GET /users/:userId conflicts with GET /users/:id"
+    );
+
+    // A rejected mount leaves the table as it was
+    let admin = Router::new();
+    admin
+      .register("/users/:id", Method::Delete, vec![handler.clone()])
+      .unwrap();
+    admin
+      .register("/:section", Method::Get, vec![handler.clone()])
+      .unwrap();
+    admin
+      .register("/stats", Method::Get, vec![handler.clone()])
+      .unwrap();
+    assert_eq!(
+      app.mount("/users", &admin).unwrap_err().reason(),
+      "Route GET /users/:section conflicts with GET /users/:id"
+    );
+    assert_eq!(app.routes().len(), 3);
+
+    // By default only overlaps warn, the last registration wins
+    let app = Tachyon::new();
+    register(&app, "/users/:id", Method::Get).unwrap();
+    assert_eq!(
+      register(&app, "/users/:id", Method::Get)
+        .unwrap_err()
+        .reason(),
+      "Route GET /users/:id is already registered"
+    );
+    register(&app, "/users/:userId", Method::Get).unwrap();
+    register(&app, r"/users/:id(\d+)", Method::Get).unwrap();
+    assert_eq!(app.routes().len(), 3);
+
+    let app = Tachyon::with_options(TachyonOptions {
+      route_conflicts: Some(ConflictPolicy::Warn),
+      ..Default::default()
+    });
+    register(&app, "/users/:id", Method::Get).unwrap();
+    register(&app, "/users/:id", Method::Get).unwrap();
+    assert_eq!(app.routes().len(), 1);
+  }
+
+  #[tokio::test]
+  async fn test_replace_mounted_route() {
+    let handler: Arc<dyn TachyonHandler> = Arc::new(|_req, _res| async move {});
+    let require_auth: Arc<dyn TachyonHandler> = Arc::new(|_req, res: TachyonResponse| async move {
+      res.status(401).send(Some(json!("unauthorized")));
+    });
+    let admin = Router::new();
+    admin.add_middleware(vec![require_auth]);
+    admin
+      .register_with(
+        "/stats",
+        Method::Get,
+        vec![handler.clone()],
+        RouteOptions {
+          name: Some("admin.stats".to_string()),
+          ..Default::default()
+        },
+      )
+      .unwrap();
+
+    let app = Tachyon::new();
+    app
+      .register_with(
+        "/health",
+        Method::Get,
+        vec![handler.clone()],
+        RouteOptions {
+          name: Some("health".to_string()),
+          ..Default::default()
+        },
+      )
+      .unwrap();
+    app.mount("/admin", &admin).unwrap();
+
+    let stats: Arc<dyn TachyonHandler> = Arc::new(|_req, res: TachyonResponse| async move {
+      res.send(Some(json!({ "users": 1 })));
+    });
+    app
+      .replace(
+        "/admin/stats",
+        Method::Get,
+        vec![stats],
+        RouteOptions::default(),
+      )
+      .unwrap();
+
+    // The middleware of the router still answers first
+    let handler = app
+      .route_table()
+      .get(&format!("{}:/admin/stats", Method::Get.id()))
+      .unwrap()
+      .handler();
+    let res = TachyonResponse::new();
+    handler.call(TachyonRequest::default(), res.clone()).await;
+    assert_eq!(res.get_status(), 401);
+    assert_eq!(app.describe_routes()[0].middleware, 1);
+
+    assert_eq!(
+      app
+        .replace(
+          "/admin/stats",
+          Method::Get,
+          vec![Arc::new(|_req, _res| async move {})],
+          RouteOptions {
+            name: Some("health".to_string()),
+            ..Default::default()
+          },
+        )
+        .unwrap_err()
+        .reason(),
+      "Route name 'health' is already used by GET /health"
+    );
+  }
+
+  #[test]
+  fn test_describe_routes() {
+    let handler: Arc<dyn TachyonHandler> = Arc::new(|_req, _res| async move {});
+    let app = Tachyon::new();
+    app
+      .register("/users", Method::Post, vec![handler.clone()])
+      .unwrap();
+    app
+      .register_with(
+        "/users/:id",
+        Method::Get,
+        vec![handler.clone(), handler.clone()],
+        RouteOptions {
+          meta: Some(json!({ "tags": ["users"] })),
+          ..Default::default()
+        },
+      )
+      .unwrap();
+    app
+      .register("/", Method::Get, vec![handler.clone()])
+      .unwrap();
+    app.register("/users", Method::Get, vec![handler]).unwrap();
+
+    assert_eq!(
+      app.routes(),
+      vec!["/ GET", "/users GET", "/users POST", "/users/:id GET"]
+    );
+
+    let routes = app.describe_routes();
+    assert_eq!(routes[3].params, vec!["id".to_string()]);
+    assert_eq!(routes[3].middleware, 1);
+    assert_eq!(routes[3].meta, Some(json!({ "tags": ["users"] })));
+
+    assert_eq!(
+      app.print_routes(),
+      "METHOD  PATH        PARAMS  MIDDLEWARE\n\
+       GET     /           -       0\n\
+       GET     /users      -       0\n\
+       POST    /users      -       0\n\
+       GET     /users/:id  id      1\n"
+    );
+  }
+
+  #[test]
+  fn test_named_routes() {
+    let handler: Arc<dyn TachyonHandler> = Arc::new(|_req, _res| async move {});
+    let named = |name: &str| RouteOptions {
+      name: Some(name.to_string()),
+      ..Default::default()
+    };
+    let app = Tachyon::new();
+    app
+      .register_with(
+        r"/users/:id(\d+)",
+        Method::Get,
+        vec![handler.clone()],
+        named("user.show"),
+      )
+      .unwrap();
+
+    let params = HashMap::from([("id".to_string(), "5".to_string())]);
+    let query = vec![
+      ("tab".to_string(), "posts & more".to_string()),
+      ("page".to_string(), "2".to_string()),
+    ];
+    assert_eq!(
+      app.url_for("user.show", &params, &query).unwrap(),
+      "/users/5?tab=posts%20%26%20more&page=2"
+    );
+    assert_eq!(
+      app
+        .url_for("user.show", &HashMap::new(), &[])
+        .unwrap_err()
+        .reason(),
+      r"Cannot build '/users/:id(\d+)': missing parameter ':id'"
+    );
+    assert!(app.url_for("user.edit", &params, &[]).is_err());
+
+    assert_eq!(
+      app
+        .register_with("/me", Method::Get, vec![handler], named("user.show"))
+        .unwrap_err()
+        .reason(),
+      r"Route name 'user.show' is already used by GET /users/:id(\d+)"
+    );
+  }
+
+  #[test]
+  fn test_find_routes_by_any_spelling() {
+    let handler: Arc<dyn TachyonHandler> = Arc::new(|_req, _res| async move {});
+    let hook = || vec![handler.clone()];
+
+    let app = Tachyon::with_options(TachyonOptions {
+      strict_trailing_slash: Some(false),
+      case_sensitive: Some(false),
+      ..Default::default()
+    });
+    app
+      .register("/users/:id(\\d+)", Method::Get, hook())
+      .unwrap();
+    app.register("/about", Method::Get, hook()).unwrap();
+    let docs = Router::new();
+    docs.register("/guide/", Method::Get, hook()).unwrap();
+    app.mount("/docs/", &docs).unwrap();
+    app.mount_host(":tenant.example.com", &docs).unwrap();
+
+    app
+      .add_route_hook(
+        "/users/:userId(\\d+)",
+        Method::Get,
+        HookName::OnSend,
+        hook(),
+      )
+      .unwrap();
+    app
+      .replace("/About/", Method::Get, hook(), RouteOptions::default())
+      .unwrap();
+    // The route keeps the spelling it was registered with
+    assert!(app.routes().contains(&"/about GET".to_string()));
+
+    assert_eq!(
+      app
+        .add_route_hook("/users/:id", Method::Get, HookName::OnSend, hook())
+        .unwrap_err()
+        .reason(),
+      "Route GET /users/:id is not registered"
+    );
+    assert_eq!(
+      app
+        .replace("users", Method::Get, hook(), RouteOptions::default())
+        .unwrap_err()
+        .reason(),
+      "Invalid route 'users': it must start with '/'"
+    );
+    assert!(!app.remove("/about", Method::Post));
+
+    assert!(app.remove("/docs/guide", Method::Get));
+    assert!(app.remove(":tenant.example.com/guide", Method::Get));
+    assert!(!app.remove("api.example.com/guide", Method::Get));
+    assert_eq!(app.routes(), vec!["/about GET", "/users/:id(\\d+) GET"]);
+  }
+
+  #[tokio::test]
+  async fn test_remove_and_replace_routes() {
+    let app = Tachyon::new();
+    let release = Arc::new(tokio::sync::Notify::new());
+    let waiting = Arc::clone(&release);
+    app
+      .route(Method::Get, "/slow", move |_req, res: TachyonResponse| {
+        let waiting = Arc::clone(&waiting);
+        async move {
+          waiting.notified().await;
+          res.send(Some("old".into()));
+        }
+      })
+      .unwrap();
+
+    let mut service = app.service();
+
+    // Swap the handler while a request is still waiting on the old one
+    let in_flight = tokio::spawn({
+      let mut service = service.clone();
+      async move { call(&mut service, get("/slow")).await }
+    });
+    tokio::task::yield_now().await;
+    let handler: Arc<dyn TachyonHandler> = Arc::new(|_req, res: TachyonResponse| async move {
+      res.send(Some("new".into()));
+    });
+    app
+      .replace("/slow", Method::Get, vec![handler], Default::default())
+      .unwrap();
+    release.notify_one();
+
+    assert_eq!(in_flight.await.unwrap().text(), "\"old\"");
+    assert_eq!(call(&mut service, get("/slow")).await.text(), "\"new\"");
+
+    assert!(app.remove("/slow", Method::Get));
+    assert!(!app.remove("/slow", Method::Get));
+    let res = call(&mut service, get("/slow")).await;
+    assert_eq!(res.status, hyper::StatusCode::NOT_FOUND);
+
+    let handler: Arc<dyn TachyonHandler> = Arc::new(|_req, _res| async move {});
+    assert_eq!(
+      app
+        .replace("/slow", Method::Get, vec![handler], Default::default())
+        .unwrap_err()
+        .reason(),
+      "Route GET /slow is not registered"
+    );
+  }
+}
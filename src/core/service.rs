@@ -0,0 +1,327 @@
+use bytes::Bytes;
+use http_body_util::{combinators::BoxBody, BodyExt};
+use hyper::{body::Incoming, Request, Response};
+use std::{
+  future::Future,
+  pin::Pin,
+  sync::Arc,
+  task::{Context, Poll},
+};
+use tower::{util::BoxCloneService, BoxError, Layer, Service, ServiceExt};
+
+use crate::core::tachyon::Tachyon;
+
+/// Response body produced once `tower` layers are applied
+pub type LayeredBody = BoxBody<Bytes, BoxError>;
+
+/// The app wrapped in every registered layer
+pub type LayeredService = BoxCloneService<Request<Incoming>, Response<LayeredBody>, BoxError>;
+
+/// Type-erased `tower::Layer`, applied when the server starts
+pub type BoxLayer = Arc<dyn Fn(LayeredService) -> LayeredService + Send + Sync>;
+
+/// `tower::Service` answering requests from a `Tachyon` route table
+///
+/// Generic over the request body so it can be mounted in any hyper, axum or
+/// tower based server, not only the one started by `listen`.
+#[derive(Clone)]
+pub struct TachyonService {
+  app: Tachyon,
+}
+
+impl TachyonService {
+  pub(crate) fn new(app: Tachyon) -> Self {
+    Self { app }
+  }
+}
+
+impl<B> Service<Request<B>> for TachyonService
+where
+  B: hyper::body::Body<Data = Bytes> + Send + 'static,
+  B::Error: Send,
+{
+  type Response = Response<LayeredBody>;
+  type Error = B::Error;
+  type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+  #[inline]
+  fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+    Poll::Ready(Ok(()))
+  }
+
+  #[inline]
+  fn call(&mut self, req: Request<B>) -> Self::Future {
+    Box::pin(self.app.clone().echo(req))
+  }
+}
+
+/// Erase a layer so it can be stored next to the others
+pub fn box_layer<L, ResBody>(layer: L) -> BoxLayer
+where
+  L: Layer<LayeredService> + Send + Sync + 'static,
+  L::Service: Service<Request<Incoming>, Response = Response<ResBody>> + Clone + Send + 'static,
+  <L::Service as Service<Request<Incoming>>>::Error: Into<BoxError>,
+  <L::Service as Service<Request<Incoming>>>::Future: Send + 'static,
+  ResBody: hyper::body::Body<Data = Bytes> + Send + Sync + 'static,
+  ResBody::Error: Into<BoxError>,
+{
+  Arc::new(move |inner| {
+    layer
+      .layer(inner)
+      .map_response(|res| res.map(|body| body.map_err(Into::into).boxed()))
+      .map_err(Into::into)
+      .boxed_clone()
+  })
+}
+
+/// Wrap the bare app service in `layers`, the last one being the outermost
+pub fn apply_layers(service: TachyonService, layers: &[BoxLayer]) -> LayeredService {
+  let base = BoxCloneService::new(ServiceExt::<Request<Incoming>>::map_err(
+    service,
+    BoxError::from,
+  ));
+
+  layers.iter().fold(base, |service, layer| layer(service))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::core::{methods::Method, options::TachyonOptions};
+  use crate::testing::{call, fetch, get, serve};
+  use crate::{HttpError, TachyonRequest, TachyonResponse};
+  use http_body_util::Full;
+  use hyper::StatusCode;
+
+  #[tokio::test]
+  async fn test_service_routes_request() {
+    let app = Tachyon::new();
+    app
+      .route(
+        Method::Get,
+        "/ping",
+        |_req, res: TachyonResponse| async move {
+          res.send(Some("pong".into()));
+        },
+      )
+      .unwrap();
+
+    let mut service = app.service();
+
+    let req = Request::get("/ping").body(Full::new(Bytes::new())).unwrap();
+    let res = ServiceExt::<Request<Full<Bytes>>>::ready(&mut service)
+      .await
+      .unwrap()
+      .call(req)
+      .await
+      .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let body = res.into_body().collect().await.unwrap().to_bytes();
+    assert_eq!(body, Bytes::from_static(b"\"pong\""));
+
+    let res = call(&mut service, get("/missing")).await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+  }
+
+  #[tokio::test]
+  async fn test_service_normalizes_paths() {
+    let app = Tachyon::new();
+    app
+      .route(
+        Method::Get,
+        "/users/:name",
+        |req: TachyonRequest, res: TachyonResponse| async move {
+          res.send(Some(req.params["name"].clone().into()));
+        },
+      )
+      .unwrap();
+
+    let mut service = app.service();
+    let res = call(&mut service, get("/a/..//users/./john%20doe")).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.text(), "\"john doe\"");
+
+    for uri in ["/../users/john", "/users/%FF"] {
+      let res = call(&mut service, get(uri)).await;
+      assert_eq!(res.status, StatusCode::BAD_REQUEST, "{}", uri);
+    }
+  }
+
+  #[tokio::test]
+  async fn test_service_trailing_slash_and_case() {
+    let routes = |options: TachyonOptions| {
+      let app = Tachyon::with_options(options);
+      for route in ["/users", "/users/:id/"] {
+        app
+          .route(
+            Method::Get,
+            route,
+            |_req, res: TachyonResponse| async move {
+              res.send(Some("ok".into()));
+            },
+          )
+          .unwrap();
+      }
+      app.service()
+    };
+
+    let mut loose = routes(TachyonOptions {
+      strict_trailing_slash: Some(false),
+      case_sensitive: Some(false),
+      ..Default::default()
+    });
+    for uri in ["/users", "/users/", "/USERS", "/Users/1", "/users/1/"] {
+      let res = call(&mut loose, get(uri)).await;
+      assert_eq!(res.status, StatusCode::OK, "{}", uri);
+    }
+
+    // Strict by default, as the conflict checks of registration
+    let mut strict = routes(TachyonOptions::default());
+    for uri in ["/users/", "/USERS"] {
+      let res = call(&mut strict, get(uri)).await;
+      assert_eq!(res.status, StatusCode::NOT_FOUND, "{}", uri);
+    }
+
+    let mut redirecting = routes(TachyonOptions {
+      redirect_trailing_slash: Some(true),
+      ..Default::default()
+    });
+    let res = call(&mut redirecting, get("/users/1?tab=posts")).await;
+    assert_eq!(res.status, StatusCode::MOVED_PERMANENTLY);
+    assert_eq!(res.headers["location"], "/users/1/?tab=posts");
+  }
+
+  #[tokio::test]
+  async fn test_not_found_and_error_handlers() {
+    let app = Tachyon::with_options(TachyonOptions {
+      body_limit: Some(8),
+      ..Default::default()
+    });
+    app
+      .route(
+        Method::Post,
+        "/items",
+        |_req, res: TachyonResponse| async move {
+          res.send(Some("created".into()));
+        },
+      )
+      .unwrap();
+    app
+      .route(
+        Method::Get,
+        "/broken",
+        |_req, res: TachyonResponse| async move {
+          res.fail(HttpError::handler_error("database is down"));
+        },
+      )
+      .unwrap();
+
+    let mut service = app.service();
+    let post = |body: &'static str| {
+      Request::post("/items")
+        .body(Full::new(Bytes::from_static(body.as_bytes())))
+        .unwrap()
+    };
+
+    // Plain defaults, server errors keep their details out of the body
+    let res = call(&mut service, get("/missing")).await;
+    assert_eq!(
+      (res.status, res.text()),
+      (StatusCode::NOT_FOUND, "Not Found")
+    );
+    let res = call(&mut service, get("/items")).await;
+    assert_eq!(res.status, StatusCode::METHOD_NOT_ALLOWED);
+    assert_eq!(res.headers[hyper::header::ALLOW], "POST");
+    let res = call(&mut service, post("0123456789")).await;
+    assert_eq!(res.status, StatusCode::PAYLOAD_TOO_LARGE);
+    let res = call(&mut service, post("{}")).await;
+    assert_eq!(res.status, StatusCode::OK);
+    let res = call(&mut service, get("/broken")).await;
+    assert_eq!(
+      (res.status, res.text()),
+      (StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error")
+    );
+
+    app.set_not_found(|_req, res: TachyonResponse| async move {
+      res.send(Some("nothing here".into()));
+    });
+    app.set_error_handler(|err: HttpError, _req, res: TachyonResponse| async move {
+      res
+        .status(err.status)
+        .send(Some(serde_json::json!({ "error": err.code })));
+    });
+
+    let res = call(&mut service, get("/missing")).await;
+    assert_eq!(
+      (res.status, res.text()),
+      (StatusCode::NOT_FOUND, "\"nothing here\"")
+    );
+    let res = call(&mut service, get("/items")).await;
+    assert_eq!(res.status, StatusCode::METHOD_NOT_ALLOWED);
+    assert_eq!(res.headers[hyper::header::ALLOW], "POST");
+    assert_eq!(res.text(), r#"{"error":"METHOD_NOT_ALLOWED"}"#);
+    let res = call(&mut service, post("0123456789")).await;
+    assert_eq!(res.text(), r#"{"error":"PAYLOAD_TOO_LARGE"}"#);
+    let res = call(&mut service, get("/broken")).await;
+    assert_eq!(
+      (res.status, res.text()),
+      (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        r#"{"error":"HANDLER_ERROR"}"#
+      )
+    );
+  }
+
+  #[tokio::test]
+  async fn test_body_limit_is_opt_in() {
+    let app = |body_limit| {
+      let app = Tachyon::with_options(TachyonOptions {
+        body_limit,
+        ..Default::default()
+      });
+      app
+        .route(
+          Method::Post,
+          "/upload",
+          |_req, res: TachyonResponse| async move {
+            res.send(Some("stored".into()));
+          },
+        )
+        .unwrap();
+      app.service()
+    };
+    let upload = || {
+      Request::post("/upload")
+        .body(Full::new(Bytes::from(vec![b'x'; 2 * 1024 * 1024])))
+        .unwrap()
+    };
+
+    let res = call(&mut app(None), upload()).await;
+    assert_eq!(res.status, StatusCode::OK);
+    let res = call(&mut app(Some(1024 * 1024)), upload()).await;
+    assert_eq!(res.status, StatusCode::PAYLOAD_TOO_LARGE);
+  }
+
+  #[tokio::test]
+  async fn test_layers_wrap_served_requests() {
+    let app = Tachyon::new();
+    app
+      .route(Method::Get, "/", |_req, res: TachyonResponse| async move {
+        res.send(Some("home".into()));
+      })
+      .unwrap();
+    app.layer(tower::layer::layer_fn(|service: LayeredService| {
+      service.map_response(|mut res: Response<LayeredBody>| {
+        res
+          .headers_mut()
+          .insert("x-layer", hyper::header::HeaderValue::from_static("on"));
+        res
+      })
+    }));
+
+    let raw = String::from_utf8(fetch(serve(app), "/").await).unwrap();
+    assert!(raw.starts_with("HTTP/1.1 200 OK"));
+    assert!(raw.contains("x-layer: on"));
+    assert!(raw.ends_with("\"home\""));
+  }
+}
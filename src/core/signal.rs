@@ -0,0 +1,385 @@
+use std::{
+  fmt,
+  sync::{Arc, OnceLock},
+};
+use tokio::sync::Notify;
+
+/// Why a request was aborted before its handlers settled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AbortReason {
+  /// The client closed the connection
+  Disconnected,
+  /// A handler did not settle in time
+  TimedOut,
+}
+
+impl AbortReason {
+  /// Name of the `DOMException` set as `signal.reason` in JS
+  pub fn name(&self) -> &'static str {
+    match self {
+      AbortReason::Disconnected => "AbortError",
+      AbortReason::TimedOut => "TimeoutError",
+    }
+  }
+
+  pub fn message(&self) -> &'static str {
+    match self {
+      AbortReason::Disconnected => "The client closed the connection",
+      AbortReason::TimedOut => "The handler timed out",
+    }
+  }
+}
+
+/// Fires once the request is abandoned, so that handlers can stop their work
+///
+/// JS handlers get it as `req.signal`, a regular `AbortSignal` that can be
+/// passed on to `fetch` or database drivers.
+#[derive(Clone, Default)]
+pub struct AbortSignal {
+  inner: Arc<SignalInner>,
+}
+
+#[derive(Default)]
+struct SignalInner {
+  reason: OnceLock<AbortReason>,
+  notify: Notify,
+  #[cfg(feature = "napi")]
+  js: std::sync::Mutex<Option<js::JsSignal>>,
+}
+
+impl AbortSignal {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Abort the request, only the first reason is kept
+  pub fn abort(&self, reason: AbortReason) {
+    if self.inner.reason.set(reason).is_err() {
+      return;
+    }
+    self.inner.notify.notify_waiters();
+
+    #[cfg(feature = "napi")]
+    self.abort_js();
+  }
+
+  pub fn is_aborted(&self) -> bool {
+    self.inner.reason.get().is_some()
+  }
+
+  pub fn reason(&self) -> Option<AbortReason> {
+    self.inner.reason.get().copied()
+  }
+
+  /// Wait until the request is aborted
+  ///
+  /// ```ignore
+  /// tokio::select! {
+  ///   rows = db.query(sql) => res.send(Some(rows.into())),
+  ///   _ = req.signal.aborted() => {}
+  /// }
+  /// ```
+  pub async fn aborted(&self) -> AbortReason {
+    loop {
+      let notified = self.inner.notify.notified();
+      if let Some(reason) = self.reason() {
+        return reason;
+      }
+      notified.await;
+    }
+  }
+
+  /// Abort with [`AbortReason::Disconnected`] when the guard is dropped armed
+  pub(crate) fn abort_on_drop(&self) -> AbortGuard {
+    AbortGuard {
+      signal: Some(self.clone()),
+    }
+  }
+}
+
+impl fmt::Debug for AbortSignal {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("AbortSignal")
+      .field("reason", &self.reason())
+      .finish()
+  }
+}
+
+/// Aborts the request when hyper drops its future, i.e. the client went away
+pub(crate) struct AbortGuard {
+  signal: Option<AbortSignal>,
+}
+
+impl AbortGuard {
+  pub fn disarm(mut self) {
+    self.signal = None;
+  }
+}
+
+impl Drop for AbortGuard {
+  fn drop(&mut self) {
+    if let Some(signal) = self.signal.take() {
+      signal.abort(AbortReason::Disconnected);
+    }
+  }
+}
+
+#[cfg(feature = "napi")]
+mod js {
+  use napi::{
+    bindgen_prelude::{
+      FnArgs, FromNapiValue, Function, Object, ToNapiValue, TypeName, ValidateNapiValue,
+    },
+    sys, Env, JsValue, Result, ValueType,
+  };
+
+  use super::{AbortReason, AbortSignal};
+  use crate::core::js_ref::{script, JsRef};
+
+  /// Creates an `AbortController` with a function aborting it with a `DOMException`
+  const CONTROLLER: &str = r#"() => {
+  const controller = new AbortController()
+  return {
+    signal: controller.signal,
+    abort: (name, message) => controller.abort(new DOMException(message, name)),
+  }
+}"#;
+
+  /// Controller of the JS `AbortSignal`, only created once JS reads `req.signal`
+  pub(super) struct JsSignal {
+    controller: JsRef,
+  }
+
+  impl JsSignal {
+    fn signal<'env>(&self, env: &'env Env) -> Result<Object<'env>> {
+      self
+        .controller
+        .get(env)?
+        .get("signal")?
+        .ok_or_else(|| napi::Error::from_reason("AbortController is not available"))
+    }
+
+    fn abort(&self, env: &Env, reason: AbortReason) -> Result<()> {
+      let abort: Function<FnArgs<(String, String)>, ()> =
+        self
+          .controller
+          .get(env)?
+          .get("abort")?
+          .ok_or_else(|| napi::Error::from_reason("AbortController is not available"))?;
+      abort.call((reason.name().to_string(), reason.message().to_string()).into())
+    }
+  }
+
+  impl AbortSignal {
+    /// Abort the JS signal on the JS thread, if JS asked for it
+    pub(super) fn abort_js(&self) {
+      let Ok(slot) = self.inner.js.lock() else {
+        return;
+      };
+      let Some(js) = slot.as_ref() else {
+        return;
+      };
+      let signal = self.clone();
+      js.controller.queue(move |env| {
+        let slot = signal
+          .inner
+          .js
+          .lock()
+          .map_err(|_| napi::Error::from_reason("Request signal is unavailable"))?;
+        match (slot.as_ref(), signal.reason()) {
+          (Some(js), Some(reason)) => js.abort(env, reason),
+          _ => Ok(()),
+        }
+      });
+    }
+
+    /// The JS `AbortSignal` of the request, created on first use
+    pub fn object<'env>(&self, env: &'env Env) -> Result<Object<'env>> {
+      let mut slot = self
+        .inner
+        .js
+        .lock()
+        .map_err(|_| napi::Error::from_reason("Request signal is unavailable"))?;
+      if let Some(js) = slot.as_ref() {
+        return js.signal(env);
+      }
+
+      let create: Function<(), Object> = script(env, CONTROLLER)?;
+      let js = JsSignal {
+        controller: JsRef::new(env, &create.call(())?)?,
+      };
+      // Aborted before JS asked for the signal
+      if let Some(reason) = self.reason() {
+        js.abort(env, reason)?;
+      }
+      let signal = js.signal(env)?;
+      *slot = Some(js);
+      Ok(signal)
+    }
+  }
+
+  impl TypeName for AbortSignal {
+    fn type_name() -> &'static str {
+      "AbortSignal"
+    }
+
+    fn value_type() -> ValueType {
+      ValueType::Object
+    }
+  }
+
+  impl ValidateNapiValue for AbortSignal {}
+
+  impl ToNapiValue for AbortSignal {
+    unsafe fn to_napi_value(env: sys::napi_env, val: Self) -> Result<sys::napi_value> {
+      let env = Env::from_raw(env);
+      Ok(val.object(&env)?.raw())
+    }
+  }
+
+  /// Signals coming back from JS are not tied to a request
+  impl FromNapiValue for AbortSignal {
+    unsafe fn from_napi_value(_env: sys::napi_env, _napi_val: sys::napi_value) -> Result<Self> {
+      Ok(AbortSignal::new())
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::core::methods::Method;
+  use crate::testing::{call, connect, get, serve};
+  use crate::{Tachyon, TachyonOptions, TachyonRequest, TachyonResponse};
+  use std::time::Duration;
+  use tokio::io::AsyncWriteExt;
+  use tower::Service;
+
+  #[tokio::test]
+  async fn test_abort_signal() {
+    let signal = AbortSignal::new();
+    let waiting = tokio::spawn({
+      let signal = signal.clone();
+      async move { signal.aborted().await }
+    });
+    tokio::task::yield_now().await;
+
+    signal.abort(AbortReason::TimedOut);
+    signal.abort(AbortReason::Disconnected);
+    assert_eq!(waiting.await.unwrap(), AbortReason::TimedOut);
+    assert_eq!(signal.reason(), Some(AbortReason::TimedOut));
+
+    let signal = AbortSignal::new();
+    signal.abort_on_drop().disarm();
+    assert!(!signal.is_aborted());
+    drop(signal.abort_on_drop());
+    assert_eq!(signal.aborted().await, AbortReason::Disconnected);
+  }
+
+  #[tokio::test]
+  async fn test_handler_timeout() {
+    let slow_app = |options: TachyonOptions| {
+      let app = Tachyon::with_options(options);
+      let (aborted, reason) = tokio::sync::mpsc::unbounded_channel();
+      app
+        .route(
+          Method::Get,
+          "/report",
+          move |req: TachyonRequest, res: TachyonResponse| {
+            let aborted = aborted.clone();
+            async move {
+              // e.g. a slow database query
+              tokio::select! {
+                _ = tokio::time::sleep(Duration::from_millis(200)) => {
+                  res.send(Some("done".into()));
+                }
+                reason = req.signal.aborted() => {
+                  let _ = aborted.send(reason);
+                }
+              }
+            }
+          },
+        )
+        .unwrap();
+      (app.service(), reason)
+    };
+
+    // Handlers take as long as they need by default
+    let (mut service, _) = slow_app(TachyonOptions::default());
+    let res = call(&mut service, get("/report")).await;
+    assert_eq!(res.status, hyper::StatusCode::OK);
+
+    let (mut service, mut reason) = slow_app(TachyonOptions {
+      handler_timeout: Some(50),
+      ..Default::default()
+    });
+    let res = call(&mut service, get("/report")).await;
+    assert_eq!(res.status, hyper::StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(reason.recv().await, Some(AbortReason::TimedOut));
+  }
+
+  #[tokio::test]
+  async fn test_signal_aborts_on_disconnect() {
+    let app = Tachyon::new();
+    let (aborted, mut reason) = tokio::sync::mpsc::unbounded_channel();
+    app
+      .route(
+        Method::Get,
+        "/report",
+        move |req: TachyonRequest, _res: TachyonResponse| {
+          let aborted = aborted.clone();
+          async move {
+            // Work outliving the request, e.g. a query running elsewhere
+            tokio::spawn(async move {
+              let _ = aborted.send(req.signal.aborted().await);
+            });
+            std::future::pending::<()>().await;
+          }
+        },
+      )
+      .unwrap();
+
+    let in_flight = tokio::spawn(app.service().call(get("/report")));
+    tokio::task::yield_now().await;
+
+    // Dropping the request future is what hyper does when the client goes away
+    in_flight.abort();
+    assert_eq!(reason.recv().await, Some(AbortReason::Disconnected));
+  }
+
+  #[tokio::test]
+  async fn test_signal_aborts_when_client_closes() {
+    let app = Tachyon::new();
+    let (started, mut running) = tokio::sync::mpsc::unbounded_channel();
+    let (aborted, mut reason) = tokio::sync::mpsc::unbounded_channel();
+    app
+      .route(
+        Method::Get,
+        "/report",
+        move |req: TachyonRequest, _res: TachyonResponse| {
+          let _ = started.send(());
+          let aborted = aborted.clone();
+          async move {
+            // Work outliving the request, e.g. a query running elsewhere
+            tokio::spawn(async move {
+              let _ = aborted.send(req.signal.aborted().await);
+            });
+            std::future::pending::<()>().await;
+          }
+        },
+      )
+      .unwrap();
+
+    let mut stream = connect(serve(app)).await;
+    stream
+      .write_all(b"GET /report HTTP/1.1\r\nHost: localhost\r\n\r\n")
+      .await
+      .unwrap();
+    running.recv().await;
+    // The client goes away before the handler answers
+    drop(stream);
+
+    let closed = tokio::time::timeout(Duration::from_secs(5), reason.recv()).await;
+    assert_eq!(closed, Ok(Some(AbortReason::Disconnected)));
+  }
+}
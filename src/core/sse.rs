@@ -0,0 +1,183 @@
+use bytes::Bytes;
+#[cfg(feature = "napi")]
+use napi_derive::napi;
+use serde_json::Value;
+use std::{fmt::Write, sync::Arc};
+
+use crate::core::stream::ResponseStream;
+
+/// Comment keeping idle connections open through proxies
+pub(crate) const KEEP_ALIVE: &[u8] = b": keep-alive\n\n";
+
+/// One message of a Server-Sent Events stream
+#[cfg_attr(feature = "napi", napi(object))]
+#[derive(Debug, Clone, Default)]
+pub struct SseEvent {
+  /// Event type, the browser dispatches `message` when missing
+  pub event: Option<String>,
+  /// Sent back by the browser as `Last-Event-ID` when it reconnects
+  pub id: Option<String>,
+  /// Strings are sent as is, other values as JSON
+  pub data: Option<Value>,
+  /// Milliseconds the browser waits before reconnecting
+  pub retry: Option<u32>,
+}
+
+impl SseEvent {
+  /// Wire format of the event, ending with the blank line dispatching it
+  pub fn encode(&self) -> String {
+    let mut encoded = String::new();
+    // A line break would start another field
+    let single_line = |value: &str| value.replace(['\r', '\n'], "");
+
+    if let Some(event) = &self.event {
+      let _ = writeln!(encoded, "event: {}", single_line(event));
+    }
+    if let Some(id) = &self.id {
+      let _ = writeln!(encoded, "id: {}", single_line(id).replace('\0', ""));
+    }
+    if let Some(retry) = self.retry {
+      let _ = writeln!(encoded, "retry: {}", retry);
+    }
+    match &self.data {
+      Some(Value::String(data)) => {
+        // `\r\n`, `\r` and `\n` all end a line for the browser
+        for line in data.split("\r\n").flat_map(|line| line.split(['\r', '\n'])) {
+          let _ = writeln!(encoded, "data: {}", line);
+        }
+      }
+      Some(data) => {
+        let _ = writeln!(encoded, "data: {}", data);
+      }
+      None => {}
+    }
+    encoded.push('\n');
+    encoded
+  }
+}
+
+/// Server-Sent Events stream returned by `res.sse()`
+///
+/// The stream ends with `close`, or when the client disconnects, which
+/// aborts `req.signal` as well.
+#[cfg_attr(feature = "napi", napi)]
+#[derive(Clone)]
+pub struct EventStream {
+  stream: Arc<ResponseStream>,
+}
+
+impl EventStream {
+  pub(crate) fn new(stream: Arc<ResponseStream>) -> Self {
+    Self { stream }
+  }
+}
+
+#[cfg_attr(feature = "napi", napi)]
+impl EventStream {
+  /// Send an event, false once the stream is closed
+  ///
+  /// Example usage:
+  /// ```javascript
+  /// const events = res.sse()
+  /// events.send({ event: 'price', id: '42', data: { symbol: 'ACME', price: 12.5 } })
+  /// ```
+  #[cfg_attr(feature = "napi", napi)]
+  pub fn send(&self, event: SseEvent) -> bool {
+    self.stream.push(Bytes::from(event.encode()))
+  }
+
+  /// End the stream, the browser reconnects unless told otherwise
+  #[cfg_attr(feature = "napi", napi)]
+  pub fn close(&self) {
+    self.stream.end();
+  }
+
+  /// Whether the stream was closed or the client went away
+  #[cfg_attr(feature = "napi", napi(getter))]
+  pub fn closed(&self) -> bool {
+    self.stream.is_closed()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::core::methods::Method;
+  use crate::testing::call;
+  use crate::{SseOptions, Tachyon, TachyonRequest, TachyonResponse};
+  use http_body_util::Empty;
+  use hyper::{header, Request};
+  use serde_json::json;
+  use std::time::Duration;
+
+  #[test]
+  fn test_encode_events() {
+    let event = SseEvent {
+      event: Some("price".into()),
+      id: Some("42".into()),
+      data: Some(json!({ "price": 12.5 })),
+      retry: Some(3000),
+    };
+    assert_eq!(
+      event.encode(),
+      "event: price\nid: 42\nretry: 3000\ndata: {\"price\":12.5}\n\n"
+    );
+
+    // Multi-line data spans several fields, other fields stay on one line
+    let event = SseEvent {
+      event: Some("a\nb".into()),
+      data: Some(json!("first\r\nsecond")),
+      ..Default::default()
+    };
+    assert_eq!(event.encode(), "event: ab\ndata: first\ndata: second\n\n");
+
+    // A lone `\r` cannot end the event and smuggle in another one
+    let event = SseEvent {
+      data: Some(json!("x\r\rdata: injected\rlast\n")),
+      ..Default::default()
+    };
+    assert_eq!(
+      event.encode(),
+      "data: x\ndata: \ndata: data: injected\ndata: last\ndata: \n\n"
+    );
+  }
+
+  #[tokio::test]
+  async fn test_server_sent_events() {
+    let app = Tachyon::new();
+    app
+      .route(
+        Method::Get,
+        "/events",
+        |req: TachyonRequest, res: TachyonResponse| async move {
+          let events = res.sse(Some(SseOptions {
+            keep_alive: Some(10),
+          }));
+          let resumed = req.last_event_id.unwrap_or_default();
+          events.send(SseEvent {
+            id: Some(format!("{}+1", resumed)),
+            data: Some("ready".into()),
+            ..Default::default()
+          });
+          // Idle long enough for a keep-alive comment
+          tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            events.close();
+            assert!(events.closed());
+          });
+        },
+      )
+      .unwrap();
+
+    let req = Request::get("/events")
+      .header("last-event-id", "41")
+      .body(Empty::<Bytes>::new())
+      .unwrap();
+    let res = call(&mut app.service(), req).await;
+    assert_eq!(res.headers[header::CONTENT_TYPE], "text/event-stream");
+    assert_eq!(res.headers[header::CACHE_CONTROL], "no-cache");
+    assert!(res
+      .text()
+      .starts_with("id: 41+1\ndata: ready\n\n: keep-alive\n\n"));
+  }
+}
@@ -0,0 +1,453 @@
+use bytes::Bytes;
+use hyper::body::{Body, Frame, SizeHint};
+use std::{
+  collections::VecDeque,
+  future::Future,
+  pin::Pin,
+  sync::{Arc, Mutex, OnceLock},
+  task::{Context, Poll},
+  time::Duration,
+};
+use tokio::{
+  sync::{
+    mpsc::{self, error::TrySendError},
+    Notify,
+  },
+  time::{Instant, Sleep},
+};
+use tower::BoxError;
+
+use crate::core::signal::AbortGuard;
+
+/// Chunks a streamed response queues before `write` waits for the client
+pub const CAPACITY: usize = 16;
+
+/// Bytes waiting for room in the queue at most, writers that never wait for it fail the body there
+pub const MAX_BUFFERED: usize = 1024 * 1024;
+
+/// Chunks of a streamed response body, started by the first write
+///
+/// Chunks go through a channel of [`CAPACITY`] chunks, writers wait until
+/// theirs got a place in it. Chunks written meanwhile wait in order behind
+/// it. Past [`MAX_BUFFERED`] of them, e.g. events sent to a stalled client,
+/// the body fails so the client sees it was cut off.
+#[derive(Default)]
+pub(crate) struct ResponseStream {
+  channel: OnceLock<Channel>,
+  started: Notify,
+  drained: Notify,
+  heartbeat: OnceLock<(Duration, Bytes)>,
+}
+
+struct Channel {
+  queue: Mutex<Queue>,
+  receiver: Mutex<Option<mpsc::Receiver<Bytes>>>,
+}
+
+/// Writing end of a stream
+struct Queue {
+  sender: Option<mpsc::Sender<Bytes>>,
+  /// Chunks written while the channel was full
+  waiting: VecDeque<Bytes>,
+  waiting_len: usize,
+  /// `end` was called, the sender goes once nothing is waiting anymore
+  ending: bool,
+  /// Too much was waiting, the body ends with an error
+  failed: bool,
+}
+
+impl Queue {
+  /// Move waiting chunks into the channel while it has room
+  fn flush(&mut self) {
+    let Some(sender) = self.sender.as_ref() else {
+      return;
+    };
+    while let Some(chunk) = self.waiting.pop_front() {
+      let len = chunk.len();
+      match sender.try_send(chunk) {
+        Ok(()) => self.waiting_len -= len,
+        Err(TrySendError::Full(chunk)) => {
+          self.waiting.push_front(chunk);
+          return;
+        }
+        Err(TrySendError::Closed(_)) => {
+          self.waiting.clear();
+          self.waiting_len = 0;
+          return;
+        }
+      }
+    }
+    if self.ending {
+      self.sender = None;
+    }
+  }
+
+  fn is_closed(&self) -> bool {
+    self.ending || self.sender.as_ref().is_none_or(|sender| sender.is_closed())
+  }
+}
+
+impl ResponseStream {
+  fn channel(&self) -> &Channel {
+    let mut created = false;
+    let channel = self.channel.get_or_init(|| {
+      created = true;
+      let (sender, receiver) = mpsc::channel(CAPACITY);
+      Channel {
+        queue: Mutex::new(Queue {
+          sender: Some(sender),
+          waiting: VecDeque::new(),
+          waiting_len: 0,
+          ending: false,
+          failed: false,
+        }),
+        receiver: Mutex::new(Some(receiver)),
+      }
+    });
+    // Only once the channel is set, waiters look for it right away
+    if created {
+      self.started.notify_one();
+    }
+    channel
+  }
+
+  /// Start the body before the first chunk is written
+  pub fn start(&self) {
+    self.channel();
+  }
+
+  pub fn is_started(&self) -> bool {
+    self.channel.get().is_some()
+  }
+
+  /// Wait until the first chunk was written or the stream ended
+  pub async fn started(&self) {
+    if !self.is_started() {
+      self.started.notified().await;
+    }
+  }
+
+  /// Queue a chunk, false once the stream ended or the client went away
+  pub fn push(&self, chunk: Bytes) -> bool {
+    let Ok(mut queue) = self.channel().queue.lock() else {
+      return false;
+    };
+    if queue.is_closed() {
+      return false;
+    }
+    if chunk.is_empty() {
+      return true;
+    }
+
+    // The client is too far behind, fail the body rather than grow it without limit
+    if !queue.waiting.is_empty() && queue.waiting_len + chunk.len() > MAX_BUFFERED {
+      queue.failed = true;
+      queue.sender = None;
+      queue.waiting.clear();
+      queue.waiting_len = 0;
+      return false;
+    }
+    queue.waiting_len += chunk.len();
+    queue.waiting.push_back(chunk);
+    queue.flush();
+    true
+  }
+
+  /// Wait until every written chunk got a place in the channel
+  pub async fn drain(&self) {
+    let Some(channel) = self.channel.get() else {
+      return;
+    };
+    loop {
+      let drained = self.drained.notified();
+      tokio::pin!(drained);
+      drained.as_mut().enable();
+      let done = channel
+        .queue
+        .lock()
+        .map(|queue| queue.waiting.is_empty() || queue.sender.is_none())
+        .unwrap_or(true);
+      if done || self.is_closed() {
+        return;
+      }
+      drained.await;
+    }
+  }
+
+  /// The client read a chunk, make room for the waiting ones
+  fn flush(&self) {
+    if let Some(channel) = self.channel.get() {
+      if let Ok(mut queue) = channel.queue.lock() {
+        queue.flush();
+      }
+    }
+    self.drained.notify_waiters();
+  }
+
+  /// Whether the body fails instead of ending
+  fn is_failed(&self) -> bool {
+    self
+      .channel
+      .get()
+      .and_then(|channel| channel.queue.lock().ok().map(|queue| queue.failed))
+      .unwrap_or(false)
+  }
+
+  /// Send `chunk` whenever the body stayed idle for `every`, e.g. a keep-alive comment
+  pub fn set_heartbeat(&self, every: Duration, chunk: Bytes) {
+    let _ = self.heartbeat.set((every, chunk));
+  }
+
+  /// Whether no more chunks can be written
+  pub fn is_closed(&self) -> bool {
+    let Some(channel) = self.channel.get() else {
+      return false;
+    };
+    channel
+      .queue
+      .lock()
+      .map(|queue| queue.is_closed())
+      .unwrap_or(true)
+  }
+
+  /// Finish the body once the waiting chunks went out, starting an empty one if nothing was written
+  pub fn end(&self) {
+    if let Ok(mut queue) = self.channel().queue.lock() {
+      queue.ending = true;
+      queue.flush();
+    }
+  }
+
+  /// Drop the reading end, as if the client went away before the body started
+  pub fn close(&self) {
+    if let Ok(mut receiver) = self.channel().receiver.lock() {
+      receiver.take();
+    }
+  }
+
+  /// Body reading the chunks, only the first call gets it
+  pub fn take_body(self: &Arc<Self>) -> Option<StreamBody> {
+    let receiver = self.channel.get()?.receiver.lock().ok()?.take()?;
+    let heartbeat = self.heartbeat.get().map(|(every, chunk)| Heartbeat {
+      every: *every,
+      chunk: chunk.clone(),
+      sleep: None,
+    });
+    Some(StreamBody {
+      receiver,
+      stream: Arc::clone(self),
+      abort_guard: None,
+      heartbeat,
+    })
+  }
+}
+
+/// Response body of a streamed response
+pub struct StreamBody {
+  receiver: mpsc::Receiver<Bytes>,
+  stream: Arc<ResponseStream>,
+  abort_guard: Option<AbortGuard>,
+  heartbeat: Option<Heartbeat>,
+}
+
+/// Idle timer of a body, armed on its first poll
+struct Heartbeat {
+  every: Duration,
+  chunk: Bytes,
+  sleep: Option<Pin<Box<Sleep>>>,
+}
+
+impl Heartbeat {
+  /// Restart the idle timer, data was just sent
+  fn reset(&mut self) {
+    if let Some(sleep) = self.sleep.as_mut() {
+      sleep.as_mut().reset(Instant::now() + self.every);
+    }
+  }
+
+  fn poll_beat(&mut self, cx: &mut Context<'_>) -> Poll<Bytes> {
+    let every = self.every;
+    let sleep = self
+      .sleep
+      .get_or_insert_with(|| Box::pin(tokio::time::sleep(every)));
+    if sleep.as_mut().poll(cx).is_pending() {
+      return Poll::Pending;
+    }
+    sleep.as_mut().reset(Instant::now() + every);
+    Poll::Ready(self.chunk.clone())
+  }
+}
+
+impl StreamBody {
+  /// Abort the request when the client goes away before the end of the body
+  pub(crate) fn with_abort_guard(mut self, abort_guard: AbortGuard) -> Self {
+    self.abort_guard = Some(abort_guard);
+    self
+  }
+}
+
+impl Body for StreamBody {
+  type Data = Bytes;
+  type Error = BoxError;
+
+  fn poll_frame(
+    mut self: Pin<&mut Self>,
+    cx: &mut Context<'_>,
+  ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+    match self.receiver.poll_recv(cx) {
+      Poll::Ready(Some(chunk)) => {
+        self.stream.flush();
+        if let Some(heartbeat) = self.heartbeat.as_mut() {
+          heartbeat.reset();
+        }
+        Poll::Ready(Some(Ok(Frame::data(chunk))))
+      }
+      // Ending normally would pass a cut off body as complete, the guard aborts the request
+      Poll::Ready(None) if self.stream.is_failed() => Poll::Ready(Some(Err(BoxError::from(
+        "Client fell too far behind the response stream",
+      )))),
+      Poll::Ready(None) => {
+        if let Some(abort_guard) = self.abort_guard.take() {
+          abort_guard.disarm();
+        }
+        Poll::Ready(None)
+      }
+      Poll::Pending => match self.heartbeat.as_mut() {
+        Some(heartbeat) => heartbeat
+          .poll_beat(cx)
+          .map(|chunk| Some(Ok(Frame::data(chunk)))),
+        None => Poll::Pending,
+      },
+    }
+  }
+
+  fn size_hint(&self) -> SizeHint {
+    SizeHint::default()
+  }
+}
+
+impl Drop for StreamBody {
+  fn drop(&mut self) {
+    // Wake up the writers, their next write fails
+    self.receiver.close();
+    self.stream.drained.notify_waiters();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::core::methods::Method;
+  use crate::testing::{call, get};
+  use crate::{Tachyon, TachyonResponse};
+  use http_body_util::BodyExt;
+  use hyper::header;
+
+  #[tokio::test]
+  async fn test_stream_backpressure() {
+    let stream = Arc::new(ResponseStream::default());
+    assert!(!stream.is_started());
+
+    // With the channel full, writers wait for the client
+    for index in 0..CAPACITY {
+      assert!(stream.push(Bytes::from(index.to_string())));
+    }
+    stream.drain().await;
+    assert!(stream.push(Bytes::from_static(b"last")));
+    let mut body = stream.take_body().unwrap();
+    let drain = tokio::spawn({
+      let stream = Arc::clone(&stream);
+      async move { stream.drain().await }
+    });
+    tokio::task::yield_now().await;
+    assert!(!drain.is_finished());
+
+    let first = body.frame().await.unwrap().unwrap().into_data().unwrap();
+    assert_eq!(first, "0");
+    drain.await.unwrap();
+
+    // Ending keeps the chunks already written, in order
+    stream.end();
+    assert!(!stream.push(Bytes::from_static(b"late")));
+    let rest = body.collect().await.unwrap().to_bytes();
+    assert_eq!(
+      rest,
+      (1..CAPACITY)
+        .map(|index| index.to_string())
+        .collect::<String>()
+        + "last"
+    );
+  }
+
+  #[tokio::test]
+  async fn test_stream_fails_past_max_buffered() {
+    let stream = Arc::new(ResponseStream::default());
+
+    // Pushing without waiting for the client, like `events.send` in a loop
+    let chunk = Bytes::from(vec![b'a'; 64 * 1024]);
+    let mut pushed = 0;
+    while stream.push(chunk.clone()) {
+      pushed += chunk.len();
+    }
+    assert!(pushed > MAX_BUFFERED && pushed <= CAPACITY * chunk.len() + MAX_BUFFERED);
+    assert!(stream.is_closed());
+    assert!(!stream.push(Bytes::from_static(b"late")));
+
+    // The chunks already in the channel reach the client, then the body fails
+    let mut body = stream.take_body().unwrap();
+    let mut received = 0;
+    let error = loop {
+      match body.frame().await.unwrap() {
+        Ok(frame) => received += frame.into_data().unwrap().len(),
+        Err(error) => break error,
+      }
+    };
+    assert_eq!(received, CAPACITY * chunk.len());
+    assert_eq!(
+      error.to_string(),
+      "Client fell too far behind the response stream"
+    );
+  }
+
+  #[tokio::test]
+  async fn test_stream_accepts_large_chunks() {
+    let stream = Arc::new(ResponseStream::default());
+
+    // A single chunk over the limit is fine as long as nothing waits before it
+    assert!(stream.push(Bytes::from(vec![b'a'; 2 * MAX_BUFFERED])));
+    stream.end();
+    let body = stream.take_body().unwrap();
+    assert_eq!(
+      body.collect().await.unwrap().to_bytes().len(),
+      2 * MAX_BUFFERED
+    );
+  }
+
+  #[tokio::test]
+  async fn test_streamed_response() {
+    let app = Tachyon::new();
+    app
+      .route(
+        Method::Get,
+        "/export",
+        |_req, res: TachyonResponse| async move {
+          res.set_header("content-type", "text/csv").unwrap();
+          // Far more than the buffer, written while the client reads it
+          for row in 0..10_000 {
+            assert!(res.write(format!("{},{}\n", row, "x".repeat(32))).await);
+          }
+          res.end();
+        },
+      )
+      .unwrap();
+
+    let res = call(&mut app.service(), get("/export")).await;
+    assert_eq!(res.status, hyper::StatusCode::OK);
+    assert_eq!(res.headers[header::CONTENT_TYPE], "text/csv");
+    assert!(!res.headers.contains_key(header::CONTENT_LENGTH));
+
+    let rows: Vec<_> = res.body.split(|&byte| byte == b'\n').collect();
+    assert_eq!(rows.len(), 10_001);
+    assert!(rows[9_999].starts_with(b"9999,"));
+  }
+}
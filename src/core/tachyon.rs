@@ -1,54 +1,144 @@
 use bytes::Bytes;
-use dashmap::DashMap;
+use dashmap::{mapref::multiple::RefMulti, DashMap};
 use http_body_util::{combinators::BoxBody, BodyExt};
-use hyper::{header, server::conn::http1, service::service_fn, Request, Response, StatusCode};
-use hyper_util::rt::TokioIo;
-use napi::{
-  bindgen_prelude::{FnArgs, Function},
-  Result,
-};
+use hyper::{body::Incoming, header, server::conn::http1, Request, Response, StatusCode};
+use hyper_util::{rt::TokioIo, service::TowerToHyperService};
+#[cfg(feature = "napi")]
+use napi::bindgen_prelude::{Either, Uint8Array};
+#[cfg(feature = "napi")]
 use napi_derive::napi;
 use std::{
+  cmp::Ordering,
+  collections::HashMap,
   net::{IpAddr, Ipv4Addr, SocketAddr},
-  sync::Arc,
+  sync::{Arc, RwLock},
+  time::{Duration, Instant},
+};
+use tokio::{
+  net::{TcpListener, TcpStream},
+  task,
 };
-use tokio::{net::TcpListener, task};
+use tower::{BoxError, Layer, Service};
 
+#[cfg(feature = "napi")]
+use crate::core::{
+  options::{RouteOptions, UrlOptions},
+  router::HandlerChain,
+  websocket::{into_websocket_handler, WebSocketCallbacks},
+  wrapper::{into_error_handler, into_handlers, RouteArg, TachyonCallbacks, TachyonErrorCallback},
+};
 use crate::{
   core::{
+    files::{ConditionalHeaders, StaticFiles},
+    group::Router,
+    hooks::{CompletionBody, HookName, HookSet, Hooks},
+    host::{request_host, HostPattern, VirtualHost},
     methods::Method,
+    options::{StaticOptions, TachyonOptions, WebSocketOptions},
+    path::{decode_param, normalize_path},
+    pattern::MatchOptions,
     request::TachyonRequest,
     response::TachyonResponse,
-    router::{HTTPCall, TachyonRouter},
+    router::{
+      describe_routes, format_route_table, route_infos, HTTPCall, RouteInfo, TachyonErrorHandler,
+      TachyonHandler, TachyonRouter,
+    },
+    service::{apply_layers, box_layer, BoxLayer, LayeredService, TachyonService},
+    signal::{AbortGuard, AbortReason},
+    websocket::{PendingUpgrade, Topics, WebSocketHandler, WebSocketMessage, WebSocketRoute},
   },
-  utils::{self, empty, full, warmup_routes},
+  error::{HttpError, Result, TachyonError},
+  utils::{empty, full, warmup_routes},
 };
+#[cfg(feature = "napi")]
+use serde_json::{Map, Value};
 
-static NOTFOUND: &str = "Not Found";
+/// Handler of the matched route, its params still percent-encoded and its hooks
+type RouteMatch = (
+  Arc<dyn TachyonHandler>,
+  Vec<(String, String)>,
+  Option<Arc<Hooks>>,
+);
 
-#[napi]
+/// Request and response objects of a request, and the hooks of its route
+type Exchange = (TachyonRequest, TachyonResponse, Option<Arc<Hooks>>);
+
+/// The app, cheap to clone as every field is shared
+#[cfg_attr(feature = "napi", napi)]
+#[derive(Clone)]
 pub struct Tachyon {
   routes: Arc<DashMap<String, TachyonRouter>>,
+  layers: Arc<RwLock<Vec<BoxLayer>>>,
+  hosts: Arc<RwLock<Vec<VirtualHost>>>,
+  options: Arc<TachyonOptions>,
+  not_found: Arc<RwLock<Option<Arc<dyn TachyonHandler>>>>,
+  error_handler: Arc<RwLock<Option<Arc<dyn TachyonErrorHandler>>>>,
+  /// `None` until a hook is added, so that requests skip them for free
+  hooks: Arc<RwLock<Option<Arc<Hooks>>>>,
+  /// Subscribers of the topics WebSocket messages are published to
+  topics: Arc<Topics>,
 }
 
 impl Default for Tachyon {
   fn default() -> Self {
-    Self {
-      routes: Arc::new(DashMap::new()),
-    }
+    Self::with_options(TachyonOptions::default())
   }
 }
 
-#[napi]
+#[cfg_attr(feature = "napi", napi)]
 impl Tachyon {
-  #[napi(constructor)]
+  #[cfg_attr(feature = "napi", napi(constructor))]
   pub fn new() -> Self {
     Self::default()
   }
 
+  #[cfg_attr(feature = "napi", napi)]
+  pub fn routes(&self) -> Vec<String> {
+    describe_routes(&self.describe_routes())
+  }
+
+  /// Every route with its params, middleware count and metadata, sorted by path
+  ///
+  /// Routes of virtual hosts come last, grouped by host.
+  #[cfg_attr(feature = "napi", napi)]
+  pub fn describe_routes(&self) -> Vec<RouteInfo> {
+    let mut routes = route_infos(&self.routes);
+    for host in self.virtual_hosts() {
+      routes.extend(
+        route_infos(&host.route_table())
+          .into_iter()
+          .map(|info| RouteInfo {
+            host: Some(host.pattern().as_str().to_string()),
+            ..info
+          }),
+      );
+    }
+    routes
+  }
+
+  /// Route table formatted for startup logs
+  ///
+  /// Example usage:
+  /// ```javascript
+  /// console.log(app.printRoutes())
+  /// // METHOD  PATH        PARAMS  MIDDLEWARE
+  /// // GET     /           -       0
+  /// // GET     /users/:id  id      1
+  /// ```
+  #[cfg_attr(feature = "napi", napi)]
+  pub fn print_routes(&self) -> String {
+    format_route_table(&self.describe_routes())
+  }
+}
+
+#[cfg(feature = "napi")]
+#[napi]
+impl Tachyon {
   /// Add a GET route handler with Express-like syntax
   /// Supports both sync and async handlers
   ///
+  /// Up to five handlers, handler arrays or, last, route options follow the path
+  ///
   /// Example usage:
   /// ```javascript
   /// app.get('/', (req, res) => {
@@ -58,17 +148,26 @@ impl Tachyon {
   /// app.get('/async', async (req, res) => {
   ///   res.send('Async Hello!')
   /// })
+  ///
+  /// // Middleware runs in order until one of them sends the response
+  /// app.get('/admin', requireAuth, rateLimit, (req, res) => {
+  ///   res.send('Welcome!')
+  /// })
   /// ```
   #[napi(
-    ts_args_type = r#"route: string, callback: ((req: TachyonRequest, res: TachyonResponse) => void) | ((req: TachyonRequest, res: TachyonResponse) => Promise<void>)"#,
+    ts_args_type = r#"route: string, arg1: ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>) | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>> | RouteOptions, arg2?: ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>) | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>> | RouteOptions | undefined | null, arg3?: ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>) | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>> | RouteOptions | undefined | null, arg4?: ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>) | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>> | RouteOptions | undefined | null, arg5?: ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>) | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>> | RouteOptions | undefined | null"#,
     js_name = "get"
   )]
   pub fn get(
     &self,
     route: String,
-    callback: Function<'static, FnArgs<(TachyonRequest, TachyonResponse)>, ()>,
-  ) -> Result<()> {
-    self.call(route, Method::new(0), callback)
+    arg1: Option<RouteArg>,
+    arg2: Option<RouteArg>,
+    arg3: Option<RouteArg>,
+    arg4: Option<RouteArg>,
+    arg5: Option<RouteArg>,
+  ) -> napi::Result<()> {
+    self.call(route, Method::new(0), vec![arg1, arg2, arg3, arg4, arg5])
   }
 
   /// Add a POST route handler with Express-like syntax
@@ -86,14 +185,18 @@ impl Tachyon {
   /// })
   /// ```
   #[napi(
-    ts_args_type = r#"route: string, callback: ((req: TachyonRequest, res: TachyonResponse) => void) | ((req: TachyonRequest, res: TachyonResponse) => Promise<void>)"#
+    ts_args_type = r#"route: string, arg1: ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>) | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>> | RouteOptions, arg2?: ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>) | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>> | RouteOptions | undefined | null, arg3?: ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>) | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>> | RouteOptions | undefined | null, arg4?: ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>) | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>> | RouteOptions | undefined | null, arg5?: ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>) | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>> | RouteOptions | undefined | null"#
   )]
   pub fn post(
     &self,
     route: String,
-    callback: Function<'static, FnArgs<(TachyonRequest, TachyonResponse)>, ()>,
-  ) -> Result<()> {
-    self.call(route, Method::new(1), callback)
+    arg1: Option<RouteArg>,
+    arg2: Option<RouteArg>,
+    arg3: Option<RouteArg>,
+    arg4: Option<RouteArg>,
+    arg5: Option<RouteArg>,
+  ) -> napi::Result<()> {
+    self.call(route, Method::new(1), vec![arg1, arg2, arg3, arg4, arg5])
   }
 
   /// Add a PUT route handler with Express-like syntax
@@ -106,14 +209,18 @@ impl Tachyon {
   /// })
   /// ```
   #[napi(
-    ts_args_type = r#"route: string, callback: ((req: TachyonRequest, res: TachyonResponse) => void) | ((req: TachyonRequest, res: TachyonResponse) => Promise<void>)"#
+    ts_args_type = r#"route: string, arg1: ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>) | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>> | RouteOptions, arg2?: ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>) | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>> | RouteOptions | undefined | null, arg3?: ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>) | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>> | RouteOptions | undefined | null, arg4?: ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>) | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>> | RouteOptions | undefined | null, arg5?: ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>) | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>> | RouteOptions | undefined | null"#
   )]
   pub fn put(
     &self,
     route: String,
-    callback: Function<'static, FnArgs<(TachyonRequest, TachyonResponse)>, ()>,
-  ) -> Result<()> {
-    self.call(route, Method::new(2), callback)
+    arg1: Option<RouteArg>,
+    arg2: Option<RouteArg>,
+    arg3: Option<RouteArg>,
+    arg4: Option<RouteArg>,
+    arg5: Option<RouteArg>,
+  ) -> napi::Result<()> {
+    self.call(route, Method::new(2), vec![arg1, arg2, arg3, arg4, arg5])
   }
 
   /// Add a DELETE route handler with Express-like syntax
@@ -126,14 +233,18 @@ impl Tachyon {
   /// })
   /// ```
   #[napi(
-    ts_args_type = r#"route: string, callback: ((req: TachyonRequest, res: TachyonResponse) => void) | ((req: TachyonRequest, res: TachyonResponse) => Promise<void>)"#
+    ts_args_type = r#"route: string, arg1: ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>) | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>> | RouteOptions, arg2?: ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>) | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>> | RouteOptions | undefined | null, arg3?: ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>) | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>> | RouteOptions | undefined | null, arg4?: ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>) | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>> | RouteOptions | undefined | null, arg5?: ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>) | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>> | RouteOptions | undefined | null"#
   )]
   pub fn delete(
     &self,
     route: String,
-    callback: Function<'static, FnArgs<(TachyonRequest, TachyonResponse)>, ()>,
-  ) -> Result<()> {
-    self.call(route, Method::new(3), callback)
+    arg1: Option<RouteArg>,
+    arg2: Option<RouteArg>,
+    arg3: Option<RouteArg>,
+    arg4: Option<RouteArg>,
+    arg5: Option<RouteArg>,
+  ) -> napi::Result<()> {
+    self.call(route, Method::new(3), vec![arg1, arg2, arg3, arg4, arg5])
   }
 
   /// Add a PATCH route handler with Express-like syntax
@@ -146,73 +257,799 @@ impl Tachyon {
   /// })
   /// ```
   #[napi(
-    ts_args_type = r#"route: string, callback: ((req: TachyonRequest, res: TachyonResponse) => void) | ((req: TachyonRequest, res: TachyonResponse) => Promise<void>)"#
+    ts_args_type = r#"route: string, arg1: ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>) | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>> | RouteOptions, arg2?: ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>) | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>> | RouteOptions | undefined | null, arg3?: ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>) | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>> | RouteOptions | undefined | null, arg4?: ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>) | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>> | RouteOptions | undefined | null, arg5?: ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>) | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>> | RouteOptions | undefined | null"#
   )]
   pub fn patch(
     &self,
     route: String,
-    callback: Function<'static, FnArgs<(TachyonRequest, TachyonResponse)>, ()>,
-  ) -> Result<()> {
-    self.call(route, Method::new(4), callback)
+    arg1: Option<RouteArg>,
+    arg2: Option<RouteArg>,
+    arg3: Option<RouteArg>,
+    arg4: Option<RouteArg>,
+    arg5: Option<RouteArg>,
+  ) -> napi::Result<()> {
+    self.call(route, Method::new(4), vec![arg1, arg2, arg3, arg4, arg5])
   }
 
+  /// Build the path of a named route, params and query values being percent-encoded
+  ///
+  /// Example usage:
+  /// ```javascript
+  /// app.get('/users/:id', showUser, { name: 'user.show' })
+  ///
+  /// app.url('user.show', { id: 5 }, { query: { tab: 'posts' } }) // '/users/5?tab=posts'
+  /// ```
   #[napi]
-  pub fn routes(&self) -> Vec<String> {
-    let mut result = Vec::with_capacity(self.routes.len());
-    for r in self.routes.iter() {
-      let key = r.key();
-      let router = r.value();
-      if let Some(colon_pos) = key.find(':') {
-        let method = &key[..colon_pos];
-        let path = &key[colon_pos + 1..];
-        result.push(format!("{} {}", path, Method::from(method)));
-      } else {
-        result.push(format!("{} {}", key, Method::new(router.method())));
+  pub fn url(
+    &self,
+    name: String,
+    params: Option<Map<String, Value>>,
+    options: Option<UrlOptions>,
+  ) -> napi::Result<String> {
+    let mut values = HashMap::new();
+    for (key, value) in params.unwrap_or_default() {
+      if !value.is_null() {
+        values.insert(key.clone(), url_value(&key, &value)?);
       }
     }
-    result
+
+    let mut query = Vec::new();
+    for (key, value) in options
+      .and_then(|options| options.query)
+      .unwrap_or_default()
+    {
+      match value {
+        Value::Null => {}
+        Value::Array(items) => {
+          for item in &items {
+            query.push((key.clone(), url_value(&key, item)?));
+          }
+        }
+        value => query.push((key.clone(), url_value(&key, &value)?)),
+      }
+    }
+
+    Ok(self.url_for(&name, &values, &query)?)
+  }
+
+  /// Swap the handlers of a registered route without restarting the server
+  ///
+  /// Requests already running finish on the previous handlers. The middleware
+  /// of a mounted router keeps running before the new ones.
+  ///
+  /// Example usage:
+  /// ```javascript
+  /// app.replaceRoute('GET', '/feature', (req, res) => {
+  ///   res.send('New implementation')
+  /// })
+  /// ```
+  #[napi(
+    ts_args_type = r#"method: string, route: string, callbacks: ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>) | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>>, options?: RouteOptions | undefined | null"#
+  )]
+  pub fn replace_route(
+    &self,
+    method: String,
+    route: String,
+    callbacks: TachyonCallbacks,
+    options: Option<RouteOptions>,
+  ) -> napi::Result<()> {
+    let handlers = into_handlers(callbacks)?;
+    Ok(self.replace(
+      &route,
+      method.parse()?,
+      handlers,
+      options.unwrap_or_default(),
+    )?)
+  }
+
+  /// Unregister a route, returns `false` when it was not registered
+  ///
+  /// Example usage:
+  /// ```javascript
+  /// if (!flags.betaSearch) {
+  ///   app.removeRoute('GET', '/search')
+  /// }
+  /// ```
+  #[napi]
+  pub fn remove_route(&self, method: String, route: String) -> napi::Result<bool> {
+    Ok(self.remove(&route, method.parse()?))
+  }
+
+  /// Serve the routes of a `Router` to requests for matching hosts only
+  ///
+  /// Patterns are exact (`api.example.com`), wildcard (`*.example.com`) or
+  /// capture a label (`:tenant.example.com`) into `req.params`.
+  ///
+  /// Example usage:
+  /// ```javascript
+  /// const tenants = router()
+  /// tenants.get('/', (req, res) => {
+  ///   res.send(`Hello ${req.params.tenant}`)
+  /// })
+  ///
+  /// app.host(':tenant.example.com', tenants)
+  /// ```
+  #[napi(js_name = "host")]
+  pub fn host_router(&self, host: String, router: &Router) -> napi::Result<()> {
+    Ok(self.mount_host(&host, router)?)
+  }
+
+  /// Answer requests matching no route, instead of the plain `Not Found` text
+  ///
+  /// Example usage:
+  /// ```javascript
+  /// app.notFound((req, res) => {
+  ///   res.status(404).send({ error: { code: 'NOT_FOUND', message: 'No such page' } })
+  /// })
+  /// ```
+  #[napi(
+    js_name = "notFound",
+    ts_args_type = r#"callbacks: ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>) | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>>"#
+  )]
+  pub fn not_found_handler(&self, callbacks: TachyonCallbacks) -> napi::Result<()> {
+    let handlers = into_handlers(callbacks)?;
+    if handlers.is_empty() {
+      return Err(TachyonError::new("notFound needs at least one handler").into());
+    }
+    self.set_not_found(HandlerChain::new(handlers));
+    Ok(())
+  }
+
+  /// Render the errors raised while serving a request
+  ///
+  /// Called for unknown routes without a `notFound` handler, unsupported
+  /// methods, invalid or too large bodies and handlers that throw or time out.
+  /// The default plain text response is sent when the handler sends nothing.
+  ///
+  /// Example usage:
+  /// ```javascript
+  /// app.onError((err, req, res) => {
+  ///   res.status(err.status).send({ error: { code: err.code, message: err.message } })
+  /// })
+  /// ```
+  #[napi(
+    js_name = "onError",
+    ts_args_type = r#"callback: (err: HttpError, req: TachyonRequest, res: TachyonResponse) => void | Promise<void>"#
+  )]
+  pub fn on_error(&self, callback: TachyonErrorCallback) -> napi::Result<()> {
+    let handler = into_error_handler(callback)?;
+    if let Ok(mut error_handler) = self.error_handler.write() {
+      *error_handler = Some(handler);
+    }
+    Ok(())
+  }
+
+  /// Add a lifecycle hook run for every request
+  ///
+  /// `onRequest` runs once the route matched and `preHandler` once the body is
+  /// parsed, both stop the request when they send a response. `onSend` can
+  /// still change the payload and headers, and `onResponse` runs once the
+  /// response is written.
+  ///
+  /// Example usage:
+  /// ```javascript
+  /// app.addHook('onSend', (req, res) => {
+  ///   res.header('x-powered-by', 'tachyon')
+  /// })
+  ///
+  /// app.addHook('onResponse', (req, res) => {
+  ///   console.log(res.statusCode, `${res.elapsedTime.toFixed(1)}ms`)
+  /// })
+  /// ```
+  #[napi(
+    ts_args_type = r#"name: HookName, callbacks: ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>) | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>>"#
+  )]
+  pub fn add_hook(&self, name: HookName, callbacks: TachyonCallbacks) -> napi::Result<()> {
+    self.add_hooks(name, into_handlers(callbacks)?);
+    Ok(())
+  }
+
+  /// Add a lifecycle hook to a single route, run after the global ones
+  ///
+  /// Example usage:
+  /// ```javascript
+  /// app.addRouteHook('GET', '/reports/:id', 'preHandler', requireAdmin)
+  /// ```
+  #[napi(
+    js_name = "addRouteHook",
+    ts_args_type = r#"method: string, route: string, name: HookName, callbacks: ((req: TachyonRequest, res: TachyonResponse) => void | Promise<void>) | Array<(req: TachyonRequest, res: TachyonResponse) => void | Promise<void>>"#
+  )]
+  pub fn route_hook(
+    &self,
+    method: String,
+    route: String,
+    name: HookName,
+    callbacks: TachyonCallbacks,
+  ) -> napi::Result<()> {
+    let handlers = into_handlers(callbacks)?;
+    Ok(self.add_route_hook(&route, method.parse()?, name, handlers)?)
+  }
+
+  /// Mount every route of a `Router` under a path prefix
+  ///
+  /// Example usage:
+  /// ```javascript
+  /// const users = router()
+  /// users.use(requireAuth)
+  /// users.get('/:id', (req, res) => {
+  ///   res.send('User!')
+  /// })
+  ///
+  /// app.mount('/api/v1/users', users)
+  /// ```
+  #[napi(js_name = "mount")]
+  pub fn mount_router(&self, prefix: String, router: &Router) -> napi::Result<()> {
+    Ok(self.mount(&prefix, router)?)
+  }
+
+  /// Accept WebSocket connections on a path, other requests get 426
+  ///
+  /// Callbacks of a connection run in order. `ws` is the same object for all
+  /// of them and exposes the params, query and locals of the upgrade request.
+  ///
+  /// Example usage:
+  /// ```javascript
+  /// app.ws('/chat/:room', {
+  ///   open(ws) {
+  ///     ws.send(`Welcome to ${ws.params.room}`)
+  ///   },
+  ///   message(ws, data) {
+  ///     ws.send(data)
+  ///   },
+  ///   close(ws, code, reason) {
+  ///     console.log('left', code, reason)
+  ///   },
+  /// })
+  /// ```
+  #[napi(
+    js_name = "ws",
+    ts_args_type = r#"route: string, callbacks: WebSocketCallbacks, options?: WebSocketOptions | undefined | null"#
+  )]
+  pub fn ws_route(
+    &self,
+    route: String,
+    callbacks: WebSocketCallbacks,
+    options: Option<WebSocketOptions>,
+  ) -> napi::Result<()> {
+    let handler = into_websocket_handler(callbacks)?;
+    let route_handler = WebSocketRoute::new(
+      handler,
+      options.unwrap_or_default(),
+      Arc::clone(&self.topics),
+    );
+    Ok(self.register(&route, Method::Get, vec![Arc::new(route_handler)])?)
+  }
+
+  /// Serve the files of a directory under a path prefix, without calling into JS
+  ///
+  /// Directories answer with their `index.html`, files get an `ETag`,
+  /// `Last-Modified` and `Cache-Control`, and paths resolving outside of the
+  /// directory are answered with 404.
+  ///
+  /// Example usage:
+  /// ```javascript
+  /// app.static('/assets', './public', { maxAge: 31536000, immutable: true })
+  /// ```
+  #[napi(js_name = "static")]
+  pub fn static_files(
+    &self,
+    prefix: String,
+    root: String,
+    options: Option<StaticOptions>,
+  ) -> napi::Result<()> {
+    Ok(self.serve_static(&prefix, &root, options.unwrap_or_default())?)
+  }
+
+  /// Send a message to every WebSocket subscribed to a topic, returning how many got it
+  ///
+  /// Example usage:
+  /// ```javascript
+  /// app.publish('prices', JSON.stringify({ symbol: 'ACME', price: 12.5 }))
+  /// ```
+  #[napi(
+    js_name = "publish",
+    ts_args_type = "topic: string, data: string | Uint8Array"
+  )]
+  pub fn publish_data(&self, topic: String, data: Either<String, Uint8Array>) -> u32 {
+    let message = match data {
+      Either::A(text) => WebSocketMessage::Text(text),
+      Either::B(data) => WebSocketMessage::Binary(Bytes::copy_from_slice(&data)),
+    };
+    self.publish(&topic, message) as u32
   }
 
   #[napi]
   pub async fn listen(&self, port: u16) -> napi::Result<()> {
+    Ok(self.serve(port).await?)
+  }
+}
+
+impl Tachyon {
+  /// Create an app with non-default settings
+  pub fn with_options(options: TachyonOptions) -> Self {
+    Self {
+      routes: Arc::new(DashMap::new()),
+      layers: Arc::new(RwLock::new(Vec::new())),
+      hosts: Arc::new(RwLock::new(Vec::new())),
+      options: Arc::new(options),
+      not_found: Arc::new(RwLock::new(None)),
+      error_handler: Arc::new(RwLock::new(None)),
+      hooks: Arc::new(RwLock::new(None)),
+      topics: Arc::new(Topics::default()),
+    }
+  }
+
+  pub fn options(&self) -> &TachyonOptions {
+    &self.options
+  }
+
+  /// Copy every route of `router` to the table of the `host` pattern
+  ///
+  /// Requests whose `Host` matches a pattern only see the routes of that host,
+  /// exact hosts being tried before wildcard and `:param` ones.
+  pub fn mount_host(&self, host: &str, router: &Router) -> Result<()> {
+    let pattern = HostPattern::parse(host)?;
+
+    let virtual_host = {
+      let Ok(mut hosts) = self.hosts.write() else {
+        return Err(TachyonError::failure("Virtual hosts are unavailable"));
+      };
+      match hosts.iter().find(|host| host.pattern() == &pattern) {
+        Some(existing) => existing.clone(),
+        None => {
+          let virtual_host = VirtualHost::new(pattern, Arc::clone(&self.options));
+          hosts.push(virtual_host.clone());
+          hosts.sort_by_key(|host| !host.pattern().is_static());
+          virtual_host
+        }
+      }
+    };
+
+    virtual_host.mount("/", router)
+  }
+
+  /// Handle requests matching no route
+  pub fn set_not_found<H>(&self, handler: H)
+  where
+    H: TachyonHandler + 'static,
+  {
+    if let Ok(mut not_found) = self.not_found.write() {
+      *not_found = Some(Arc::new(handler));
+    }
+  }
+
+  /// Render the errors raised while serving a request
+  ///
+  /// ```ignore
+  /// app.set_error_handler(|err: HttpError, _req, res: TachyonResponse| async move {
+  ///   res.status(err.status).send(Some(serde_json::json!({ "error": err.code })));
+  /// });
+  /// ```
+  pub fn set_error_handler<H>(&self, handler: H)
+  where
+    H: TachyonErrorHandler + 'static,
+  {
+    if let Ok(mut error_handler) = self.error_handler.write() {
+      *error_handler = Some(Arc::new(handler));
+    }
+  }
+
+  /// Append native hooks run for every request
+  pub fn add_hooks(&self, name: HookName, handlers: Vec<Arc<dyn TachyonHandler>>) {
+    if let Ok(mut hooks) = self.hooks.write() {
+      let mut updated = hooks.as_deref().cloned().unwrap_or_default();
+      updated.add(name, handlers);
+      *hooks = (!updated.is_empty()).then(|| Arc::new(updated));
+    }
+  }
+
+  fn global_hooks(&self) -> Option<Arc<Hooks>> {
+    self.hooks.read().ok()?.clone()
+  }
+
+  pub(crate) fn virtual_hosts(&self) -> Vec<VirtualHost> {
+    self
+      .hosts
+      .read()
+      .map(|hosts| hosts.clone())
+      .unwrap_or_default()
+  }
+
+  /// Route table for the host of `req`, with the labels its pattern captured
+  fn select_host<B>(&self, req: &Request<B>) -> Option<(VirtualHost, Vec<(String, String)>)> {
+    let hosts = self.hosts.read().ok()?;
+    if hosts.is_empty() {
+      return None;
+    }
+
+    let host = request_host(req)?;
+    hosts.iter().find_map(|virtual_host| {
+      let params = virtual_host
+        .pattern()
+        .captures(host)?
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value.to_ascii_lowercase()))
+        .collect();
+      Some((virtual_host.clone(), params))
+    })
+  }
+
+  /// Bind to `127.0.0.1:port` and serve requests until the listener fails
+  pub async fn serve(&self, port: u16) -> Result<()> {
     let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), port);
-    let listener = TcpListener::bind(addr).await.map_err(napi::Error::from)?;
+    let listener = TcpListener::bind(addr).await?;
     println!("Listening on http://{}", addr);
 
     warmup_routes(&self.routes);
 
+    // Skip the boxed layer stack entirely when no layer was registered
+    let layers = self.layers();
+    if layers.is_empty() {
+      let service = self.service();
+      loop {
+        let (stream, _) = listener.accept().await?;
+        Self::spawn_connection(stream, service.clone());
+      }
+    }
+
+    let service = apply_layers(self.service(), &layers);
     loop {
-      let (stream, _) = listener.accept().await.map_err(napi::Error::from)?;
-      let io = TokioIo::new(stream);
-      let routes = Arc::clone(&self.routes);
-
-      task::spawn(async move {
-        if let Err(err) = http1::Builder::new()
-          .serve_connection(
-            io,
-            service_fn(move |req| {
-              let routes_clone = Arc::clone(&routes);
-              async move { Self::echo(routes_clone, req).await }
-            }),
-          )
-          .await
-        {
-          eprintln!("Error serving connection: {:?}", err);
-        }
-      });
+      let (stream, _) = listener.accept().await?;
+      Self::spawn_connection(stream, service.clone());
+    }
+  }
+
+  fn spawn_connection<S, ResBody>(stream: TcpStream, service: S)
+  where
+    S: Service<Request<Incoming>, Response = Response<ResBody>> + Clone + Send + 'static,
+    S::Error: Into<BoxError>,
+    S::Future: Send + 'static,
+    ResBody: hyper::body::Body + Send + 'static,
+    ResBody::Data: Send,
+    ResBody::Error: Into<BoxError>,
+  {
+    let io = TokioIo::new(stream);
+    task::spawn(async move {
+      if let Err(err) = http1::Builder::new()
+        .serve_connection(io, TowerToHyperService::new(service))
+        .with_upgrades()
+        .await
+      {
+        eprintln!("Error serving connection: {:?}", err);
+      }
+    });
+  }
+
+  /// The app as a `tower::Service`, without the registered layers
+  pub fn service(&self) -> TachyonService {
+    TachyonService::new(self.clone())
+  }
+
+  /// The app wrapped in every layer registered with [`Tachyon::layer`]
+  pub fn layered_service(&self) -> LayeredService {
+    apply_layers(self.service(), &self.layers())
+  }
+
+  /// Apply a `tower::Layer` (timeouts, concurrency limits, tower-http tracing...)
+  /// to every request served by `listen`
+  ///
+  /// Layers added later wrap the ones added before them.
+  ///
+  /// ```ignore
+  /// app.layer(tower::timeout::TimeoutLayer::new(Duration::from_secs(5)));
+  /// app.layer(tower_http::trace::TraceLayer::new_for_http());
+  /// ```
+  pub fn layer<L, ResBody>(&self, layer: L)
+  where
+    L: Layer<LayeredService> + Send + Sync + 'static,
+    L::Service: Service<Request<Incoming>, Response = Response<ResBody>> + Clone + Send + 'static,
+    <L::Service as Service<Request<Incoming>>>::Error: Into<BoxError>,
+    <L::Service as Service<Request<Incoming>>>::Future: Send + 'static,
+    ResBody: hyper::body::Body<Data = Bytes> + Send + Sync + 'static,
+    ResBody::Error: Into<BoxError>,
+  {
+    if let Ok(mut layers) = self.layers.write() {
+      layers.push(box_layer(layer));
     }
   }
 
+  fn layers(&self) -> Vec<BoxLayer> {
+    self
+      .layers
+      .read()
+      .map(|layers| layers.clone())
+      .unwrap_or_default()
+  }
+
+  /// Register a native Rust handler next to the JS ones
+  ///
+  /// ```ignore
+  /// app.route(Method::Get, "/health", |_req, res: TachyonResponse| async move {
+  ///   res.send(Some(serde_json::json!({ "status": "OK" })));
+  /// })?;
+  /// ```
+  pub fn route<H>(&self, method: Method, route: &str, handler: H) -> Result<()>
+  where
+    H: TachyonHandler + 'static,
+  {
+    self.register(route, method, vec![Arc::new(handler)])
+  }
+
+  /// Accept WebSocket connections on a path, other requests get 426
+  pub fn websocket<H>(&self, route: &str, handler: H, options: WebSocketOptions) -> Result<()>
+  where
+    H: WebSocketHandler + 'static,
+  {
+    let handler = WebSocketRoute::new(Arc::new(handler), options, Arc::clone(&self.topics));
+    self.register(route, Method::Get, vec![Arc::new(handler)])
+  }
+
+  /// Serve the files of `root` under `prefix`, e.g. `/assets/app.css` from `./public/app.css`
+  pub fn serve_static(
+    &self,
+    prefix: &str,
+    root: impl AsRef<std::path::Path>,
+    options: StaticOptions,
+  ) -> Result<()> {
+    let handler: Arc<dyn TachyonHandler> = Arc::new(StaticFiles::new(root, options)?);
+    let prefix = prefix.trim_end_matches('/');
+    if !prefix.is_empty() {
+      self.register(prefix, Method::Get, vec![Arc::clone(&handler)])?;
+    }
+    self.register(&format!("{}/*", prefix), Method::Get, vec![handler])
+  }
+
+  /// Send a message to every WebSocket subscribed to `topic`, returning how many got it
+  pub fn publish(&self, topic: &str, message: impl Into<WebSocketMessage>) -> usize {
+    self.topics.publish(topic, &message.into())
+  }
+
   pub fn get_routes(&self) -> Arc<DashMap<String, TachyonRouter>> {
     Arc::clone(&self.routes)
   }
 
-  async fn echo(
-    routes: Arc<DashMap<String, TachyonRouter>>,
-    req: Request<hyper::body::Incoming>,
-  ) -> std::result::Result<Response<BoxBody<Bytes, hyper::Error>>, hyper::Error> {
-    let path = req.uri().path();
-    let method = Method::from(req.method());
+  /// Most specific route answering `path`
+  fn find_route(
+    routes: &DashMap<String, TachyonRouter>,
+    method_id: u8,
+    path: &str,
+    options: MatchOptions,
+  ) -> Option<RouteMatch> {
+    // Ultra-fast route lookup - try exact match first
+    let route_key = format!("{}:{}", method_id, path);
+    if let Some(route_ref) = routes
+      .get(&route_key)
+      .filter(|route_ref| route_ref.pattern().is_static())
+    {
+      return Some((route_ref.handler(), Vec::new(), route_ref.hooks().cloned()));
+    }
+
+    // Fallback to parameter matching only if exact match fails,
+    // keeping the most specific pattern when several of them match
+    let mut best: Option<RefMulti<'_, String, TachyonRouter>> = None;
+    for entry in routes.iter() {
+      let router = entry.value();
+      if router.method() != method_id || !router.pattern().is_match(path, options) {
+        continue;
+      }
+      let is_better = best.as_ref().is_none_or(|best| {
+        router.pattern().compare_specificity(best.value().pattern()) == Ordering::Less
+      });
+      if is_better {
+        best = Some(entry);
+      }
+    }
+
+    // Capture params once, for the winning pattern only
+    best.map(|entry| {
+      let router = entry.value();
+      let params = router
+        .pattern()
+        .captures(path, options)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+      (router.handler(), params, router.hooks().cloned())
+    })
+  }
+
+  pub(crate) async fn echo<B>(
+    self,
+    req: Request<B>,
+  ) -> std::result::Result<Response<BoxBody<Bytes, BoxError>>, B::Error>
+  where
+    B: hyper::body::Body<Data = Bytes>,
+  {
+    let started = Instant::now();
+    let global_hooks = self.global_hooks();
+
+    // hyper drops this future when the client goes away
+    let request = TachyonRequest::default();
+    let abort_guard = request.signal.abort_on_drop();
+    let (request, response, route_hooks) = self
+      .dispatch(req, request, global_hooks.as_deref(), started)
+      .await?;
+
+    // Without hooks the response goes out as is
+    let hooks = HookSet {
+      global: global_hooks.as_deref(),
+      route: route_hooks.as_deref(),
+    };
+    if hooks.is_empty() {
+      return Ok(into_http_response(&response, abort_guard));
+    }
+
+    hooks.run(HookName::OnSend, &request, &response).await;
+    let http_response = into_http_response(&response, abort_guard);
+
+    let on_response: Vec<_> = hooks.stage(HookName::OnResponse).cloned().collect();
+    if on_response.is_empty() {
+      return Ok(http_response);
+    }
+    Ok(http_response.map(|body| {
+      CompletionBody::wrap(body, move || {
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+          return;
+        };
+        runtime.spawn(async move {
+          for hook in on_response {
+            hook.call(request.clone(), response.clone()).await;
+          }
+        });
+      })
+    }))
+  }
+
+  /// Request and response objects of a request, and the hooks of its route
+  async fn dispatch<B>(
+    &self,
+    req: Request<B>,
+    request: TachyonRequest,
+    global_hooks: Option<&Hooks>,
+    started: Instant,
+  ) -> std::result::Result<Exchange, B::Error>
+  where
+    B: hyper::body::Body<Data = Bytes>,
+  {
+    let options = Arc::clone(&self.options);
+
+    // Match on the normalised path, never on `..` or duplicate slashes
+    let path = match normalize_path(req.uri().path(), options.merge_slashes()) {
+      Ok(path) => path,
+      Err(err) => return Ok(self.bad_request(err, request, started).await),
+    };
+    let path = path.as_ref();
+    let method = Method::from_http(req.method());
+
+    // Virtual hosts only see their own routes, other hosts the app ones
+    let (routes, host_params) = match self.select_host(&req) {
+      Some((virtual_host, params)) => (virtual_host.route_table(), params),
+      None => (Arc::clone(&self.routes), Vec::new()),
+    };
+
+    let match_options = options.match_options();
+    let matched = method
+      .as_ref()
+      .and_then(|method| Self::find_route(&routes, method.id(), path, match_options));
+
+    let Some((handler, params, route_hooks)) = matched else {
+      // Unless the path only misses or has an extra trailing slash
+      if let Some(method) = method
+        .as_ref()
+        .filter(|_| options.redirect_trailing_slash())
+      {
+        if path != "/" {
+          let alternate = match path.strip_suffix('/') {
+            Some(trimmed) => trimmed.to_string(),
+            None => format!("{}/", path),
+          };
+          if Self::find_route(&routes, method.id(), &alternate, match_options).is_some() {
+            let response = redirect(method, alternate, req.uri().query()).with_start(started);
+            return Ok((request, response, None));
+          }
+        }
+      }
+
+      // The path exists for other methods only
+      let allowed = Self::allowed_methods(&routes, path, match_options);
+      let response = if allowed.is_empty() {
+        self
+          .render_error(HttpError::not_found(), request.clone(), started)
+          .await
+      } else {
+        let response = self
+          .render_error(HttpError::method_not_allowed(), request.clone(), started)
+          .await;
+        // Method names are always valid header values
+        let _ = response.set_header("allow", &allowed.join(", "));
+        response
+      };
+      return Ok((request, response, None));
+    };
+    let method = method.unwrap_or(Method::Get);
+
+    // Decode params once, for the winning pattern only, path params
+    // taking precedence over host labels with the same name
+    let params = params
+      .into_iter()
+      .map(|(name, value)| {
+        let value = decode_param(&value, options.allow_encoded_slashes())?.into_owned();
+        Ok((name, value))
+      })
+      .collect::<Result<Vec<_>>>()
+      .map(|params| {
+        host_params
+          .into_iter()
+          .chain(params)
+          .collect::<HashMap<_, _>>()
+      });
+    let params = match params {
+      Ok(params) => params,
+      Err(err) => return Ok(self.bad_request(err, request, started).await),
+    };
+
+    // Create request and response objects
+    let mut request = request.with_params(params);
+    let response = TachyonResponse::new()
+      .with_start(started)
+      .with_locals(request.locals.clone());
+    let hooks = HookSet {
+      global: global_hooks,
+      route: route_hooks.as_deref(),
+    };
+
+    let failed = self
+      .run_route(req, handler, hooks, &mut request, &response, &method)
+      .await?
+      .or_else(|| response.error().filter(|_| !response.is_sent()).cloned());
+    let response = match failed {
+      Some(err) => self.render_error(err, request.clone(), started).await,
+      None => response,
+    };
+
+    Ok((request, response, route_hooks))
+  }
+
+  /// Read the body and call the hooks and handlers of the matched route
+  ///
+  /// Returns the error to render when the request is rejected before the
+  /// handlers run.
+  async fn run_route<B>(
+    &self,
+    mut req: Request<B>,
+    handler: Arc<dyn TachyonHandler>,
+    hooks: HookSet<'_>,
+    request: &mut TachyonRequest,
+    response: &TachyonResponse,
+    method: &Method,
+  ) -> std::result::Result<Option<HttpError>, B::Error>
+  where
+    B: hyper::body::Body<Data = Bytes>,
+  {
+    let replied = |response: &TachyonResponse| response.is_sent() || response.error().is_some();
+
+    // WebSocket handshakes wait for the route to take the connection over
+    if let Some(upgrade) = PendingUpgrade::from_request(&mut req) {
+      request.locals.insert(upgrade);
+    }
+
+    // Kept for the file responses, which may answer 304
+    if let Some(conditions) = ConditionalHeaders::from_headers(req.headers()) {
+      request.locals.insert(conditions);
+    }
+
+    // Sent by browsers reconnecting to a Server-Sent Events stream
+    request.last_event_id = req
+      .headers()
+      .get("last-event-id")
+      .and_then(|id| id.to_str().ok())
+      .map(str::to_string);
+
+    hooks.run(HookName::OnRequest, request, response).await;
+    if replied(response) {
+      return Ok(None);
+    }
 
     // Fast content-type check
     let is_json = req
@@ -222,77 +1059,272 @@ impl Tachyon {
       .map(|ct| ct.starts_with("application/json"))
       .unwrap_or(false);
 
-    // Pre-build route key for fastest lookup
-    let route_key = format!("{}:{}", method.id(), path);
-
-    // Ultra-fast route lookup - try exact match first
-    let handler = if let Some(route_ref) = routes.get(&route_key) {
-      Some(route_ref.handler())
-    } else {
-      // Fallback to parameter matching only if exact match fails
-      routes
-        .iter()
-        .find(|entry| utils::route_matches(entry.key(), &route_key))
-        .map(|entry| entry.value().handler())
-    };
-
-    // If no route found, return 404 immediately
-    let Some(handler) = handler else {
-      return Ok(
-        Response::builder()
-          .status(StatusCode::NOT_FOUND)
-          .body(full(NOTFOUND))
-          .unwrap(),
-      );
-    };
+    // Reject announced oversized bodies before reading them
+    let body_limit = self.options.body_limit();
+    let content_length = req
+      .headers()
+      .get(header::CONTENT_LENGTH)
+      .and_then(|len| len.to_str().ok())
+      .and_then(|len| len.parse::<u64>().ok());
+    if content_length
+      .zip(body_limit)
+      .is_some_and(|(len, limit)| len > limit as u64)
+    {
+      return Ok(Some(HttpError::payload_too_large()));
+    }
 
-    // Collect body
-    let whole_body = req.collect().await?.to_bytes();
+    // Collect body, chunked ones being cut off at the limit
+    let mut body = std::pin::pin!(req.into_body());
+    let mut whole_body = Vec::with_capacity(content_length.unwrap_or(0) as usize);
+    while let Some(frame) = body.frame().await {
+      if let Ok(data) = frame?.into_data() {
+        if body_limit.is_some_and(|limit| whole_body.len() + data.len() > limit) {
+          return Ok(Some(HttpError::payload_too_large()));
+        }
+        whole_body.extend_from_slice(&data);
+      }
+    }
 
     // Parse JSON only if content-type is JSON
-    let data = if is_json {
-      serde_json::from_slice(&whole_body).unwrap_or(serde_json::Value::Null)
-    } else {
-      serde_json::Value::Null
-    };
+    if is_json {
+      request.body = serde_json::from_slice(&whole_body).unwrap_or(serde_json::Value::Null);
+    }
 
     // Validate JSON for POST/PUT/PATCH
-    if data.is_null()
+    if request.body.is_null()
       && is_json
-      && (method == Method::Post || method == Method::Put || method == Method::Patch)
+      && (*method == Method::Post || *method == Method::Put || *method == Method::Patch)
     {
-      return Ok(
-        Response::builder()
-          .status(StatusCode::BAD_REQUEST)
-          .body(full("Invalid JSON"))
-          .unwrap(),
-      );
+      return Ok(Some(HttpError::invalid_json()));
     }
 
-    // Create request and response objects
-    let request = TachyonRequest::new(data);
-    let response = TachyonResponse::new();
+    hooks.run(HookName::PreHandler, request, response).await;
+    if replied(response) {
+      return Ok(None);
+    }
 
     // Call handler (supports both sync and async)
-    handler.call(request, response.clone()).await;
+    let call = {
+      let (request, response) = (request.clone(), response.clone());
+      async move {
+        handler.call(request, response.clone()).await;
+        // e.g. a file the handler did not wait for
+        response.settled().await;
+      }
+    };
+    let mut call = Box::pin(call);
+    tokio::select! {
+      _ = &mut call => {}
+      // A streamed body goes out while the handler still writes it
+      _ = response.streaming() => {
+        task::spawn(call);
+      }
+      _ = deadline(self.options.handler_timeout()) => {
+        if !replied(response) {
+          // Let the handler stop the work it still does in the background
+          request.signal.abort(AbortReason::TimedOut);
+          response.fail(HttpError::handler_timeout());
+        }
+        task::spawn(call);
+      }
+    }
+
+    if let Some(err) = response.error().filter(|_| response.is_sent()) {
+      eprintln!("Error after the response was sent: {}", err);
+    }
+    Ok(None)
+  }
 
-    // Build response with minimal allocations
-    let status_code =
-      StatusCode::from_u16(response.get_status()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+  /// Names of the methods having a route for `path`, e.g. `["GET", "POST"]`
+  fn allowed_methods(
+    routes: &DashMap<String, TachyonRouter>,
+    path: &str,
+    options: MatchOptions,
+  ) -> Vec<String> {
+    (0..=4)
+      .filter(|&id| Self::find_route(routes, id, path, options).is_some())
+      .map(|id| Method::new(id).to_string())
+      .collect()
+  }
+
+  async fn bad_request(
+    &self,
+    err: TachyonError,
+    request: TachyonRequest,
+    started: Instant,
+  ) -> Exchange {
+    let err = HttpError::bad_request(err.reason());
+    let response = self.render_error(err, request.clone(), started).await;
+    (request, response, None)
+  }
+
+  /// Response for an error, from `notFound`, `onError` or the plain default
+  ///
+  /// Each handler only takes over when it sends a response.
+  async fn render_error(
+    &self,
+    mut err: HttpError,
+    request: TachyonRequest,
+    started: Instant,
+  ) -> TachyonResponse {
+    // Error handlers share the locals set before the request failed
+    let new_response = |status: u16| {
+      TachyonResponse::new()
+        .with_start(started)
+        .with_locals(request.locals.clone())
+        .status(status)
+    };
 
-    let mut response_builder = Response::builder().status(status_code);
+    if err.status == 404 {
+      let not_found = self
+        .not_found
+        .read()
+        .ok()
+        .and_then(|handler| handler.clone());
+      if let Some(not_found) = not_found {
+        let response = new_response(404);
+        not_found.call(request.clone(), response.clone()).await;
+        if response.is_sent() {
+          return response;
+        }
+        if let Some(not_found_err) = response.error() {
+          err = not_found_err.clone();
+        }
+      }
+    }
 
-    let response_data = if let Some(data) = response.take_data() {
-      // Auto-detect JSON response
-      let trimmed = data.trim_start();
-      if trimmed.starts_with('{') || trimmed.starts_with('[') {
-        response_builder = response_builder.header(header::CONTENT_TYPE, "application/json");
+    let error_handler = self
+      .error_handler
+      .read()
+      .ok()
+      .and_then(|handler| handler.clone());
+    if let Some(error_handler) = error_handler {
+      let response = new_response(err.status);
+      error_handler
+        .call(err.clone(), request.clone(), response.clone())
+        .await;
+      if response.is_sent() {
+        return response;
       }
-      full(data)
+    }
+
+    let status = StatusCode::from_u16(err.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+    // Server errors may carry internal details, only log them
+    let body = if status.is_server_error() {
+      eprintln!("Error serving request: {}", err);
+      status.canonical_reason().unwrap_or_default().to_string()
     } else {
-      empty()
+      err.message
     };
 
-    Ok(response_builder.body(response_data).unwrap())
+    let response = new_response(status.as_u16());
+    response.send_raw(body);
+    response
+  }
+}
+
+/// Resolves once `timeout` elapsed, never without one
+async fn deadline(timeout: Option<Duration>) {
+  match timeout {
+    Some(timeout) => tokio::time::sleep(timeout).await,
+    None => std::future::pending().await,
+  }
+}
+
+/// HTTP response for what the handlers sent
+///
+/// The request is aborted when the client goes away before the end of a
+/// streamed body.
+fn into_http_response(
+  response: &TachyonResponse,
+  abort_guard: AbortGuard,
+) -> Response<BoxBody<Bytes, BoxError>> {
+  // Build response with minimal allocations
+  let status_code =
+    StatusCode::from_u16(response.get_status()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+
+  let mut response_builder = Response::builder().status(status_code);
+  let headers = response.take_headers();
+  let has_content_type = headers.contains_key(header::CONTENT_TYPE);
+  if let Some(builder_headers) = response_builder.headers_mut() {
+    builder_headers.extend(headers);
+  }
+
+  if let Some(stream) = response.take_stream() {
+    let body = stream.with_abort_guard(abort_guard).boxed();
+    return response_builder.body(body).unwrap();
+  }
+  abort_guard.disarm();
+
+  let response_data = if let Some(data) = response.take_data() {
+    // Auto-detect JSON response
+    let trimmed = data.trim_start();
+    if !has_content_type && (trimmed.starts_with('{') || trimmed.starts_with('[')) {
+      response_builder = response_builder.header(header::CONTENT_TYPE, "application/json");
+    }
+    full(data)
+  } else {
+    empty()
+  };
+
+  response_builder.body(response_data).unwrap()
+}
+
+/// Send the client to the canonical form of the path, keeping the query
+fn redirect(method: &Method, path: String, query: Option<&str>) -> TachyonResponse {
+  // 308 keeps the method and body of non GET requests
+  let status = if *method == Method::Get {
+    StatusCode::MOVED_PERMANENTLY
+  } else {
+    StatusCode::PERMANENT_REDIRECT
+  };
+  let location = match query {
+    Some(query) => format!("{}?{}", path, query),
+    None => path,
+  };
+
+  let response = TachyonResponse::new().status(status.as_u16());
+  // Always valid, both parts come from the request URI
+  let _ = response.set_header("location", &location);
+  response
+}
+
+/// Text of a param or query value passed to `url`
+#[cfg(feature = "napi")]
+fn url_value(key: &str, value: &Value) -> Result<String> {
+  match value {
+    Value::String(text) => Ok(text.clone()),
+    Value::Number(number) => Ok(number.to_string()),
+    Value::Bool(flag) => Ok(flag.to_string()),
+    _ => Err(TachyonError::new(format!(
+      "Value of '{}' must be a string, number or boolean",
+      key
+    ))),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::core::methods::Method;
+
+  #[test]
+  fn test_method_mismatch() {
+    let app = Tachyon::new();
+    let handler: Arc<dyn TachyonHandler> = Arc::new(|_req, _res| async move {});
+    app
+      .register("/users", Method::Get, vec![handler.clone()])
+      .unwrap();
+    app
+      .register("/users/:id", Method::Get, vec![handler])
+      .unwrap();
+
+    let find = |method: Method, path: &str| {
+      Tachyon::find_route(&app.routes, method.id(), path, MatchOptions::default()).is_some()
+    };
+    assert!(find(Method::Get, "/users"));
+    assert!(find(Method::Get, "/users/7"));
+    assert!(!find(Method::Post, "/users"));
+    assert!(!find(Method::Delete, "/users/7"));
   }
 }
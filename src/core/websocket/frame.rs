@@ -0,0 +1,208 @@
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+
+/// Close codes of RFC 6455 used by the server
+pub mod close_code {
+  pub const NORMAL: u16 = 1000;
+  pub const PROTOCOL_ERROR: u16 = 1002;
+  pub const NO_STATUS: u16 = 1005;
+  pub const ABNORMAL: u16 = 1006;
+  pub const INVALID_DATA: u16 = 1007;
+  pub const TOO_BIG: u16 = 1009;
+  pub const TRY_AGAIN_LATER: u16 = 1013;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OpCode {
+  Continuation,
+  Text,
+  Binary,
+  Close,
+  Ping,
+  Pong,
+}
+
+impl OpCode {
+  fn from_bits(bits: u8) -> Option<Self> {
+    match bits {
+      0x0 => Some(OpCode::Continuation),
+      0x1 => Some(OpCode::Text),
+      0x2 => Some(OpCode::Binary),
+      0x8 => Some(OpCode::Close),
+      0x9 => Some(OpCode::Ping),
+      0xA => Some(OpCode::Pong),
+      _ => None,
+    }
+  }
+
+  fn bits(self) -> u8 {
+    match self {
+      OpCode::Continuation => 0x0,
+      OpCode::Text => 0x1,
+      OpCode::Binary => 0x2,
+      OpCode::Close => 0x8,
+      OpCode::Ping => 0x9,
+      OpCode::Pong => 0xA,
+    }
+  }
+
+  pub fn is_control(self) -> bool {
+    matches!(self, OpCode::Close | OpCode::Ping | OpCode::Pong)
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+  pub fin: bool,
+  pub opcode: OpCode,
+  pub payload: Bytes,
+}
+
+/// Take the first frame sent by a client off `buf`, `None` until it is complete
+///
+/// Fails with the close code to answer with when the frame breaks the
+/// protocol or its payload is bigger than `max_payload`.
+pub fn parse(buf: &mut BytesMut, max_payload: usize) -> Result<Option<Frame>, u16> {
+  if buf.len() < 2 {
+    return Ok(None);
+  }
+
+  let fin = buf[0] & 0x80 != 0;
+  // No extension is negotiated, reserved bits must stay clear
+  if buf[0] & 0x70 != 0 {
+    return Err(close_code::PROTOCOL_ERROR);
+  }
+  let opcode = OpCode::from_bits(buf[0] & 0x0F).ok_or(close_code::PROTOCOL_ERROR)?;
+  // Clients always mask their frames
+  if buf[1] & 0x80 == 0 {
+    return Err(close_code::PROTOCOL_ERROR);
+  }
+
+  let (len, offset) = match buf[1] & 0x7F {
+    126 if buf.len() >= 4 => (u16::from_be_bytes([buf[2], buf[3]]) as u64, 4),
+    127 if buf.len() >= 10 => {
+      let mut len = [0; 8];
+      len.copy_from_slice(&buf[2..10]);
+      (u64::from_be_bytes(len), 10)
+    }
+    126 | 127 => return Ok(None),
+    len => (len as u64, 2),
+  };
+  if opcode.is_control() && (len > 125 || !fin) {
+    return Err(close_code::PROTOCOL_ERROR);
+  }
+  if len > max_payload as u64 {
+    return Err(close_code::TOO_BIG);
+  }
+
+  let len = len as usize;
+  let header = offset + 4;
+  if buf.len() < header + len {
+    buf.reserve(header + len - buf.len());
+    return Ok(None);
+  }
+
+  let mut mask = [0; 4];
+  mask.copy_from_slice(&buf[offset..header]);
+  buf.advance(header);
+  let mut payload = buf.split_to(len);
+  for (i, byte) in payload.iter_mut().enumerate() {
+    *byte ^= mask[i % 4];
+  }
+
+  Ok(Some(Frame {
+    fin,
+    opcode,
+    payload: payload.freeze(),
+  }))
+}
+
+/// Single unmasked frame, as servers send them
+pub fn encode(opcode: OpCode, payload: &[u8]) -> Bytes {
+  let mut frame = BytesMut::with_capacity(payload.len() + 10);
+  frame.put_u8(0x80 | opcode.bits());
+  match payload.len() {
+    len @ 0..=125 => frame.put_u8(len as u8),
+    len @ 126..=0xFFFF => {
+      frame.put_u8(126);
+      frame.put_u16(len as u16);
+    }
+    len => {
+      frame.put_u8(127);
+      frame.put_u64(len as u64);
+    }
+  }
+  frame.put_slice(payload);
+  frame.freeze()
+}
+
+/// Payload of a close frame
+pub fn close_payload(code: u16, reason: &str) -> Vec<u8> {
+  let mut payload = code.to_be_bytes().to_vec();
+  payload.extend_from_slice(reason.as_bytes());
+  payload
+}
+
+/// Code and reason of a received close frame
+pub fn parse_close(payload: &[u8]) -> Result<(u16, String), u16> {
+  match payload {
+    [] => Ok((close_code::NO_STATUS, String::new())),
+    [_] => Err(close_code::PROTOCOL_ERROR),
+    [high, low, reason @ ..] => {
+      let reason = std::str::from_utf8(reason).map_err(|_| close_code::INVALID_DATA)?;
+      Ok((u16::from_be_bytes([*high, *low]), reason.to_string()))
+    }
+  }
+}
+
+/// Whether an endpoint may send `code` in a close frame
+pub fn is_valid_close_code(code: u16) -> bool {
+  matches!(code, 1000..=1003 | 1007..=1011 | 3000..=4999)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn masked(first: u8, payload: &[u8]) -> BytesMut {
+    let mask = [0x37, 0xfa, 0x21, 0x3d];
+    let mut frame = BytesMut::new();
+    frame.put_u8(first);
+    frame.put_u8(0x80 | payload.len() as u8);
+    frame.put_slice(&mask);
+    frame.extend(payload.iter().enumerate().map(|(i, b)| b ^ mask[i % 4]));
+    frame
+  }
+
+  #[test]
+  fn test_parse_frames() {
+    // Masked "Hello" from RFC 6455, section 5.7, split across two reads
+    let mut buf = masked(0x81, b"Hello");
+    let rest = buf.split_off(4);
+    assert_eq!(parse(&mut buf, 1024), Ok(None));
+    buf.unsplit(rest);
+    let frame = parse(&mut buf, 1024).unwrap().unwrap();
+    assert!(frame.fin);
+    assert_eq!(frame.opcode, OpCode::Text);
+    assert_eq!(frame.payload, "Hello");
+    assert!(buf.is_empty());
+
+    assert_eq!(parse(&mut masked(0x81, b"Hello"), 4), Err(1009));
+    // Unmasked, reserved bit set, fragmented ping
+    assert_eq!(parse(&mut BytesMut::from(&[0x81, 0x00][..]), 4), Err(1002));
+    assert_eq!(parse(&mut masked(0xC1, b"x"), 4), Err(1002));
+    assert_eq!(parse(&mut masked(0x09, b""), 4), Err(1002));
+  }
+
+  #[test]
+  fn test_encode_frames() {
+    assert_eq!(&encode(OpCode::Text, b"Hi")[..], b"\x81\x02Hi");
+    let long = encode(OpCode::Binary, &[0; 300]);
+    assert_eq!(&long[..4], &[0x82, 126, 0x01, 0x2C]);
+    assert_eq!(long.len(), 304);
+
+    let close = close_payload(1000, "bye");
+    assert_eq!(parse_close(&close), Ok((1000, "bye".to_string())));
+    assert_eq!(parse_close(&[]), Ok((1005, String::new())));
+    assert!(!is_valid_close_code(1005));
+  }
+}
@@ -0,0 +1,133 @@
+use hyper::{header, HeaderMap, Method};
+
+/// Appended to the client key before hashing, see RFC 6455 section 1.3
+const GUID: &[u8] = b"258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// Whether the request asks to switch to the WebSocket protocol
+pub fn is_upgrade(method: &Method, headers: &HeaderMap) -> bool {
+  let has_token = |name: header::HeaderName, token: &str| {
+    headers
+      .get_all(name)
+      .iter()
+      .filter_map(|value| value.to_str().ok())
+      .flat_map(|value| value.split(','))
+      .any(|value| value.trim().eq_ignore_ascii_case(token))
+  };
+
+  method == Method::GET
+    && has_token(header::UPGRADE, "websocket")
+    && has_token(header::CONNECTION, "upgrade")
+    && headers
+      .get(header::SEC_WEBSOCKET_VERSION)
+      .is_some_and(|version| version == "13")
+    && headers.contains_key(header::SEC_WEBSOCKET_KEY)
+}
+
+/// `Sec-WebSocket-Accept` value answering `Sec-WebSocket-Key`
+pub fn accept_key(key: &[u8]) -> String {
+  let mut input = key.to_vec();
+  input.extend_from_slice(GUID);
+  base64(&sha1(&input))
+}
+
+/// SHA-1 of RFC 3174, only fit for the handshake
+fn sha1(data: &[u8]) -> [u8; 20] {
+  let mut state: [u32; 5] = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476, 0xC3D2E1F0];
+
+  let mut message = data.to_vec();
+  message.push(0x80);
+  while message.len() % 64 != 56 {
+    message.push(0);
+  }
+  message.extend_from_slice(&((data.len() as u64) * 8).to_be_bytes());
+
+  for block in message.chunks_exact(64) {
+    let mut words = [0u32; 80];
+    for (i, word) in block.chunks_exact(4).enumerate() {
+      words[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+    }
+    for i in 16..80 {
+      words[i] = (words[i - 3] ^ words[i - 8] ^ words[i - 14] ^ words[i - 16]).rotate_left(1);
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e] = state;
+    for (i, word) in words.iter().enumerate() {
+      let (f, k) = match i {
+        0..=19 => ((b & c) | (!b & d), 0x5A827999),
+        20..=39 => (b ^ c ^ d, 0x6ED9EBA1),
+        40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1BBCDC),
+        _ => (b ^ c ^ d, 0xCA62C1D6),
+      };
+      let temp = a
+        .rotate_left(5)
+        .wrapping_add(f)
+        .wrapping_add(e)
+        .wrapping_add(k)
+        .wrapping_add(*word);
+      e = d;
+      d = c;
+      c = b.rotate_left(30);
+      b = a;
+      a = temp;
+    }
+
+    for (value, add) in state.iter_mut().zip([a, b, c, d, e]) {
+      *value = value.wrapping_add(add);
+    }
+  }
+
+  let mut digest = [0; 20];
+  for (bytes, value) in digest.chunks_exact_mut(4).zip(state) {
+    bytes.copy_from_slice(&value.to_be_bytes());
+  }
+  digest
+}
+
+fn base64(data: &[u8]) -> String {
+  const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+  let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+  for chunk in data.chunks(3) {
+    let bytes = [
+      chunk[0],
+      *chunk.get(1).unwrap_or(&0),
+      *chunk.get(2).unwrap_or(&0),
+    ];
+    let bits = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+    for i in 0..4 {
+      if i <= chunk.len() {
+        encoded.push(ALPHABET[(bits >> (18 - i * 6)) as usize & 0x3F] as char);
+      } else {
+        encoded.push('=');
+      }
+    }
+  }
+  encoded
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_accept_key() {
+    // Example of RFC 6455, section 1.3
+    assert_eq!(
+      accept_key(b"dGhlIHNhbXBsZSBub25jZQ=="),
+      "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+    );
+    assert_eq!(base64(b"ab"), "YWI=");
+    assert_eq!(base64(b"a"), "YQ==");
+
+    let mut headers = HeaderMap::new();
+    headers.insert(header::UPGRADE, "WebSocket".parse().unwrap());
+    headers.insert(header::CONNECTION, "keep-alive, Upgrade".parse().unwrap());
+    headers.insert(header::SEC_WEBSOCKET_VERSION, "13".parse().unwrap());
+    headers.insert(header::SEC_WEBSOCKET_KEY, "x".parse().unwrap());
+    assert!(is_upgrade(&Method::GET, &headers));
+    assert!(!is_upgrade(&Method::POST, &headers));
+
+    headers.insert(header::SEC_WEBSOCKET_VERSION, "8".parse().unwrap());
+    assert!(!is_upgrade(&Method::GET, &headers));
+  }
+}
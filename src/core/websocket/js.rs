@@ -0,0 +1,145 @@
+use async_trait::async_trait;
+use napi::{
+  bindgen_prelude::{
+    Buffer, Either, FnArgs, FromNapiValue, Function, JsValuesTupleIntoVec, Object, ToNapiValue,
+    TypeName,
+  },
+  sys,
+  threadsafe_function::ThreadsafeFunction,
+  Env, JsValue, Result, Status, ValueType,
+};
+use napi_derive::napi;
+use std::sync::Arc;
+
+use super::{WebSocket, WebSocketHandler, WebSocketMessage};
+use crate::core::{js_ref::JsRef, wrapper::HandlerReturn};
+
+type Callback<Args> = Function<'static, FnArgs<Args>, HandlerReturn>;
+
+type Tsfn<Args> = ThreadsafeFunction<FnArgs<Args>, HandlerReturn, FnArgs<Args>, Status, false>;
+
+type MessageData = Either<String, Buffer>;
+
+/// Callbacks of a WebSocket route as received from `app.ws(...)`
+#[napi(object, object_to_js = false)]
+pub struct WebSocketCallbacks {
+  pub open: Option<Callback<(JsWebSocket,)>>,
+  pub message: Option<Callback<(JsWebSocket, MessageData)>>,
+  pub close: Option<Callback<(JsWebSocket, u16, String)>>,
+}
+
+/// Threadsafe functions of the callbacks of a WebSocket route
+struct JsWebSocketHandler {
+  open: Option<Tsfn<(JsWebSocket,)>>,
+  message: Option<Tsfn<(JsWebSocket, MessageData)>>,
+  close: Option<Tsfn<(JsWebSocket, u16, String)>>,
+}
+
+pub fn into_websocket_handler(callbacks: WebSocketCallbacks) -> Result<Arc<dyn WebSocketHandler>> {
+  fn threadsafe<Args>(callback: Option<Callback<Args>>) -> Result<Option<Tsfn<Args>>>
+  where
+    Args: 'static,
+    FnArgs<Args>: JsValuesTupleIntoVec,
+  {
+    callback
+      .map(|callback| callback.build_threadsafe_function().weak::<false>().build())
+      .transpose()
+  }
+
+  Ok(Arc::new(JsWebSocketHandler {
+    open: threadsafe(callbacks.open)?,
+    message: threadsafe(callbacks.message)?,
+    close: threadsafe(callbacks.close)?,
+  }))
+}
+
+/// Call a callback in order with the other ones of the connection
+///
+/// Async callbacks are not awaited, a slow one must not hold the connection.
+async fn invoke<Args>(callback: &Option<Tsfn<Args>>, args: Args, name: &'static str)
+where
+  Args: 'static,
+  FnArgs<Args>: JsValuesTupleIntoVec,
+{
+  let Some(callback) = callback else {
+    return;
+  };
+  match callback.call_async_catch(args.into()).await {
+    Ok(HandlerReturn::Async(promise)) => {
+      tokio::spawn(async move {
+        if let Err(err) = promise.await {
+          eprintln!("Error in WebSocket {} callback: {}", name, err);
+        }
+      });
+    }
+    Ok(HandlerReturn::Sync) => {}
+    Err(err) => eprintln!("Error in WebSocket {} callback: {}", name, err),
+  }
+}
+
+#[async_trait]
+impl WebSocketHandler for JsWebSocketHandler {
+  async fn open(&self, ws: WebSocket) {
+    invoke(&self.open, (JsWebSocket(ws),), "open").await;
+  }
+
+  async fn message(&self, ws: WebSocket, message: WebSocketMessage) {
+    let data = match message {
+      WebSocketMessage::Text(text) => Either::A(text),
+      WebSocketMessage::Binary(data) => Either::B(data.to_vec().into()),
+    };
+    invoke(&self.message, (JsWebSocket(ws), data), "message").await;
+  }
+
+  async fn close(&self, ws: WebSocket, code: u16, reason: String) {
+    invoke(&self.close, (JsWebSocket(ws), code, reason), "close").await;
+  }
+}
+
+impl WebSocket {
+  /// The JS object of the connection, the same one for every callback
+  pub fn object<'env>(&self, env: &'env Env) -> Result<Object<'env>> {
+    let mut slot = self
+      .inner
+      .object
+      .lock()
+      .map_err(|_| napi::Error::from_reason("WebSocket is unavailable"))?;
+    if let Some(object) = slot.as_ref() {
+      return object.get(env);
+    }
+
+    let object = unsafe {
+      let raw = <WebSocket as ToNapiValue>::to_napi_value(env.raw(), self.clone())?;
+      Object::from_napi_value(env.raw(), raw)?
+    };
+    *slot = Some(JsRef::new(env, &object)?);
+    Ok(object)
+  }
+
+  /// Let the JS object go once the connection is closed
+  pub(super) fn release_object(&self) {
+    if let Ok(mut slot) = self.inner.object.lock() {
+      slot.take();
+    }
+  }
+}
+
+/// Connection passed to JS callbacks, keeping `ws` the same object for a connection
+pub struct JsWebSocket(WebSocket);
+
+impl TypeName for JsWebSocket {
+  fn type_name() -> &'static str {
+    "WebSocket"
+  }
+
+  fn value_type() -> ValueType {
+    ValueType::Object
+  }
+}
+
+impl ToNapiValue for JsWebSocket {
+  unsafe fn to_napi_value(env: sys::napi_env, val: Self) -> Result<sys::napi_value> {
+    let env = Env::from_raw(env);
+    Ok(val.0.object(&env)?.raw())
+  }
+}
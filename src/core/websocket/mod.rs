@@ -0,0 +1,788 @@
+use async_trait::async_trait;
+use bytes::{Bytes, BytesMut};
+use hyper::{header, header::HeaderValue, upgrade::OnUpgrade, Request};
+use hyper_util::rt::TokioIo;
+#[cfg(feature = "napi")]
+use napi_derive::napi;
+use std::{
+  collections::{HashMap, HashSet},
+  sync::{
+    atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+    Arc, Mutex,
+  },
+  time::Duration,
+};
+use tokio::{
+  io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+  sync::mpsc,
+  time::{self, Instant, Interval},
+};
+
+use crate::{
+  core::{
+    locals::Locals, options::WebSocketOptions, path::parse_query, request::TachyonRequest,
+    response::TachyonResponse, router::TachyonHandler, stream::MAX_BUFFERED,
+  },
+  error::{HttpError, Result, TachyonError},
+};
+use frame::{close_code, Frame, OpCode};
+
+mod frame;
+mod handshake;
+#[cfg(feature = "napi")]
+mod js;
+mod topics;
+
+#[cfg(feature = "napi")]
+pub(crate) use js::{into_websocket_handler, WebSocketCallbacks};
+pub(crate) use topics::Topics;
+
+/// How long a closing connection waits for the close frame of the client
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Message received from or sent to a client
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WebSocketMessage {
+  Text(String),
+  Binary(Bytes),
+}
+
+impl WebSocketMessage {
+  /// Frame carrying the message
+  fn encode(&self) -> Bytes {
+    match self {
+      WebSocketMessage::Text(text) => frame::encode(OpCode::Text, text.as_bytes()),
+      WebSocketMessage::Binary(data) => frame::encode(OpCode::Binary, data),
+    }
+  }
+}
+
+impl From<String> for WebSocketMessage {
+  fn from(text: String) -> Self {
+    WebSocketMessage::Text(text)
+  }
+}
+
+impl From<&str> for WebSocketMessage {
+  fn from(text: &str) -> Self {
+    WebSocketMessage::Text(text.to_string())
+  }
+}
+
+impl From<Bytes> for WebSocketMessage {
+  fn from(data: Bytes) -> Self {
+    WebSocketMessage::Binary(data)
+  }
+}
+
+impl From<Vec<u8>> for WebSocketMessage {
+  fn from(data: Vec<u8>) -> Self {
+    WebSocketMessage::Binary(data.into())
+  }
+}
+
+/// Callbacks of a WebSocket route, called in order for each connection
+#[async_trait]
+pub trait WebSocketHandler: Send + Sync {
+  /// Connection established, `ws.params()` and `ws.query()` tell which one
+  async fn open(&self, _ws: WebSocket) {}
+
+  async fn message(&self, ws: WebSocket, message: WebSocketMessage);
+
+  /// Connection gone, with 1006 when the client vanished without a close frame
+  async fn close(&self, _ws: WebSocket, _code: u16, _reason: String) {}
+}
+
+enum Outgoing {
+  /// Encoded data frame, shared by every subscriber of a topic
+  Frame(Bytes),
+  Close(u16, String),
+}
+
+/// Open connection of a WebSocket route, cheap to clone
+#[cfg_attr(feature = "napi", napi)]
+#[derive(Clone)]
+pub struct WebSocket {
+  inner: Arc<WebSocketInner>,
+}
+
+struct WebSocketInner {
+  id: u64,
+  outgoing: mpsc::UnboundedSender<Outgoing>,
+  /// Bytes of the frames queued and not written yet
+  buffered: AtomicUsize,
+  topics: Arc<Topics>,
+  subscriptions: Mutex<HashSet<String>>,
+  params: HashMap<String, String>,
+  query: HashMap<String, String>,
+  locals: Locals,
+  closed: AtomicBool,
+  #[cfg(feature = "napi")]
+  object: Mutex<Option<crate::core::js_ref::JsRef>>,
+}
+
+impl WebSocket {
+  fn new(
+    request: &TachyonRequest,
+    query: HashMap<String, String>,
+    outgoing: mpsc::UnboundedSender<Outgoing>,
+    topics: Arc<Topics>,
+  ) -> Self {
+    static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+    Self {
+      inner: Arc::new(WebSocketInner {
+        id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+        outgoing,
+        buffered: AtomicUsize::new(0),
+        topics,
+        subscriptions: Mutex::new(HashSet::new()),
+        params: request.params.clone(),
+        query,
+        locals: request.locals.clone(),
+        closed: AtomicBool::new(false),
+        #[cfg(feature = "napi")]
+        object: Mutex::new(None),
+      }),
+    }
+  }
+
+  /// Route params of the upgrade request
+  pub fn params(&self) -> &HashMap<String, String> {
+    &self.inner.params
+  }
+
+  /// Query string of the upgrade request, the last value winning
+  pub fn query(&self) -> &HashMap<String, String> {
+    &self.inner.query
+  }
+
+  /// Same values as `req.locals` of the upgrade request
+  pub fn locals(&self) -> &Locals {
+    &self.inner.locals
+  }
+
+  fn id(&self) -> u64 {
+    self.inner.id
+  }
+
+  /// Queue a message, false once the connection is closing
+  pub fn send(&self, message: impl Into<WebSocketMessage>) -> bool {
+    self.send_frame(message.into().encode())
+  }
+
+  fn send_frame(&self, frame: Bytes) -> bool {
+    if self.is_closed() {
+      return false;
+    }
+    // A client this far behind would make the queue grow without limit
+    if self.inner.buffered.load(Ordering::SeqCst) > MAX_BUFFERED {
+      self.start_close(
+        close_code::TRY_AGAIN_LATER,
+        "Client is too slow".to_string(),
+      );
+      return false;
+    }
+
+    let len = frame.len();
+    self.inner.buffered.fetch_add(len, Ordering::SeqCst);
+    if self.inner.outgoing.send(Outgoing::Frame(frame)).is_err() {
+      self.inner.buffered.fetch_sub(len, Ordering::SeqCst);
+      return false;
+    }
+    true
+  }
+
+  /// Receive the messages published to `topic` until unsubscribed or closed
+  pub fn subscribe(&self, topic: &str) {
+    let Ok(mut subscriptions) = self.inner.subscriptions.lock() else {
+      return;
+    };
+    // A closed connection was already taken off its topics
+    if !self.is_closed() && subscriptions.insert(topic.to_string()) {
+      self.inner.topics.subscribe(topic, self);
+    }
+  }
+
+  /// Stop receiving the messages of `topic`, false when not subscribed
+  pub fn unsubscribe(&self, topic: &str) -> bool {
+    let Ok(mut subscriptions) = self.inner.subscriptions.lock() else {
+      return false;
+    };
+    subscriptions.remove(topic) && self.inner.topics.unsubscribe(topic, self)
+  }
+
+  pub fn is_subscribed(&self, topic: &str) -> bool {
+    self
+      .inner
+      .subscriptions
+      .lock()
+      .is_ok_and(|subscriptions| subscriptions.contains(topic))
+  }
+
+  /// Topics the connection is subscribed to
+  pub fn topics(&self) -> Vec<String> {
+    let mut topics: Vec<_> = self
+      .inner
+      .subscriptions
+      .lock()
+      .map(|subscriptions| subscriptions.iter().cloned().collect())
+      .unwrap_or_default();
+    topics.sort();
+    topics
+  }
+
+  /// Leave every topic, once the connection is gone
+  fn unsubscribe_all(&self) {
+    if let Ok(mut subscriptions) = self.inner.subscriptions.lock() {
+      for topic in subscriptions.drain() {
+        self.inner.topics.unsubscribe(&topic, self);
+      }
+    }
+  }
+
+  /// Start the close handshake, with 1000 unless another code is given
+  pub fn close(&self, code: Option<u16>, reason: Option<String>) -> Result<()> {
+    let code = code.unwrap_or(close_code::NORMAL);
+    if !frame::is_valid_close_code(code) {
+      return Err(TachyonError::new(format!("Invalid close code {}", code)));
+    }
+    let reason = reason.unwrap_or_default();
+    // Control frames carry 125 bytes at most, 2 of them for the code
+    if reason.len() > 123 {
+      return Err(TachyonError::new("Close reason is longer than 123 bytes"));
+    }
+
+    self.start_close(code, reason);
+    Ok(())
+  }
+
+  /// Queue the close frame unless the connection is already closing
+  fn start_close(&self, code: u16, reason: String) {
+    if !self.inner.closed.swap(true, Ordering::SeqCst) {
+      let _ = self.inner.outgoing.send(Outgoing::Close(code, reason));
+    }
+  }
+
+  pub fn is_closed(&self) -> bool {
+    self.inner.closed.load(Ordering::SeqCst)
+  }
+
+  /// Whether both handles belong to the same connection
+  pub fn ptr_eq(&self, other: &WebSocket) -> bool {
+    Arc::ptr_eq(&self.inner, &other.inner)
+  }
+}
+
+#[cfg(feature = "napi")]
+#[napi]
+impl WebSocket {
+  /// Send a text or binary message, false once the connection is closing
+  ///
+  /// Example usage:
+  /// ```javascript
+  /// ws.send(JSON.stringify({ type: 'joined', room: ws.params.room }))
+  /// ```
+  #[napi(js_name = "send", ts_args_type = "data: string | Uint8Array")]
+  pub fn send_data(
+    &self,
+    data: napi::bindgen_prelude::Either<String, napi::bindgen_prelude::Uint8Array>,
+  ) -> bool {
+    match data {
+      napi::bindgen_prelude::Either::A(text) => self.send(text),
+      napi::bindgen_prelude::Either::B(data) => self.send(Bytes::copy_from_slice(&data)),
+    }
+  }
+
+  /// Receive the messages sent with `app.publish` to a topic
+  ///
+  /// Example usage:
+  /// ```javascript
+  /// app.ws('/rooms/:room', {
+  ///   open(ws) {
+  ///     ws.subscribe(`room:${ws.params.room}`)
+  ///   },
+  ///   message(ws, data) {
+  ///     app.publish(`room:${ws.params.room}`, data)
+  ///   },
+  /// })
+  /// ```
+  #[napi(js_name = "subscribe")]
+  pub fn js_subscribe(&self, topic: String) {
+    self.subscribe(&topic);
+  }
+
+  /// Stop receiving the messages of a topic, false when not subscribed
+  #[napi(js_name = "unsubscribe")]
+  pub fn js_unsubscribe(&self, topic: String) -> bool {
+    self.unsubscribe(&topic)
+  }
+
+  #[napi(js_name = "isSubscribed")]
+  pub fn js_is_subscribed(&self, topic: String) -> bool {
+    self.is_subscribed(&topic)
+  }
+
+  /// Topics the connection is subscribed to
+  #[napi(getter, js_name = "topics")]
+  pub fn js_topics(&self) -> Vec<String> {
+    self.topics()
+  }
+
+  /// Close the connection, with 1000 unless another code is given
+  #[napi(js_name = "close")]
+  pub fn close_with(&self, code: Option<u16>, reason: Option<String>) -> napi::Result<()> {
+    Ok(self.close(code, reason)?)
+  }
+
+  #[napi(getter, js_name = "params")]
+  pub fn js_params(&self) -> HashMap<String, String> {
+    self.params().clone()
+  }
+
+  #[napi(getter, js_name = "query")]
+  pub fn js_query(&self) -> HashMap<String, String> {
+    self.query().clone()
+  }
+
+  /// Same object as `req.locals` of the upgrade request
+  #[napi(getter, js_name = "locals", ts_return_type = "Record<string, any>")]
+  pub fn js_locals<'env>(
+    &self,
+    env: &'env napi::Env,
+  ) -> napi::Result<napi::bindgen_prelude::Object<'env>> {
+    self.inner.locals.object(env)
+  }
+
+  /// Whether `close` was called or the connection is gone
+  #[napi(getter, js_name = "closed")]
+  pub fn js_closed(&self) -> bool {
+    self.is_closed()
+  }
+}
+
+/// Handshake of a request asking for a WebSocket, taken by the route handler
+#[derive(Clone)]
+pub(crate) struct PendingUpgrade(Arc<Mutex<Option<Upgrade>>>);
+
+struct Upgrade {
+  on_upgrade: OnUpgrade,
+  key: HeaderValue,
+  query: HashMap<String, String>,
+}
+
+impl PendingUpgrade {
+  pub fn from_request<B>(req: &mut Request<B>) -> Option<Self> {
+    if !handshake::is_upgrade(req.method(), req.headers()) {
+      return None;
+    }
+    let key = req.headers().get(header::SEC_WEBSOCKET_KEY)?.clone();
+    let query = req.uri().query().map(parse_query).unwrap_or_default();
+    let upgrade = Upgrade {
+      on_upgrade: hyper::upgrade::on(req),
+      key,
+      query,
+    };
+    Some(Self(Arc::new(Mutex::new(Some(upgrade)))))
+  }
+
+  fn take(&self) -> Option<Upgrade> {
+    self.0.lock().ok()?.take()
+  }
+}
+
+/// Route handler answering the handshake and serving the connection
+pub(crate) struct WebSocketRoute {
+  handler: Arc<dyn WebSocketHandler>,
+  options: WebSocketOptions,
+  topics: Arc<Topics>,
+}
+
+impl WebSocketRoute {
+  pub fn new(
+    handler: Arc<dyn WebSocketHandler>,
+    options: WebSocketOptions,
+    topics: Arc<Topics>,
+  ) -> Self {
+    Self {
+      handler,
+      options,
+      topics,
+    }
+  }
+}
+
+#[async_trait]
+impl TachyonHandler for WebSocketRoute {
+  async fn call(&self, req: TachyonRequest, res: TachyonResponse) {
+    let Some(upgrade) = req
+      .locals
+      .get::<PendingUpgrade>()
+      .and_then(|pending| pending.take())
+    else {
+      res.fail(HttpError::upgrade_required());
+      return;
+    };
+
+    let accept = handshake::accept_key(upgrade.key.as_bytes());
+    res.status(101);
+    for (name, value) in [
+      ("upgrade", "websocket"),
+      ("connection", "upgrade"),
+      ("sec-websocket-accept", accept.as_str()),
+    ] {
+      let _ = res.set_header(name, value);
+    }
+
+    // hyper hands the connection over once the 101 response is written
+    let (outgoing, receiver) = mpsc::unbounded_channel();
+    let ws = WebSocket::new(&req, upgrade.query, outgoing, Arc::clone(&self.topics));
+    let handler = Arc::clone(&self.handler);
+    let options = self.options.clone();
+    tokio::spawn(async move {
+      match upgrade.on_upgrade.await {
+        Ok(upgraded) => {
+          let connection = Connection::new(TokioIo::new(upgraded), ws, receiver, options);
+          connection.run(handler.as_ref()).await;
+        }
+        Err(err) => eprintln!("WebSocket upgrade failed: {}", err),
+      }
+    });
+  }
+}
+
+/// One WebSocket connection, reading frames and writing queued messages
+struct Connection<S> {
+  io: S,
+  buf: BytesMut,
+  ws: WebSocket,
+  outgoing: mpsc::UnboundedReceiver<Outgoing>,
+  options: WebSocketOptions,
+  /// Fragmented message being received
+  message: Option<(OpCode, BytesMut)>,
+  /// Close frame sent by the server, with the time to give up on the reply
+  closing: Option<(Instant, u16, String)>,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> Connection<S> {
+  fn new(
+    io: S,
+    ws: WebSocket,
+    outgoing: mpsc::UnboundedReceiver<Outgoing>,
+    options: WebSocketOptions,
+  ) -> Self {
+    Self {
+      io,
+      buf: BytesMut::with_capacity(8 * 1024),
+      ws,
+      outgoing,
+      options,
+      message: None,
+      closing: None,
+    }
+  }
+
+  async fn run(mut self, handler: &dyn WebSocketHandler) {
+    handler.open(self.ws.clone()).await;
+    let (code, reason) = self.serve(handler).await;
+
+    self.ws.inner.closed.store(true, Ordering::SeqCst);
+    self.ws.unsubscribe_all();
+    self.outgoing.close();
+    let _ = self.io.shutdown().await;
+    handler.close(self.ws.clone(), code, reason).await;
+    #[cfg(feature = "napi")]
+    self.ws.release_object();
+  }
+
+  /// Exchange frames until the connection ends, returning its close code and reason
+  async fn serve(&mut self, handler: &dyn WebSocketHandler) -> (u16, String) {
+    let mut ping = self
+      .options
+      .ping_interval()
+      .map(|every| time::interval_at(Instant::now() + every, every));
+    let mut awaiting_pong = false;
+
+    loop {
+      let close_deadline = self.closing.as_ref().map(|(deadline, ..)| *deadline);
+      tokio::select! {
+        read = self.io.read_buf(&mut self.buf) => {
+          if !matches!(read, Ok(len) if len > 0) {
+            return self.abnormal_close();
+          }
+          // Any frame proves the client is still there
+          awaiting_pong = false;
+          loop {
+            let frame = match frame::parse(&mut self.buf, self.options.max_message_size()) {
+              Ok(Some(frame)) => frame,
+              Ok(None) => break,
+              Err(code) => return self.fail(code).await,
+            };
+            if let Some(closed) = self.handle(frame, handler).await {
+              return closed;
+            }
+          }
+        }
+        Some(outgoing) = self.outgoing.recv() => {
+          let written = match outgoing {
+            Outgoing::Frame(frame) => {
+              self.ws.inner.buffered.fetch_sub(frame.len(), Ordering::SeqCst);
+              self.write_frame(&frame).await
+            }
+            Outgoing::Close(code, reason) => {
+              let written = self.write(OpCode::Close, &frame::close_payload(code, &reason)).await;
+              self.closing = Some((Instant::now() + CLOSE_TIMEOUT, code, reason));
+              written
+            }
+          };
+          if !written {
+            return self.abnormal_close();
+          }
+        }
+        _ = tick(&mut ping) => {
+          if awaiting_pong {
+            return (close_code::ABNORMAL, "Ping timeout".to_string());
+          }
+          awaiting_pong = true;
+          if !self.write(OpCode::Ping, b"").await {
+            return self.abnormal_close();
+          }
+        }
+        _ = sleep_until(close_deadline) => {
+          return self.abnormal_close();
+        }
+      }
+    }
+  }
+
+  /// React to a frame, returning the close code and reason once the connection closed
+  async fn handle(
+    &mut self,
+    frame: Frame,
+    handler: &dyn WebSocketHandler,
+  ) -> Option<(u16, String)> {
+    let max_message_size = self.options.max_message_size();
+    let (opcode, payload) = match frame.opcode {
+      OpCode::Ping => {
+        self.write(OpCode::Pong, &frame.payload).await;
+        return None;
+      }
+      OpCode::Pong => return None,
+      OpCode::Close => {
+        let (code, reason) = match frame::parse_close(&frame.payload) {
+          Ok((code, _)) if code != close_code::NO_STATUS && !frame::is_valid_close_code(code) => {
+            return Some(self.fail(close_code::PROTOCOL_ERROR).await);
+          }
+          Ok(close) => close,
+          Err(code) => return Some(self.fail(code).await),
+        };
+        // Reply to the client, or report the code we closed with
+        if let Some((_, code, reason)) = self.closing.take() {
+          return Some((code, reason));
+        }
+        let reply = match code {
+          close_code::NO_STATUS => Vec::new(),
+          code => frame::close_payload(code, ""),
+        };
+        self.write(OpCode::Close, &reply).await;
+        return Some((code, reason));
+      }
+      OpCode::Text | OpCode::Binary if self.message.is_some() => {
+        return Some(self.fail(close_code::PROTOCOL_ERROR).await);
+      }
+      OpCode::Text | OpCode::Binary if !frame.fin => {
+        self.message = Some((frame.opcode, BytesMut::from(&frame.payload[..])));
+        return None;
+      }
+      OpCode::Text | OpCode::Binary => (frame.opcode, frame.payload),
+      OpCode::Continuation => {
+        let Some((opcode, mut data)) = self.message.take() else {
+          return Some(self.fail(close_code::PROTOCOL_ERROR).await);
+        };
+        if data.len() + frame.payload.len() > max_message_size {
+          return Some(self.fail(close_code::TOO_BIG).await);
+        }
+        data.extend_from_slice(&frame.payload);
+        if !frame.fin {
+          self.message = Some((opcode, data));
+          return None;
+        }
+        (opcode, data.freeze())
+      }
+    };
+
+    let message = match opcode {
+      OpCode::Text => match String::from_utf8(payload.to_vec()) {
+        Ok(text) => WebSocketMessage::Text(text),
+        Err(_) => return Some(self.fail(close_code::INVALID_DATA).await),
+      },
+      _ => WebSocketMessage::Binary(payload),
+    };
+    handler.message(self.ws.clone(), message).await;
+    None
+  }
+
+  /// Close the connection because of the client, with `code` telling why
+  async fn fail(&mut self, code: u16) -> (u16, String) {
+    if self.closing.is_none() {
+      self
+        .write(OpCode::Close, &frame::close_payload(code, ""))
+        .await;
+    }
+    (code, String::new())
+  }
+
+  /// Code and reason of a connection that ended without the close handshake
+  fn abnormal_close(&mut self) -> (u16, String) {
+    match self.closing.take() {
+      Some((_, code, reason)) => (code, reason),
+      None => (close_code::ABNORMAL, String::new()),
+    }
+  }
+
+  /// Write a frame, false once the connection is broken
+  async fn write(&mut self, opcode: OpCode, payload: &[u8]) -> bool {
+    self.write_frame(&frame::encode(opcode, payload)).await
+  }
+
+  async fn write_frame(&mut self, frame: &[u8]) -> bool {
+    self.io.write_all(frame).await.is_ok() && self.io.flush().await.is_ok()
+  }
+}
+
+async fn tick(interval: &mut Option<Interval>) {
+  match interval {
+    Some(interval) => {
+      interval.tick().await;
+    }
+    None => std::future::pending().await,
+  }
+}
+
+async fn sleep_until(deadline: Option<Instant>) {
+  match deadline {
+    Some(deadline) => time::sleep_until(deadline).await,
+    None => std::future::pending().await,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::testing::{call, connect, get, serve};
+  use crate::{Tachyon, WebSocketOptions};
+  use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+  #[test]
+  fn test_slow_client_closed() {
+    let (outgoing, mut receiver) = mpsc::unbounded_channel();
+    let ws = WebSocket::new(
+      &TachyonRequest::default(),
+      HashMap::new(),
+      outgoing,
+      Arc::new(Topics::default()),
+    );
+
+    // Nothing reads the queue, as with a client that stopped reading
+    let message = WebSocketMessage::Binary(Bytes::from(vec![0; 64 * 1024]));
+    let mut sent = 0;
+    while ws.send(message.clone()) {
+      sent += 1;
+    }
+    assert!(ws.is_closed());
+    assert!(!ws.send("late"));
+
+    let mut frames = 0;
+    let mut close = None;
+    while let Ok(outgoing) = receiver.try_recv() {
+      match outgoing {
+        Outgoing::Frame(_) => frames += 1,
+        Outgoing::Close(code, reason) => close = Some((code, reason)),
+      }
+    }
+    assert_eq!(frames, sent);
+    assert!(frames * 64 * 1024 <= MAX_BUFFERED + 64 * 1024);
+    assert_eq!(
+      close,
+      Some((
+        close_code::TRY_AGAIN_LATER,
+        "Client is too slow".to_string()
+      ))
+    );
+  }
+
+  #[tokio::test]
+  async fn test_websocket_echo() {
+    struct Echo(mpsc::UnboundedSender<(u16, String)>);
+
+    #[async_trait]
+    impl WebSocketHandler for Echo {
+      async fn open(&self, ws: WebSocket) {
+        ws.subscribe(&format!("room:{}", ws.params()["room"]));
+      }
+
+      async fn message(&self, ws: WebSocket, message: WebSocketMessage) {
+        if let WebSocketMessage::Text(text) = message {
+          ws.send(format!("{} {}", ws.params()["room"], text));
+        }
+      }
+
+      async fn close(&self, _ws: WebSocket, code: u16, reason: String) {
+        let _ = self.0.send((code, reason));
+      }
+    }
+
+    let app = Tachyon::new();
+    let (closed, mut close) = mpsc::unbounded_channel();
+    app
+      .websocket("/chat/:room", Echo(closed), WebSocketOptions::default())
+      .unwrap();
+
+    let res = call(&mut app.service(), get("/chat/lobby")).await;
+    assert_eq!(res.status, hyper::StatusCode::UPGRADE_REQUIRED);
+
+    let publisher = app.clone();
+    let mut stream = connect(serve(app)).await;
+    stream
+      .write_all(
+        b"GET /chat/lobby HTTP/1.1\r\nHost: localhost\r\nUpgrade: websocket\r\n\
+          Connection: Upgrade\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\
+          Sec-WebSocket-Version: 13\r\n\r\n",
+      )
+      .await
+      .unwrap();
+    let mut head = Vec::new();
+    while !head.ends_with(b"\r\n\r\n") {
+      head.push(stream.read_u8().await.unwrap());
+    }
+    let head = String::from_utf8(head).unwrap();
+    assert!(head.starts_with("HTTP/1.1 101 Switching Protocols"));
+    assert!(head.contains("sec-websocket-accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo="));
+
+    // Masked text frame "hi", then a close frame with code 1000
+    stream
+      .write_all(&[0x81, 0x82, 1, 2, 3, 4, b'h' ^ 1, b'i' ^ 2])
+      .await
+      .unwrap();
+    let mut reply = [0; 10];
+    stream.read_exact(&mut reply).await.unwrap();
+    assert_eq!(&reply, b"\x81\x08lobby hi");
+
+    assert_eq!(publisher.publish("room:lobby", "news"), 1);
+    assert_eq!(publisher.publish("room:other", "news"), 0);
+    let mut published = [0; 6];
+    stream.read_exact(&mut published).await.unwrap();
+    assert_eq!(&published, b"\x81\x04news");
+
+    stream
+      .write_all(&[0x88, 0x82, 0, 0, 0, 0, 0x03, 0xE8])
+      .await
+      .unwrap();
+    let mut reply = [0; 4];
+    stream.read_exact(&mut reply).await.unwrap();
+    assert_eq!(reply, [0x88, 0x02, 0x03, 0xE8]);
+    assert_eq!(close.recv().await, Some((1000, String::new())));
+    // Closed connections leave their topics
+    assert_eq!(publisher.publish("room:lobby", "news"), 0);
+  }
+}
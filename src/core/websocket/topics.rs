@@ -0,0 +1,48 @@
+use dashmap::DashMap;
+use std::collections::HashMap;
+
+use super::{WebSocket, WebSocketMessage};
+
+/// Connections subscribed to each topic of an app
+#[derive(Default)]
+pub(crate) struct Topics {
+  subscribers: DashMap<String, HashMap<u64, WebSocket>>,
+}
+
+impl Topics {
+  /// Add `ws` to `topic`, false when it was already there
+  pub fn subscribe(&self, topic: &str, ws: &WebSocket) -> bool {
+    self
+      .subscribers
+      .entry(topic.to_string())
+      .or_default()
+      .insert(ws.id(), ws.clone())
+      .is_none()
+  }
+
+  /// Remove `ws` from `topic`, dropping the topic once nobody listens
+  pub fn unsubscribe(&self, topic: &str, ws: &WebSocket) -> bool {
+    let removed = self
+      .subscribers
+      .get_mut(topic)
+      .is_some_and(|mut subscribers| subscribers.remove(&ws.id()).is_some());
+    self
+      .subscribers
+      .remove_if(topic, |_, subscribers| subscribers.is_empty());
+    removed
+  }
+
+  /// Queue `message` for every subscriber of `topic`, returning how many got it
+  ///
+  /// The frame is encoded once and shared by all of them.
+  pub fn publish(&self, topic: &str, message: &WebSocketMessage) -> usize {
+    let Some(subscribers) = self.subscribers.get(topic) else {
+      return 0;
+    };
+    let frame = message.encode();
+    subscribers
+      .values()
+      .filter(|ws| ws.send_frame(frame.clone()))
+      .count()
+  }
+}
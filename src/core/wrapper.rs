@@ -1,18 +1,148 @@
 use async_trait::async_trait;
-use napi::{bindgen_prelude::FnArgs, threadsafe_function::ThreadsafeFunctionCallMode};
-use std::{sync::Arc, time::Duration};
-use tokio::time::{timeout, Instant};
+use napi::{
+  bindgen_prelude::{
+    Either, Either3, FnArgs, FromNapiValue, Function, JsValuesTupleIntoVec, Promise,
+  },
+  sys,
+  threadsafe_function::UnknownReturnValue,
+  Error, Result,
+};
+use std::sync::Arc;
 
-use crate::{core::router::TachyonHandler, TachyonRequest, TachyonResponse};
+use crate::{
+  core::{
+    options::RouteOptions,
+    router::{TachyonErrorHandler, TachyonHandler},
+  },
+  error::HttpError,
+  TachyonRequest, TachyonResponse,
+};
 
-type TachyonThreadsafeFunction = napi::threadsafe_function::ThreadsafeFunction<
-  FnArgs<(TachyonRequest, TachyonResponse)>,
-  (),
-  FnArgs<(TachyonRequest, TachyonResponse)>,
+/// What a JS handler returns: a Promise for async handlers, anything else for sync ones
+pub enum HandlerReturn {
+  Async(Promise<UnknownReturnValue>),
+  Sync,
+}
+
+impl FromNapiValue for HandlerReturn {
+  unsafe fn from_napi_value(env: sys::napi_env, napi_val: sys::napi_value) -> Result<Self> {
+    let mut is_promise = false;
+    napi::check_status!(unsafe { sys::napi_is_promise(env, napi_val, &mut is_promise) })?;
+
+    if is_promise {
+      let promise = unsafe { Promise::from_napi_value(env, napi_val)? };
+      Ok(HandlerReturn::Async(promise))
+    } else {
+      Ok(HandlerReturn::Sync)
+    }
+  }
+}
+
+/// JS route handler or middleware as received from `app.get(...)` and friends
+pub type TachyonCallback =
+  Function<'static, FnArgs<(TachyonRequest, TachyonResponse)>, HandlerReturn>;
+
+/// One handler or an ordered list of them (middleware first, route handler last)
+pub type TachyonCallbacks = Either<TachyonCallback, Vec<TachyonCallback>>;
+
+/// Argument after the path of `app.get(...)` and friends: a handler, a list of
+/// handlers or, as the last one, the route options
+pub type RouteArg = Either3<TachyonCallback, Vec<TachyonCallback>, RouteOptions>;
+
+/// Split the arguments after the path of a route into its handlers, in order,
+/// and its options. Missing arguments are skipped
+pub fn route_args(args: Vec<Option<RouteArg>>) -> Result<(TachyonCallbacks, Option<RouteOptions>)> {
+  let mut args: Vec<RouteArg> = args.into_iter().flatten().collect();
+  let options = match args.pop() {
+    Some(Either3::C(options)) => Some(options),
+    Some(arg) => {
+      args.push(arg);
+      None
+    }
+    None => None,
+  };
+
+  let mut callbacks = Vec::with_capacity(args.len());
+  for arg in args {
+    match arg {
+      Either3::A(callback) => callbacks.push(callback),
+      Either3::B(list) => callbacks.extend(list),
+      Either3::C(_) => {
+        return Err(Error::from_reason(
+          "Route options must be the last argument",
+        ))
+      }
+    }
+  }
+
+  Ok((Either::B(callbacks), options))
+}
+
+/// JS error handler as received from `app.onError(...)`
+pub type TachyonErrorCallback =
+  Function<'static, FnArgs<(HttpError, TachyonRequest, TachyonResponse)>, HandlerReturn>;
+
+type JsThreadsafeFunction<Args> = napi::threadsafe_function::ThreadsafeFunction<
+  FnArgs<Args>,
+  HandlerReturn,
+  FnArgs<Args>,
   napi::Status,
   false,
 >;
 
+type TachyonThreadsafeFunction = JsThreadsafeFunction<(TachyonRequest, TachyonResponse)>;
+
+/// Build one threadsafe function per JS callback, keeping their order
+pub fn into_handlers(callbacks: TachyonCallbacks) -> Result<Vec<Arc<dyn TachyonHandler>>> {
+  let callbacks = match callbacks {
+    Either::A(callback) => vec![callback],
+    Either::B(callbacks) => callbacks,
+  };
+
+  // Build threadsafe functions directly
+  // This works for both sync and async JavaScript functions
+  let mut handlers: Vec<Arc<dyn TachyonHandler>> = Vec::with_capacity(callbacks.len());
+  for callback in callbacks {
+    let handler = callback
+      .build_threadsafe_function()
+      .weak::<false>()
+      .build()?;
+    handlers.push(Arc::new(ThreadsafeFunctionWrapper::new(handler)));
+  }
+
+  Ok(handlers)
+}
+
+pub fn into_error_handler(callback: TachyonErrorCallback) -> Result<Arc<dyn TachyonErrorHandler>> {
+  let tsfn = callback
+    .build_threadsafe_function()
+    .weak::<false>()
+    .build()?;
+  Ok(Arc::new(ErrorHandlerWrapper {
+    tsfn: Arc::new(tsfn),
+  }))
+}
+
+/// Call a JS callback and wait until it settled
+///
+/// The callback is settled once the JS call returned and, for async callbacks,
+/// once the returned Promise resolved or rejected.
+async fn settle<Args>(
+  tsfn: Arc<JsThreadsafeFunction<Args>>,
+  args: FnArgs<Args>,
+) -> std::result::Result<(), HttpError>
+where
+  Args: Send + 'static,
+  FnArgs<Args>: JsValuesTupleIntoVec,
+{
+  let settled = match tsfn.call_async_catch(args).await {
+    Ok(HandlerReturn::Async(promise)) => promise.await.map(|_| ()),
+    Ok(HandlerReturn::Sync) => Ok(()),
+    Err(err) => Err(err),
+  };
+  settled.map_err(|err| HttpError::handler_error(err.reason))
+}
+
 pub struct ThreadsafeFunctionWrapper {
   tsfn: Arc<TachyonThreadsafeFunction>,
 }
@@ -28,61 +158,27 @@ impl ThreadsafeFunctionWrapper {
 #[async_trait]
 impl TachyonHandler for ThreadsafeFunctionWrapper {
   async fn call(&self, req: TachyonRequest, res: TachyonResponse) {
-    // clone response para manter a original visível ao echo
-    let res_for_js = res.clone(); // this will be passed to JS in the args
-    let res_for_poll = res_for_js.clone(); // this will be used for polling after call
-
-    // build args using the clone intended for JS
-    let args = (req, res_for_js).into();
-    let tsfn = Arc::clone(&self.tsfn);
-
-    // timeout global (adjust if needed)
-    let global_timeout = Duration::from_millis(100);
-    let start = Instant::now();
-
-    // spawn_blocking: call JS (Blocking) then poll res_for_poll in this same thread
-    let blocking_handle = tokio::task::spawn_blocking(move || {
-      // call the JS handler (blocks until the callback is scheduled/executed)
-      let call_res = tsfn.call(args, ThreadsafeFunctionCallMode::Blocking);
-
-      let mut delay_micros = 10u64;
-      let max_delay_micros = 2_000u64;
-      loop {
-        if res_for_poll.get_data().is_some() {
-          break;
-        }
-        if start.elapsed() >= global_timeout {
-          break;
-        }
-        
-        if delay_micros <= 200 {
-          std::thread::sleep(Duration::from_micros(delay_micros));
-        } else {
-          std::thread::sleep(Duration::from_millis(delay_micros / 1000));
-        }
-        delay_micros = (delay_micros.saturating_mul(2)).min(max_delay_micros);
-      }
-      call_res
-    });
-
-    match timeout(global_timeout, blocking_handle).await {
-      Ok(join_result) => match join_result {
-        Ok(call_result) => match call_result {
-          napi::Status::Ok => {}
-          other_status => {
-            eprintln!("Error calling JS handler via tsfn: {:?}", other_status);
-          }
-        },
-        Err(join_err) => {
-          eprintln!(
-            "spawn_blocking join error when calling tsfn: {:?}",
-            join_err
-          );
-        }
-      },
-      Err(_) => {
-        eprintln!("Timeout waiting for JS handler to complete (100ms)");
-      }
+    let args = (req, res.clone()).into();
+    if let Err(err) = settle(Arc::clone(&self.tsfn), args).await {
+      res.fail(err);
+    }
+  }
+
+  fn warms_up(&self) -> bool {
+    true
+  }
+}
+
+struct ErrorHandlerWrapper {
+  tsfn: Arc<JsThreadsafeFunction<(HttpError, TachyonRequest, TachyonResponse)>>,
+}
+
+#[async_trait]
+impl TachyonErrorHandler for ErrorHandlerWrapper {
+  async fn call(&self, err: HttpError, req: TachyonRequest, res: TachyonResponse) {
+    // The error handler failing as well leaves the default error response
+    if let Err(err) = settle(Arc::clone(&self.tsfn), (err, req, res).into()).await {
+      eprintln!("Error calling JS error handler: {}", err);
     }
   }
 }
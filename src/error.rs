@@ -0,0 +1,174 @@
+#[cfg(feature = "napi")]
+use napi_derive::napi;
+use std::fmt;
+
+pub type Result<T> = std::result::Result<T, TachyonError>;
+
+/// Error returned by the routing and server APIs
+///
+/// Converted into a JS exception when the `napi` feature is enabled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TachyonError {
+  reason: String,
+  kind: ErrorKind,
+}
+
+/// What a `TachyonError` is blamed on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+  /// Rejected input, such as an invalid pattern or a conflicting route
+  InvalidArg,
+  /// Anything that went wrong while running, such as a port already in use
+  Failure,
+}
+
+impl TachyonError {
+  /// Error caused by an invalid argument
+  pub fn new(reason: impl Into<String>) -> Self {
+    Self {
+      reason: reason.into(),
+      kind: ErrorKind::InvalidArg,
+    }
+  }
+
+  /// Error raised at runtime, unrelated to the arguments
+  pub fn failure(reason: impl Into<String>) -> Self {
+    Self {
+      reason: reason.into(),
+      kind: ErrorKind::Failure,
+    }
+  }
+
+  pub fn reason(&self) -> &str {
+    &self.reason
+  }
+
+  pub fn kind(&self) -> ErrorKind {
+    self.kind
+  }
+}
+
+impl fmt::Display for TachyonError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(&self.reason)
+  }
+}
+
+impl std::error::Error for TachyonError {}
+
+impl From<std::io::Error> for TachyonError {
+  fn from(err: std::io::Error) -> Self {
+    Self::failure(err.to_string())
+  }
+}
+
+/// Error produced while serving a request, rendered by `onError`
+///
+/// Covers what the framework detects itself (unknown route, bad payload...)
+/// as well as handlers that throw, reject or time out.
+#[cfg_attr(feature = "napi", napi(object))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpError {
+  pub status: u16,
+  /// Stable identifier such as `NOT_FOUND` or `HANDLER_ERROR`
+  pub code: String,
+  pub message: String,
+}
+
+impl HttpError {
+  pub fn new(status: u16, code: &str, message: impl Into<String>) -> Self {
+    Self {
+      status,
+      code: code.to_string(),
+      message: message.into(),
+    }
+  }
+
+  pub fn not_found() -> Self {
+    Self::new(404, "NOT_FOUND", "Not Found")
+  }
+
+  pub fn forbidden() -> Self {
+    Self::new(403, "FORBIDDEN", "Forbidden")
+  }
+
+  pub fn method_not_allowed() -> Self {
+    Self::new(405, "METHOD_NOT_ALLOWED", "Method Not Allowed")
+  }
+
+  pub fn bad_request(message: impl Into<String>) -> Self {
+    Self::new(400, "BAD_REQUEST", message)
+  }
+
+  pub fn invalid_json() -> Self {
+    Self::new(400, "INVALID_JSON", "Invalid JSON")
+  }
+
+  pub fn payload_too_large() -> Self {
+    Self::new(413, "PAYLOAD_TOO_LARGE", "Payload Too Large")
+  }
+
+  pub fn upgrade_required() -> Self {
+    Self::new(426, "UPGRADE_REQUIRED", "Upgrade Required")
+  }
+
+  pub fn handler_timeout() -> Self {
+    Self::new(503, "HANDLER_TIMEOUT", "Handler timed out")
+  }
+
+  pub fn handler_error(message: impl Into<String>) -> Self {
+    Self::new(500, "HANDLER_ERROR", message)
+  }
+}
+
+impl fmt::Display for HttpError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{} {}: {}", self.status, self.code, self.message)
+  }
+}
+
+impl std::error::Error for HttpError {}
+
+#[cfg(feature = "napi")]
+impl From<TachyonError> for napi::Error {
+  fn from(err: TachyonError) -> Self {
+    let status = match err.kind {
+      ErrorKind::InvalidArg => napi::Status::InvalidArg,
+      ErrorKind::Failure => napi::Status::GenericFailure,
+    };
+    napi::Error::new(status, err.reason)
+  }
+}
+
+#[cfg(feature = "napi")]
+impl From<napi::Error> for TachyonError {
+  fn from(err: napi::Error) -> Self {
+    match err.status {
+      napi::Status::InvalidArg => Self::new(err.reason),
+      _ => Self::failure(err.reason),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_error_kind() {
+    assert_eq!(
+      TachyonError::new("Invalid route").kind(),
+      ErrorKind::InvalidArg
+    );
+    let io = std::io::Error::new(std::io::ErrorKind::AddrInUse, "Address in use");
+    assert_eq!(TachyonError::from(io).kind(), ErrorKind::Failure);
+
+    #[cfg(feature = "napi")]
+    {
+      let err: napi::Error = TachyonError::failure("Address in use").into();
+      assert_eq!(err.status, napi::Status::GenericFailure);
+      let err: napi::Error = TachyonError::new("Invalid route").into();
+      assert_eq!(err.status, napi::Status::InvalidArg);
+    }
+  }
+}
@@ -1,8 +1,29 @@
 mod core;
+mod error;
+#[cfg(feature = "napi")]
 pub mod server;
+#[cfg(test)]
+mod testing;
 mod utils;
 
+pub use core::group::Router;
+pub use core::hooks::{HookName, Hooks};
+pub use core::locals::Locals;
+pub use core::methods::Method;
+pub use core::options::{
+  ConflictPolicy, Dotfiles, RouteOptions, SendFileOptions, SseOptions, StaticOptions,
+  TachyonOptions, WebSocketOptions,
+};
 pub use core::request::TachyonRequest;
 pub use core::response::TachyonResponse;
+pub use core::router::{
+  HTTPCall, HandlerChain, RouteInfo, TachyonErrorHandler, TachyonHandler, TachyonRouter,
+};
+pub use core::service::{LayeredBody, LayeredService, TachyonService};
+pub use core::signal::{AbortReason, AbortSignal};
+pub use core::sse::{EventStream, SseEvent};
 pub use core::tachyon::Tachyon;
-pub use server::tachyon;
+pub use core::websocket::{WebSocket, WebSocketHandler, WebSocketMessage};
+pub use error::{ErrorKind, HttpError, Result, TachyonError};
+#[cfg(feature = "napi")]
+pub use server::{router, tachyon};
@@ -1,9 +1,14 @@
 use napi_derive::napi;
 
-use crate::core::tachyon::Tachyon;
+use crate::core::{group::Router, options::TachyonOptions, tachyon::Tachyon};
 
 #[napi]
-pub fn tachyon() -> napi::Result<Tachyon> {
-  let server = Tachyon::new();
+pub fn tachyon(options: Option<TachyonOptions>) -> napi::Result<Tachyon> {
+  let server = Tachyon::with_options(options.unwrap_or_default());
   Ok(server)
 }
+
+#[napi]
+pub fn router() -> napi::Result<Router> {
+  Ok(Router::new())
+}
@@ -0,0 +1,121 @@
+//! Helpers shared by the tests of the crate
+
+use bytes::Bytes;
+use http_body_util::{BodyExt, Empty};
+use hyper::{HeaderMap, Request, StatusCode};
+use std::{
+  fmt::Debug,
+  path::{Path, PathBuf},
+  sync::atomic::{AtomicUsize, Ordering},
+  time::Duration,
+};
+use tokio::{
+  io::{AsyncReadExt, AsyncWriteExt},
+  net::TcpStream,
+};
+use tower::Service;
+
+use crate::{Tachyon, TachyonService};
+
+/// GET request without a body
+pub fn get(uri: &str) -> Request<Empty<Bytes>> {
+  Request::get(uri).body(Empty::new()).unwrap()
+}
+
+/// Response with its body read to the end
+pub struct TestResponse {
+  pub status: StatusCode,
+  pub headers: HeaderMap,
+  pub body: Bytes,
+}
+
+impl TestResponse {
+  pub fn text(&self) -> &str {
+    std::str::from_utf8(&self.body).unwrap()
+  }
+}
+
+/// Answer `req` with `service`, reading the whole body
+pub async fn call<B>(service: &mut TachyonService, req: Request<B>) -> TestResponse
+where
+  B: hyper::body::Body<Data = Bytes> + Send + 'static,
+  B::Error: Send + Debug,
+{
+  let res = service.call(req).await.unwrap();
+  let status = res.status();
+  let headers = res.headers().clone();
+  let body = res.into_body().collect().await.unwrap().to_bytes();
+  TestResponse {
+    status,
+    headers,
+    body,
+  }
+}
+
+/// Start `app` on a free port
+pub fn serve(app: Tachyon) -> u16 {
+  let port = std::net::TcpListener::bind("127.0.0.1:0")
+    .unwrap()
+    .local_addr()
+    .unwrap()
+    .port();
+  tokio::spawn(async move { app.serve(port).await });
+  port
+}
+
+/// Connect to a server started by [`serve`], once it listens
+pub async fn connect(port: u16) -> TcpStream {
+  loop {
+    match TcpStream::connect(("127.0.0.1", port)).await {
+      Ok(stream) => return stream,
+      Err(_) => tokio::time::sleep(Duration::from_millis(10)).await,
+    }
+  }
+}
+
+/// GET `uri` over a new connection, returning the raw response
+pub async fn fetch(port: u16, uri: &str) -> Vec<u8> {
+  let mut stream = connect(port).await;
+  let request = format!("GET {uri} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n");
+  stream.write_all(request.as_bytes()).await.unwrap();
+  let mut raw = Vec::new();
+  stream.read_to_end(&mut raw).await.unwrap();
+  raw
+}
+
+/// Directory removed once dropped, even when the test panics
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+  pub fn new(name: &str) -> Self {
+    static CREATED: AtomicUsize = AtomicUsize::new(0);
+    let path = std::env::temp_dir().join(format!(
+      "tachyon-{}-{}-{}",
+      name,
+      std::process::id(),
+      CREATED.fetch_add(1, Ordering::SeqCst)
+    ));
+    std::fs::create_dir_all(&path).unwrap();
+    Self(path)
+  }
+
+  pub fn path(&self) -> &Path {
+    &self.0
+  }
+
+  /// Write `contents` to `file` inside the directory
+  pub fn write(&self, file: &str, contents: impl AsRef<[u8]>) -> PathBuf {
+    let path = self.0.join(file);
+    if let Some(parent) = path.parent() {
+      std::fs::create_dir_all(parent).unwrap();
+    }
+    std::fs::write(&path, contents).unwrap();
+    path
+  }
+}
+
+impl Drop for TempDir {
+  fn drop(&mut self) {
+    let _ = std::fs::remove_dir_all(&self.0);
+  }
+}
@@ -3,148 +3,136 @@ use std::sync::Arc;
 use bytes::Bytes;
 use dashmap::DashMap;
 use http_body_util::{combinators::BoxBody, BodyExt, Empty, Full};
+use tower::BoxError;
 
-use crate::{core::router::TachyonRouter, TachyonRequest, TachyonResponse};
+use crate::{
+  core::{router::TachyonRouter, signal::AbortReason},
+  TachyonRequest, TachyonResponse,
+};
 
 #[inline(always)]
-pub fn full<T: Into<Bytes>>(chunk: T) -> BoxBody<Bytes, hyper::Error> {
+pub fn full<T: Into<Bytes>>(chunk: T) -> BoxBody<Bytes, BoxError> {
   Full::new(chunk.into())
     .map_err(|never| match never {})
     .boxed()
 }
 
 #[inline(always)]
-pub fn empty() -> BoxBody<Bytes, hyper::Error> {
+pub fn empty() -> BoxBody<Bytes, BoxError> {
   Empty::<Bytes>::new()
     .map_err(|never| match never {})
     .boxed()
 }
 
+/// Call the routes with JS handlers once, ahead of the first requests
+///
+/// Native routes, static files and WebSockets included, are left alone. Nobody
+/// reads a warmup response, so its streamed writes fail right away.
 pub fn warmup_routes(routes: &Arc<DashMap<String, TachyonRouter>>) {
-  let req = TachyonRequest::new(serde_json::Value::Null);
-  let res = TachyonResponse::new();
-  
   for entry in routes.iter() {
     let handler = entry.value().handler();
-    let req_clone = req.clone();
-    let res_clone = res.clone();
+    if !handler.warms_up() {
+      continue;
+    }
+    let req = TachyonRequest::new(serde_json::Value::Null);
+    let res = TachyonResponse::new().with_locals(req.locals.clone());
+    res.detach();
 
     tokio::task::spawn_blocking(move || {
-      tokio::runtime::Handle::current().block_on(handler.call(req_clone, res_clone));
+      tokio::runtime::Handle::current().block_on(handler.call(req.clone(), res));
+      // Let handlers clean up what they still run in the background
+      req.signal.abort(AbortReason::Disconnected);
     });
   }
 }
 
-/// Ultra-fast route matching with zero allocations
-/// Optimized for nanosecond-level performance
-#[inline]
-pub fn route_matches(route_pattern: &str, actual_route: &str) -> bool {
-  // Fast path: if lengths are very different, can't match
-  if route_pattern.len().abs_diff(actual_route.len()) > 50 {
-    return false;
-  }
-
-  // Find method separator ':'
-  let Some(pattern_colon) = route_pattern.as_bytes().iter().position(|&b| b == b':') else {
-    return false;
-  };
-
-  let Some(actual_colon) = actual_route.as_bytes().iter().position(|&b| b == b':') else {
-    return false;
-  };
-
-  // Fast method comparison (usually just 1-6 bytes: GET, POST, PUT, DELETE, PATCH)
-  if pattern_colon != actual_colon {
-    return false;
-  }
-
-  // SAFETY: We know the position is valid and contains ':'
-  let pattern_method = unsafe { route_pattern.get_unchecked(..pattern_colon) };
-  let actual_method = unsafe { actual_route.get_unchecked(..actual_colon) };
-
-  if pattern_method != actual_method {
-    return false;
-  }
-
-  // Get paths after method
-  let pattern_path = unsafe { route_pattern.get_unchecked(pattern_colon + 1..) };
-  let actual_path = unsafe { actual_route.get_unchecked(actual_colon + 1..) };
-
-  // Fast path: exact match (no parameters)
-  if pattern_path == actual_path {
-    return true;
-  }
-
-  // Only do parameter matching if pattern contains ':'
-  if !pattern_path.contains(':') {
-    return false;
-  }
-
-  // Zero-allocation segment matching using iterators
-  let mut pattern_segments = pattern_path.split('/');
-  let mut actual_segments = actual_path.split('/');
-
-  loop {
-    match (pattern_segments.next(), actual_segments.next()) {
-      (Some(pattern_seg), Some(actual_seg)) => {
-        // Parameter segment (starts with ':')
-        if pattern_seg.starts_with(':') {
-          // Parameters can't be empty
-          if actual_seg.is_empty() {
-            return false;
-          }
-          continue;
-        }
-
-        // Exact match required
-        if pattern_seg != actual_seg {
-          return false;
-        }
-      }
-      (None, None) => return true, // Both exhausted = match
-      _ => return false,           // Length mismatch
-    }
+/// Join a mount prefix and a route path with exactly one `/` between them
+///
+/// `join_paths("/api/", "/users")` is `/api/users` and the router root
+/// `join_paths("/api", "/")` maps to the prefix itself.
+pub fn join_paths(prefix: &str, path: &str) -> String {
+  let prefix = prefix.trim_end_matches('/');
+  let path = path.trim_start_matches('/');
+
+  match (prefix.is_empty(), path.is_empty()) {
+    (true, true) => "/".to_string(),
+    (true, false) => format!("/{}", path),
+    (false, true) => prefix.to_string(),
+    (false, false) => format!("{}/{}", prefix, path),
   }
 }
 
 #[cfg(test)]
 mod tests {
   use super::*;
+  use crate::core::{methods::Method, router::HTTPCall};
+  use crate::testing::{fetch, serve, TempDir};
+  use crate::{StaticOptions, Tachyon, TachyonHandler};
+  use std::time::Duration;
 
   #[test]
-  fn test_exact_match() {
-    assert!(route_matches("0:/users", "0:/users"));
-    assert!(route_matches("1:/api/posts", "1:/api/posts"));
+  fn test_join_paths() {
+    assert_eq!(join_paths("/api/v1", "/users"), "/api/v1/users");
+    assert_eq!(join_paths("/api/v1/", "users/:id"), "/api/v1/users/:id");
+    assert_eq!(join_paths("/api/v1", "/"), "/api/v1");
+    assert_eq!(join_paths("/", "/users"), "/users");
+    assert_eq!(join_paths("", "/"), "/");
   }
 
-  #[test]
-  fn test_parameter_match() {
-    assert!(route_matches("0:/users/:id", "0:/users/123"));
-    assert!(route_matches(
-      "0:/users/:id/posts/:postId",
-      "0:/users/123/posts/456"
-    ));
-  }
-
-  #[test]
-  fn test_method_mismatch() {
-    assert!(!route_matches("0:/users", "1:/users"));
-  }
+  /// Stands in for a JS callback, the only handlers warmup calls
+  struct Callback(tokio::sync::mpsc::UnboundedSender<bool>);
 
-  #[test]
-  fn test_path_mismatch() {
-    assert!(!route_matches("0:/users", "0:/posts"));
-    assert!(!route_matches("0:/users/:id", "0:/posts/123"));
-  }
+  #[async_trait::async_trait]
+  impl TachyonHandler for Callback {
+    async fn call(&self, _req: TachyonRequest, res: TachyonResponse) {
+      // Far more than a body queues before writes wait for the client
+      let mut written = true;
+      for _ in 0..32 {
+        written &= res.write(vec![b'x'; 64 * 1024]).await;
+      }
+      res.end();
+      let _ = self.0.send(written);
+    }
 
-  #[test]
-  fn test_length_mismatch() {
-    assert!(!route_matches("0:/users/:id", "0:/users/123/extra"));
-    assert!(!route_matches("0:/users/:id/posts", "0:/users/123"));
+    fn warms_up(&self) -> bool {
+      true
+    }
   }
 
-  #[test]
-  fn test_empty_parameter() {
-    assert!(!route_matches("0:/users/:id", "0:/users/"));
+  #[tokio::test]
+  async fn test_warmup_only_calls_js_handlers() {
+    let dir = TempDir::new("warmup");
+    dir.write("index.html", "<h1>Home</h1>");
+
+    let app = Tachyon::new();
+    app
+      .serve_static("/site", dir.path(), StaticOptions::default())
+      .unwrap();
+    let (done, mut streamed) = tokio::sync::mpsc::unbounded_channel();
+    app
+      .register("/export", Method::Get, vec![Arc::new(Callback(done))])
+      .unwrap();
+    let (called, mut calls) = tokio::sync::mpsc::unbounded_channel();
+    app
+      .route(Method::Get, "/native", move |_req, res: TachyonResponse| {
+        let _ = called.send(());
+        async move { res.send(Some("native".into())) }
+      })
+      .unwrap();
+
+    // Nobody reads the warmup response, its writes fail instead of waiting
+    let port = serve(app);
+    let warmed_up = tokio::time::timeout(Duration::from_secs(5), streamed.recv()).await;
+    assert_eq!(warmed_up, Ok(Some(false)));
+
+    let raw = fetch(port, "/export").await;
+    assert!(raw.starts_with(b"HTTP/1.1 200 OK"));
+    assert_eq!(streamed.recv().await, Some(true));
+
+    assert!(fetch(port, "/native").await.ends_with(b"\"native\""));
+    assert_eq!(calls.recv().await, Some(()));
+    assert!(calls.try_recv().is_err());
+
+    assert!(fetch(port, "/site").await.ends_with(b"<h1>Home</h1>"));
   }
 }